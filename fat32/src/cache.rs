@@ -0,0 +1,389 @@
+//! Cache de secteurs avec écriture différée (“write-back”).
+//!
+//! Sans cache, chaque `read_fat_entry` relit un secteur de FAT sur le périphérique :
+//! acceptable pour une image en mémoire, trop lent pour un vrai disque.
+//! [`CachedDevice`] se place entre le système de fichiers et le périphérique et garde
+//! les derniers secteurs utilisés en mémoire, dans deux réserves séparées :
+//! - les secteurs de métadonnées (zone réservée, FAT, racine fixe FAT12/16),
+//! - les secteurs de la zone de données (clusters de fichiers et de répertoires),
+//!
+//! pour qu’une lecture de gros fichier ne chasse pas les secteurs de FAT.
+//! Chaque réserve a sa capacité ([`CacheConfig`]) et évince le secteur utilisé
+//! le moins récemment (LRU).
+//!
+//! Les écritures restent en mémoire (secteurs “sales”) jusqu’à leur éviction,
+//! [`BlockDevice::write_back`] ou [`BlockDevice::flush`]. L’ordre des écritures sur
+//! le support n’est donc garanti qu’à ces points de synchronisation : `Fat32Mut`
+//! appelle `write_back` entre chaque étape d’une modification (données, FAT, entrée,
+//! libération) pour que l’ordre prévu par l’écriture ordonnée soit respecté.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::cell::RefCell;
+
+use crate::device::check_range;
+use crate::{read_bpb, BlockDevice, FatError, SECTOR_SIZE};
+
+/// Capacités du cache, en secteurs de 512 octets.
+///
+/// La mémoire occupée est d’environ `(fat_sectors + data_sectors) * 512` octets.
+/// Une capacité nulle désactive la réserve : les accès vont directement au périphérique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Secteurs de métadonnées gardés en mémoire (zone réservée, FAT, racine fixe).
+    pub fat_sectors: usize,
+    /// Secteurs de la zone de données gardés en mémoire.
+    pub data_sectors: usize,
+}
+
+impl Default for CacheConfig {
+    /// 64 secteurs de FAT et 256 de données : 160 Kio.
+    fn default() -> Self {
+        Self {
+            fat_sectors: 64,
+            data_sectors: 256,
+        }
+    }
+}
+
+/// Compteurs d’activité du cache, en secteurs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Secteurs lus trouvés dans le cache.
+    pub hits: u64,
+    /// Secteurs lus absents du cache (lus sur le périphérique).
+    pub misses: u64,
+    /// Secteurs retirés du cache pour faire de la place.
+    pub evictions: u64,
+    /// Secteurs sales écrits sur le périphérique (éviction ou synchronisation).
+    pub write_backs: u64,
+}
+
+impl CacheStats {
+    /// Proportion de lectures servies par le cache (0.0 sans aucune lecture).
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Secteur gardé en mémoire.
+struct Slot {
+    data: Box<[u8; SECTOR_SIZE]>,
+    /// Modifié depuis la dernière écriture sur le périphérique.
+    dirty: bool,
+    /// “Date” du dernier accès (compteur du cache), pour le LRU.
+    used: u64,
+}
+
+impl Slot {
+    fn new(chunk: &[u8], dirty: bool, used: u64) -> Self {
+        let mut data = Box::new([0u8; SECTOR_SIZE]);
+        data.copy_from_slice(chunk);
+        Self { data, dirty, used }
+    }
+}
+
+/// Une réserve de secteurs, évincés du moins récemment utilisé au plus récent.
+struct Pool {
+    capacity: usize,
+    slots: BTreeMap<u64, Slot>,
+}
+
+impl Pool {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            slots: BTreeMap::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.slots.len() >= self.capacity
+    }
+
+    /// Secteur le moins récemment utilisé (parmi les secteurs propres si `clean_only`).
+    fn lru(&self, clean_only: bool) -> Option<u64> {
+        self.slots
+            .iter()
+            .filter(|(_, s)| !(clean_only && s.dirty))
+            .min_by_key(|(_, s)| s.used)
+            .map(|(lba, _)| *lba)
+    }
+}
+
+/// État modifiable du cache : partagé entre lectures (`&self`) et écritures.
+struct State {
+    fat: Pool,
+    data: Pool,
+    /// Compteur incrémenté à chaque accès.
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl State {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+/// Périphérique avec un cache LRU de secteurs et écriture différée.
+///
+/// Les secteurs sales ne sont écrits sur `D` qu’à leur éviction, ou lors de
+/// `write_back` / `flush`. Un `CachedDevice` abandonné sans synchronisation
+/// perd donc ses dernières écritures : passer par `Fat32Mut::flush`,
+/// `Fat32Mut::sync` ou `Fat32Mut::unmount`.
+///
+/// Exemples :
+/// ```rust,no_run
+/// # use fat32_parser::{CacheConfig, CachedDevice, Fat32Mut, FatError};
+/// # let mut disk = vec![0u8; 10 * 512];
+/// let dev = CachedDevice::new(&mut disk, CacheConfig::default());
+/// let mut rw = Fat32Mut::from_device(dev)?;
+/// rw.write_file_by_path("/NEW.TXT", b"DATA")?;
+/// println!("{:?}", rw.cache_stats());
+/// rw.unmount()?;
+/// # Ok::<(), FatError>(())
+/// ```
+pub struct CachedDevice<D> {
+    inner: D,
+    /// Premier secteur de la zone de données : les secteurs avant sont des métadonnées.
+    data_start: u64,
+    state: RefCell<State>,
+}
+
+impl<D: core::fmt::Debug> core::fmt::Debug for CachedDevice<D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("CachedDevice")
+            .field("inner", &self.inner)
+            .field("data_start", &self.data_start)
+            .field("cached", &(state.fat.slots.len() + state.data.slots.len()))
+            .field("stats", &state.stats)
+            .finish()
+    }
+}
+
+impl<D: BlockDevice> CachedDevice<D> {
+    /// Place un cache devant `inner`.
+    ///
+    /// Le BPB de `inner` est lu pour situer la zone de données. S’il n’est pas valide
+    /// (périphérique pas encore formaté, disque avec table de partitions...), tous les
+    /// secteurs passent par la réserve de données : mettre plutôt le cache au-dessus
+    /// de la partition ([`crate::PartitionDevice`]).
+    ///
+    /// Paramètres :
+    /// - `inner` : périphérique à mettre en cache.
+    /// - `config` : capacité de chaque réserve.
+    ///
+    /// Panics : aucune.
+    pub fn new(inner: D, config: CacheConfig) -> Self {
+        let data_start = read_bpb(&inner)
+            .map(|bpb| (bpb.data_start_byte() / SECTOR_SIZE) as u64)
+            .unwrap_or(0);
+        Self {
+            inner,
+            data_start,
+            state: RefCell::new(State {
+                fat: Pool::new(config.fat_sectors),
+                data: Pool::new(config.data_sectors),
+                clock: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    /// Périphérique sous-jacent, sans les écritures encore en cache.
+    pub fn get_ref(&self) -> &D {
+        &self.inner
+    }
+
+    /// Compteurs depuis la création (ou le dernier [`CachedDevice::reset_stats`]).
+    pub fn stats(&self) -> CacheStats {
+        self.state.borrow().stats
+    }
+
+    /// Remet les compteurs à zéro (le contenu du cache est conservé).
+    pub fn reset_stats(&mut self) {
+        self.state.get_mut().stats = CacheStats::default();
+    }
+
+    /// Nombre de secteurs en mémoire.
+    pub fn cached_sectors(&self) -> usize {
+        let state = self.state.borrow();
+        state.fat.slots.len() + state.data.slots.len()
+    }
+
+    /// Nombre de secteurs modifiés pas encore écrits sur le périphérique.
+    pub fn dirty_sectors(&self) -> usize {
+        let state = self.state.borrow();
+        let dirty = |pool: &Pool| pool.slots.values().filter(|s| s.dirty).count();
+        dirty(&state.fat) + dirty(&state.data)
+    }
+
+    /// Écrit les secteurs sales puis rend le périphérique sous-jacent.
+    ///
+    /// Erreurs :
+    /// - `Io`/`OutOfBounds` si l’écriture sur le périphérique échoue.
+    pub fn into_inner(mut self) -> Result<D, FatError> {
+        self.write_back()?;
+        Ok(self.inner)
+    }
+
+    /// Découpe `[start, start + sectors)` en au plus deux morceaux, un par réserve.
+    ///
+    /// Retour : `(premier secteur, nombre de secteurs, réserve de métadonnées ?)`.
+    fn segments(&self, start: u64, sectors: usize) -> [(u64, usize, bool); 2] {
+        let end = start + sectors as u64;
+        let split = self.data_start.clamp(start, end);
+        [
+            (start, (split - start) as usize, true),
+            (split, (end - split) as usize, false),
+        ]
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for CachedDevice<D> {
+    fn sector_count(&self) -> u64 {
+        self.inner.sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.sector_count())?;
+        let mut state = self.state.borrow_mut();
+        let now = state.tick();
+        let State {
+            fat, data, stats, ..
+        } = &mut *state;
+        let mut done = 0;
+
+        for (first, count, is_fat) in self.segments(start, buf.len() / SECTOR_SIZE) {
+            if count == 0 {
+                continue;
+            }
+            let seg = &mut buf[done..done + count * SECTOR_SIZE];
+            done += count * SECTOR_SIZE;
+
+            let pool = if is_fat { &mut *fat } else { &mut *data };
+            let missing = (first..first + count as u64)
+                .filter(|lba| !pool.slots.contains_key(lba))
+                .count();
+            stats.hits += (count - missing) as u64;
+            stats.misses += missing as u64;
+
+            // Un seul accès au périphérique pour tout le morceau, puis les copies
+            // en cache (éventuellement plus récentes) par-dessus
+            if missing > 0 {
+                self.inner.read_sectors(first, seg)?;
+            }
+            for (lba, chunk) in (first..).zip(seg.chunks_mut(SECTOR_SIZE)) {
+                if let Some(slot) = pool.slots.get_mut(&lba) {
+                    chunk.copy_from_slice(&slot.data[..]);
+                    slot.used = now;
+                }
+            }
+
+            // Une lecture plus grosse que la réserve la viderait sans profit
+            if missing == 0 || count > pool.capacity {
+                continue;
+            }
+            for (lba, chunk) in (first..).zip(seg.chunks(SECTOR_SIZE)) {
+                if pool.slots.contains_key(&lba) {
+                    continue;
+                }
+                // Sans `&mut` sur le périphérique, seul un secteur propre peut être évincé
+                if pool.is_full() {
+                    let Some(victim) = pool.lru(true) else {
+                        break;
+                    };
+                    pool.slots.remove(&victim);
+                    stats.evictions += 1;
+                }
+                pool.slots.insert(lba, Slot::new(chunk, false, now));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.sector_count())?;
+        let segments = self.segments(start, buf.len() / SECTOR_SIZE);
+        let state = self.state.get_mut();
+        let now = state.tick();
+        let State {
+            fat, data, stats, ..
+        } = state;
+        let mut done = 0;
+
+        for (first, count, is_fat) in segments {
+            if count == 0 {
+                continue;
+            }
+            let seg = &buf[done..done + count * SECTOR_SIZE];
+            done += count * SECTOR_SIZE;
+            let pool = if is_fat { &mut *fat } else { &mut *data };
+
+            // Écriture plus grosse que la réserve : directement sur le périphérique,
+            // les copies en cache sont mises à jour et redeviennent propres
+            if count > pool.capacity {
+                self.inner.write_sectors(first, seg)?;
+                for (lba, chunk) in (first..).zip(seg.chunks(SECTOR_SIZE)) {
+                    if let Some(slot) = pool.slots.get_mut(&lba) {
+                        slot.data.copy_from_slice(chunk);
+                        slot.dirty = false;
+                    }
+                }
+                continue;
+            }
+
+            for (lba, chunk) in (first..).zip(seg.chunks(SECTOR_SIZE)) {
+                if let Some(slot) = pool.slots.get_mut(&lba) {
+                    slot.data.copy_from_slice(chunk);
+                    slot.dirty = true;
+                    slot.used = now;
+                    continue;
+                }
+                if pool.is_full() {
+                    if let Some(victim) = pool.lru(false) {
+                        if let Some(slot) = pool.slots.remove(&victim) {
+                            stats.evictions += 1;
+                            if slot.dirty {
+                                self.inner.write_sector(victim, &slot.data)?;
+                                stats.write_backs += 1;
+                            }
+                        }
+                    }
+                }
+                pool.slots.insert(lba, Slot::new(chunk, true, now));
+            }
+        }
+        Ok(())
+    }
+
+    /// Écrit les secteurs sales, dans l’ordre des secteurs, et les garde en cache (propres).
+    fn write_back(&mut self) -> Result<(), FatError> {
+        let State {
+            fat, data, stats, ..
+        } = self.state.get_mut();
+        for pool in [fat, data] {
+            for (lba, slot) in pool.slots.iter_mut().filter(|(_, s)| s.dirty) {
+                self.inner.write_sector(*lba, &slot.data)?;
+                slot.dirty = false;
+                stats.write_backs += 1;
+            }
+        }
+        self.inner.write_back()
+    }
+
+    fn flush(&mut self) -> Result<(), FatError> {
+        self.write_back()?;
+        self.inner.flush()
+    }
+}
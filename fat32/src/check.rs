@@ -0,0 +1,692 @@
+//! Vérification et réparation d’un volume (l’équivalent de `fsck`).
+//!
+//! [`Fat32::check`] parcourt tout l’arbre depuis la racine en suivant chaque chaîne
+//! dans une copie en mémoire de la FAT, puis compare la FAT au résultat :
+//! - un cluster alloué que rien ne référence forme une chaîne perdue,
+//! - un cluster réclamé par deux entrées est “cross-linked”,
+//! - une chaîne qui repasse par un de ses clusters boucle,
+//! - un lien vers un cluster hors volume, libre ou défectueux casse la chaîne,
+//! - la chaîne d’un fichier ne doit pas être trop courte pour sa taille; une chaîne plus
+//!   longue est une réserve de `preallocate`, acceptée telle quelle,
+//! - les copies de la FAT doivent être identiques à la première,
+//! - un sous-répertoire commence par `.` (lui-même) et `..` (son parent, 0 pour la racine).
+//!
+//! [`Fat32Mut::repair`] applique ensuite les corrections : les chaînes cassées sont
+//! coupées avant le mauvais lien, la taille d’un fichier à la chaîne trop courte ramenée
+//! à ce que la chaîne contient, les chaînes perdues récupérées dans
+//! `/FOUND.000/FILEnnnn.CHK`, et les copies de la FAT recopiées depuis la première.
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::fat_type::BAD_CLUSTER;
+use crate::{
+    div_ceil, BlockDevice, Fat32, Fat32Mut, FatError, ATTR_ARCHIVE, ATTR_DIRECTORY, FAT32_EOC,
+};
+
+/// Répertoire où `repair` range les chaînes perdues.
+const FOUND_DIR: &str = "/FOUND.000";
+
+/// Cluster sans propriétaire dans la table `owner`.
+const NO_OWNER: u32 = u32::MAX;
+
+/// Problème trouvé par [`Fat32::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Chaîne de clusters alloués qu’aucune entrée ne référence.
+    LostChain {
+        /// Premier cluster de la chaîne.
+        start: u32,
+        /// Nombre de clusters.
+        clusters: u32,
+    },
+    /// La chaîne de `path` passe par un cluster qui appartient déjà à `other`.
+    CrossLinked {
+        /// Entrée dont la chaîne est coupée.
+        path: String,
+        /// Entrée qui possède déjà le cluster.
+        other: String,
+        /// Cluster partagé.
+        cluster: u32,
+    },
+    /// La chaîne de `path` revient sur `cluster`, déjà parcouru.
+    ChainLoop {
+        /// Entrée concernée.
+        path: String,
+        /// Cluster où la boucle se referme.
+        cluster: u32,
+    },
+    /// Le cluster `cluster` de `path` pointe sur `next`, hors volume, libre ou défectueux.
+    BrokenChain {
+        /// Entrée concernée.
+        path: String,
+        /// Dernier cluster valide de la chaîne.
+        cluster: u32,
+        /// Valeur lue dans la FAT.
+        next: u32,
+    },
+    /// Le premier cluster de `path` est hors volume, libre ou défectueux
+    /// (ou vaut 0 pour un sous-répertoire).
+    BadFirstCluster {
+        /// Entrée concernée.
+        path: String,
+        /// Premier cluster lu dans l’entrée.
+        cluster: u32,
+    },
+    /// La chaîne de `path` est trop courte pour sa taille.
+    SizeMismatch {
+        /// Fichier concerné.
+        path: String,
+        /// Taille lue dans l’entrée, en octets.
+        size: u32,
+        /// Nombre de clusters de la chaîne.
+        clusters: u32,
+    },
+    /// La copie `copy` de la FAT diffère de la première sur `entries` entrées.
+    FatCopyMismatch {
+        /// Numéro de la copie (1 = la deuxième FAT).
+        copy: u8,
+        /// Nombre d’entrées différentes.
+        entries: u32,
+    },
+    /// L’entrée `name` (`.` ou `..`) du répertoire `path` manque ou ne pointe pas sur `expected`.
+    BadDotEntry {
+        /// Répertoire concerné.
+        path: String,
+        /// `"."` ou `".."`.
+        name: &'static str,
+        /// Cluster attendu (0 pour un `..` qui désigne la racine).
+        expected: u32,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LostChain { start, clusters } => {
+                write!(f, "chaîne perdue : {clusters} cluster(s) à partir du cluster {start}")
+            }
+            Self::CrossLinked {
+                path,
+                other,
+                cluster,
+            } => write!(f, "{path} : cluster {cluster} partagé avec {other}"),
+            Self::ChainLoop { path, cluster } => {
+                write!(f, "{path} : la chaîne boucle sur le cluster {cluster}")
+            }
+            Self::BrokenChain {
+                path,
+                cluster,
+                next,
+            } => write!(
+                f,
+                "{path} : le cluster {cluster} pointe sur {next:#x} (hors volume, libre ou défectueux)"
+            ),
+            Self::BadFirstCluster { path, cluster } => {
+                write!(f, "{path} : premier cluster {cluster:#x} invalide")
+            }
+            Self::SizeMismatch {
+                path,
+                size,
+                clusters,
+            } => write!(f, "{path} : {size} octets pour une chaîne de {clusters} cluster(s)"),
+            Self::FatCopyMismatch { copy, entries } => {
+                write!(f, "FAT n°{copy} : {entries} entrée(s) différente(s) de la FAT n°0")
+            }
+            Self::BadDotEntry {
+                path,
+                name,
+                expected,
+            } => write!(
+                f,
+                "{path} : entrée `{name}` absente ou incorrecte (cluster attendu {expected})"
+            ),
+        }
+    }
+}
+
+/// Résultat d’une vérification : problèmes trouvés et taille de l’arbre parcouru.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    /// Problèmes, dans l’ordre où ils ont été trouvés.
+    pub problems: Vec<Problem>,
+    /// Nombre de fichiers parcourus.
+    pub files: u32,
+    /// Nombre de répertoires parcourus (hors racine).
+    pub dirs: u32,
+}
+
+impl CheckReport {
+    /// `true` si aucun problème n’a été trouvé.
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Correction associée à un problème, appliquée par `repair`.
+#[derive(Debug, Clone)]
+enum Fix {
+    /// Recopie la première FAT sur les autres.
+    SyncFats,
+    /// Termine la chaîne sur `cluster` (EOC).
+    EndChain(u32),
+    /// Vide l’entrée d’un fichier (cluster 0, taille 0).
+    ClearEntry(usize),
+    /// Supprime une entrée de répertoire (entrée courte et LFN).
+    DeleteEntry(Vec<usize>),
+    /// Réécrit la taille d’un fichier.
+    SetSize { entry: usize, size: u32 },
+    /// Réécrit une entrée `.` ou `..` ; `end_after` remet la fin de répertoire derrière.
+    DotEntry {
+        offset: usize,
+        name: [u8; 11],
+        cluster: u32,
+        end_after: bool,
+    },
+    /// Range une chaîne perdue dans `FOUND.000`.
+    Recover(Vec<u32>),
+}
+
+/// Entrée en cours de vérification : chemin et position de son entrée courte.
+struct Item {
+    path: String,
+    /// Offset de l’entrée courte (`None` pour la racine).
+    offset: Option<usize>,
+    lfn_offsets: Vec<usize>,
+    is_dir: bool,
+}
+
+/// État du parcours : FAT en mémoire et propriétaire de chaque cluster.
+struct Checker<'a, D> {
+    fs: &'a Fat32<D>,
+    /// Copie de la première FAT, indexée par cluster.
+    fat: Vec<u32>,
+    /// Indice (dans `paths`) de l’entrée qui possède chaque cluster.
+    owner: Vec<u32>,
+    paths: Vec<String>,
+    report: CheckReport,
+    fixes: Vec<Fix>,
+}
+
+impl<D: BlockDevice> Fat32<D> {
+    /// Vérifie la cohérence de tout le volume, sans rien modifier.
+    ///
+    /// Retour : un [`CheckReport`] (vide si le volume est sain).
+    ///
+    /// Erreurs :
+    /// - `OutOfBounds`/`Io` si la lecture du périphérique échoue.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// for problem in fs.check()?.problems {
+    ///     println!("{problem}");
+    /// }
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn check(&self) -> Result<CheckReport, FatError> {
+        Ok(self.inspect()?.0)
+    }
+
+    /// Parcourt le volume et retourne le rapport avec les corrections à appliquer.
+    fn inspect(&self) -> Result<(CheckReport, Vec<Fix>), FatError> {
+        let max_cl = self.max_cluster_number()? as usize;
+        let mut fat = alloc::vec![0u32; max_cl + 1];
+        self.for_each_fat_entry(|cl, value| fat[cl as usize] = value)?;
+
+        let mut checker = Checker {
+            fs: self,
+            fat,
+            owner: alloc::vec![NO_OWNER; max_cl + 1],
+            paths: Vec::new(),
+            report: CheckReport::default(),
+            fixes: Vec::new(),
+        };
+        checker.check_fat_copies()?;
+        checker.check_tree()?;
+        checker.collect_lost();
+        Ok((checker.report, checker.fixes))
+    }
+}
+
+impl<D: BlockDevice> Checker<'_, D> {
+    fn problem(&mut self, problem: Problem, fix: Option<Fix>) {
+        self.report.problems.push(problem);
+        self.fixes.extend(fix);
+    }
+
+    /// Compare chaque copie de la FAT à la première.
+    fn check_fat_copies(&mut self) -> Result<(), FatError> {
+        let mut any = false;
+        for copy in 1..self.fs.bpb.num_fats {
+            let mut entries = 0u32;
+            let fat = &self.fat;
+            self.fs.for_each_fat_entry_in(copy as usize, |cl, value| {
+                if fat[cl as usize] != value {
+                    entries += 1;
+                }
+            })?;
+            if entries > 0 {
+                let fix = (!any).then_some(Fix::SyncFats);
+                any = true;
+                self.problem(Problem::FatCopyMismatch { copy, entries }, fix);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parcourt l’arbre depuis la racine (en largeur, sans récursion).
+    fn check_tree(&mut self) -> Result<(), FatError> {
+        let root = Item {
+            path: String::from("/"),
+            offset: None,
+            lfn_offsets: Vec::new(),
+            is_dir: true,
+        };
+
+        // (répertoire, sa chaîne — `None` pour une racine fixe —, cluster attendu dans `..`)
+        let mut pending = Vec::new();
+        if self.fs.bpb.is_fixed_root(0) {
+            self.paths.push(root.path.clone());
+            pending.push((root, None, 0));
+        } else if let Some(chain) = self.walk(&root, self.fs.bpb.root_cluster) {
+            pending.push((root, Some(chain), 0));
+        }
+
+        while let Some((dir, chain, parent)) = pending.pop() {
+            let regions = match &chain {
+                None => self.fs.dir_regions(0)?,
+                Some(chain) => {
+                    let cs = self.fs.cluster_size();
+                    chain
+                        .iter()
+                        .map(|&cl| Ok((self.fs.cluster_to_offset(cl)?, cs)))
+                        .collect::<Result<Vec<_>, FatError>>()?
+                }
+            };
+            // Les enfants de la racine ont `..` = 0
+            let mut self_cluster = 0;
+            if let (Some(chain), Some(_)) = (&chain, dir.offset) {
+                self_cluster = chain[0];
+                self.check_dots(&dir, self_cluster, parent, regions[0].0)?;
+            }
+
+            for located in self.fs.scan_regions(regions)? {
+                if located.raw[0] == b'.' {
+                    continue;
+                }
+                let entry = located.entry;
+                let path = if dir.path == "/" {
+                    format!("/{}", entry.display_name())
+                } else {
+                    format!("{}/{}", dir.path, entry.display_name())
+                };
+                let item = Item {
+                    path,
+                    offset: Some(located.offset),
+                    lfn_offsets: located.lfn_offsets,
+                    is_dir: entry.is_dir(),
+                };
+
+                if item.is_dir {
+                    self.report.dirs += 1;
+                    if entry.first_cluster == 0 {
+                        self.paths.push(item.path.clone());
+                        let fix = self.drop_entry(&item);
+                        self.problem(
+                            Problem::BadFirstCluster {
+                                path: item.path,
+                                cluster: 0,
+                            },
+                            fix,
+                        );
+                    } else if let Some(chain) = self.walk(&item, entry.first_cluster) {
+                        pending.push((item, Some(chain), self_cluster));
+                    }
+                } else {
+                    self.report.files += 1;
+                    let chain = if entry.first_cluster == 0 {
+                        self.paths.push(item.path.clone());
+                        Some(Vec::new())
+                    } else {
+                        self.walk(&item, entry.first_cluster)
+                    };
+                    if let Some(chain) = chain {
+                        self.check_size(&item, entry.size, &chain);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Suit la chaîne de `item` à partir de `start` et s’approprie ses clusters.
+    ///
+    /// La chaîne s’arrête au premier lien invalide (problème + coupure juste avant).
+    /// Retour : les clusters valides, ou `None` si le premier cluster est déjà invalide.
+    fn walk(&mut self, item: &Item, start: u32) -> Option<Vec<u32>> {
+        let id = self.paths.len() as u32;
+        self.paths.push(item.path.clone());
+
+        let max_cl = self.fat.len() as u32 - 1;
+        let mut chain: Vec<u32> = Vec::new();
+        let mut cur = start;
+
+        loop {
+            let prev = chain.last().copied();
+            let in_volume = (2..=max_cl).contains(&cur);
+            let owner = if in_volume {
+                self.owner[cur as usize]
+            } else {
+                NO_OWNER
+            };
+
+            let problem = if in_volume && owner == id {
+                Some(Problem::ChainLoop {
+                    path: item.path.clone(),
+                    cluster: cur,
+                })
+            } else if owner != NO_OWNER {
+                Some(Problem::CrossLinked {
+                    path: item.path.clone(),
+                    other: self.paths[owner as usize].clone(),
+                    cluster: cur,
+                })
+            } else if !in_volume || matches!(self.fat[cur as usize], 0 | BAD_CLUSTER) {
+                Some(match prev {
+                    Some(cluster) => Problem::BrokenChain {
+                        path: item.path.clone(),
+                        cluster,
+                        next: cur,
+                    },
+                    None => Problem::BadFirstCluster {
+                        path: item.path.clone(),
+                        cluster: cur,
+                    },
+                })
+            } else {
+                None
+            };
+
+            if let Some(problem) = problem {
+                let fix = match prev {
+                    Some(cluster) => Some(Fix::EndChain(cluster)),
+                    None => self.drop_entry(item),
+                };
+                self.problem(problem, fix);
+                return if chain.is_empty() { None } else { Some(chain) };
+            }
+
+            self.owner[cur as usize] = id;
+            chain.push(cur);
+
+            let next = self.fat[cur as usize];
+            if next >= 0x0FFF_FFF8 {
+                return Some(chain);
+            }
+            cur = next;
+        }
+    }
+
+    /// Correction d’une entrée dont le premier cluster est inutilisable :
+    /// un fichier devient vide, un répertoire est supprimé (la racine n’a pas d’entrée).
+    fn drop_entry(&self, item: &Item) -> Option<Fix> {
+        let offset = item.offset?;
+        Some(if item.is_dir {
+            let mut offsets = item.lfn_offsets.clone();
+            offsets.push(offset);
+            Fix::DeleteEntry(offsets)
+        } else {
+            Fix::ClearEntry(offset)
+        })
+    }
+
+    /// Vérifie que la chaîne d’un fichier couvre sa taille.
+    ///
+    /// Une chaîne plus longue est valide : ce sont des clusters réservés par
+    /// `preallocate` (ou laissés par une coupure pendant une réduction).
+    fn check_size(&mut self, item: &Item, size: u32, chain: &[u32]) {
+        let Some(entry) = item.offset else {
+            return;
+        };
+        let cs = self.fs.cluster_size();
+        let expected = div_ceil(size as usize, cs);
+        if chain.len() >= expected {
+            return;
+        }
+
+        // Chaîne trop courte : la taille est ramenée à ce que la chaîne contient
+        let covered = (chain.len() as u64 * cs as u64).min(u32::MAX as u64) as u32;
+        self.problem(
+            Problem::SizeMismatch {
+                path: item.path.clone(),
+                size,
+                clusters: chain.len() as u32,
+            },
+            Some(Fix::SetSize {
+                entry,
+                size: covered,
+            }),
+        );
+    }
+
+    /// Vérifie les entrées `.` et `..` en tête du premier cluster d’un sous-répertoire.
+    ///
+    /// `parent` vaut 0 quand le parent est la racine (on accepte aussi le cluster racine
+    /// FAT32, que certains outils écrivent).
+    fn check_dots(
+        &mut self,
+        dir: &Item,
+        cluster: u32,
+        parent: u32,
+        base: usize,
+    ) -> Result<(), FatError> {
+        let mut raw = [0u8; 64];
+        self.fs.read_bytes(base, &mut raw)?;
+
+        let mut end_seen = false;
+        for (slot, name, expected) in [(0usize, ".", cluster), (1, "..", parent)] {
+            let e = &raw[slot * 32..slot * 32 + 32];
+            let mut short = [b' '; 11];
+            short[..name.len()].copy_from_slice(name.as_bytes());
+
+            let found = u32::from(u16::from_le_bytes([e[20], e[21]])) << 16
+                | u32::from(u16::from_le_bytes([e[26], e[27]]));
+            let named = e[0..11] == short && e[11] & ATTR_DIRECTORY != 0;
+            let ok = named
+                && (found == expected || (expected == 0 && found == self.fs.bpb.root_cluster));
+            let free = end_seen || e[0] == 0x00 || e[0] == 0xE5;
+            end_seen |= e[0] == 0x00;
+            if ok {
+                continue;
+            }
+
+            // On ne réécrit que le slot d’un `.`/`..` abîmé ou un slot libre
+            let fix = (named || free).then_some(Fix::DotEntry {
+                offset: base + slot * 32,
+                name: short,
+                cluster: expected,
+                end_after: end_seen,
+            });
+            self.problem(
+                Problem::BadDotEntry {
+                    path: dir.path.clone(),
+                    name,
+                    expected,
+                },
+                fix,
+            );
+        }
+        Ok(())
+    }
+
+    /// Regroupe les clusters alloués sans propriétaire en chaînes perdues.
+    fn collect_lost(&mut self) {
+        let max_cl = self.fat.len() - 1;
+        let lost: Vec<bool> = (0..=max_cl)
+            .map(|cl| {
+                cl >= 2 && self.owner[cl] == NO_OWNER && !matches!(self.fat[cl], 0 | BAD_CLUSTER)
+            })
+            .collect();
+
+        // Une tête de chaîne est un cluster perdu qu’aucun autre cluster perdu ne désigne
+        let mut pointed = alloc::vec![false; max_cl + 1];
+        for cl in 2..=max_cl {
+            let next = self.fat[cl] as usize;
+            if lost[cl] && next <= max_cl {
+                pointed[next] = true;
+            }
+        }
+
+        let mut seen = alloc::vec![false; max_cl + 1];
+        // Les têtes d’abord, puis ce qui reste (boucles sans tête)
+        for heads_only in [true, false] {
+            for head in 2..=max_cl {
+                if !lost[head] || seen[head] || (heads_only && pointed[head]) {
+                    continue;
+                }
+
+                let mut chain = Vec::new();
+                let mut cur = head;
+                while cur <= max_cl && lost[cur] && !seen[cur] {
+                    seen[cur] = true;
+                    chain.push(cur as u32);
+                    cur = self.fat[cur] as usize;
+                }
+                self.problem(
+                    Problem::LostChain {
+                        start: head as u32,
+                        clusters: chain.len() as u32,
+                    },
+                    Some(Fix::Recover(chain)),
+                );
+            }
+        }
+    }
+}
+
+impl<D: BlockDevice> Fat32Mut<D> {
+    /// Vérifie le volume puis corrige ce qui peut l’être.
+    ///
+    /// - les copies de la FAT sont recopiées depuis la première,
+    /// - une chaîne cassée (boucle, cross-link, lien invalide) est coupée avant le mauvais lien,
+    /// - une entrée dont le premier cluster est invalide est vidée (fichier) ou supprimée
+    ///   (répertoire),
+    /// - un fichier plus long que sa chaîne voit sa taille ramenée à celle de la chaîne;
+    ///   une chaîne plus longue que le fichier (réserve de `preallocate`) est gardée,
+    /// - les entrées `.` et `..` sont réécrites,
+    /// - chaque chaîne perdue devient un fichier `/FOUND.000/FILEnnnn.CHK`,
+    /// - un volume marqué “sale” (bit de FAT[1]) redevient propre à l’`unmount`.
+    ///
+    /// Retour : le rapport d’avant réparation. Un nouveau `check()` doit être propre
+    /// (sauf ce qui ne se répare pas, comme une racine FAT32 invalide).
+    ///
+    /// Erreurs :
+    /// - `NoSpaceLeft` si `FOUND.000` ne peut pas être créé ou agrandi.
+    /// - `OutOfBounds`/`Io` si le périphérique échoue.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let report = rw.repair()?;
+    /// println!("{} problème(s) corrigé(s)", report.problems.len());
+    /// assert!(rw.as_read().check()?.is_clean());
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn repair(&mut self) -> Result<CheckReport, FatError> {
+        let (report, fixes) = self.as_read().inspect()?;
+        let mut found_dir = None;
+        let mut next_name = 0u32;
+
+        for fix in fixes {
+            match fix {
+                Fix::SyncFats => self.sync_fat_copies()?,
+                Fix::EndChain(cluster) => self.write_fat_entry_all(cluster, FAT32_EOC)?,
+                Fix::ClearEntry(entry) => {
+                    self.set_entry_cluster(entry, 0)?;
+                    self.write_bytes(entry + 28, &0u32.to_le_bytes())?;
+                }
+                Fix::DeleteEntry(offsets) => {
+                    for off in offsets {
+                        self.write_bytes(off, &[0xE5])?;
+                    }
+                }
+                Fix::SetSize { entry, size } => {
+                    self.write_bytes(entry + 28, &size.to_le_bytes())?;
+                }
+                Fix::DotEntry {
+                    offset,
+                    name,
+                    cluster,
+                    end_after,
+                } => {
+                    self.write_dir_entry_at_offset(offset, &name, ATTR_DIRECTORY, cluster, 0)?;
+                    if end_after {
+                        self.write_bytes(offset + 32, &[0x00])?;
+                    }
+                }
+                Fix::Recover(chain) => {
+                    let last = chain[chain.len() - 1];
+                    if self.read_fat_entry(last)? < 0x0FFF_FFF8 {
+                        self.write_fat_entry_all(last, FAT32_EOC)?;
+                    }
+
+                    let dir = match found_dir {
+                        Some(dir) => dir,
+                        None => {
+                            self.create_dir_all(FOUND_DIR)?;
+                            let dir = self.resolve_dir_cluster(FOUND_DIR)?;
+                            found_dir = Some(dir);
+                            dir
+                        }
+                    };
+                    let name = loop {
+                        let name = format!("FILE{next_name:04}.CHK");
+                        next_name += 1;
+                        if self.as_read().find_in_dir(dir, &name)?.is_none() {
+                            break name;
+                        }
+                    };
+                    let size = (chain.len() as u64 * self.cluster_size() as u64)
+                        .min(u32::MAX as u64) as u32;
+                    self.create_dir_entry(dir, &name, ATTR_ARCHIVE, chain[0], size)?;
+                }
+            }
+        }
+
+        // Le bit est déjà effacé sur le disque : `unmount` le remettra
+        if self.as_read().is_dirty()? {
+            self.dirty = true;
+        }
+
+        Ok(report)
+    }
+
+    /// Recopie la première FAT sur toutes les autres, par blocs de 32 secteurs.
+    fn sync_fat_copies(&mut self) -> Result<(), FatError> {
+        let fat0 = self.bpb.fat_start_byte();
+        let fat_len = self.bpb.fat_bytes_len();
+        let mut buf = alloc::vec![0u8; core::cmp::min(32 * crate::SECTOR_SIZE, fat_len)];
+
+        let mut pos = 0;
+        while pos < fat_len {
+            let n = core::cmp::min(buf.len(), fat_len - pos);
+            self.read_bytes(fat0 + pos, &mut buf[..n])?;
+            for copy in 1..self.bpb.num_fats as usize {
+                self.write_bytes(fat0 + copy * fat_len + pos, &buf[..n])?;
+            }
+            pos += n;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+//! Abstraction du stockage sous-jacent (périphérique bloc).
+//!
+//! Le système de fichiers ne manipule plus directement un `&[u8]` :
+//! il lit et écrit des secteurs de 512 octets à travers le trait [`BlockDevice`].
+//! On ne charge donc que les secteurs utiles (BPB, secteurs de FAT, clusters lus),
+//! ce qui permet de travailler sur une image de plusieurs Go sans la mettre en RAM.
+//!
+//! Implémentations fournies :
+//! - `[u8]` / `Vec<u8>` (image en mémoire), donc `&[u8]` (lecture) et `&mut [u8]`,
+//! - `std::fs::File` avec la feature `std`.
+//!
+//! [`crate::CachedDevice`] s’intercale devant n’importe lequel d’entre eux pour garder
+//! les secteurs récents en mémoire.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::FatError;
+
+/// Taille d’un secteur du périphérique, en octets.
+pub const SECTOR_SIZE: usize = 512;
+
+/// Périphérique adressé par secteurs de [`SECTOR_SIZE`] octets.
+///
+/// Les buffers passés à `read_sectors` / `write_sectors` doivent avoir une taille
+/// multiple de [`SECTOR_SIZE`]; on lit/écrit `buf.len() / SECTOR_SIZE` secteurs
+/// consécutifs à partir de `start`.
+pub trait BlockDevice {
+    /// Nombre de secteurs disponibles.
+    fn sector_count(&self) -> u64;
+
+    /// Lit des secteurs consécutifs à partir du secteur `start`.
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError>;
+
+    /// Écrit des secteurs consécutifs à partir du secteur `start`.
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError>;
+
+    /// Lit un seul secteur.
+    fn read_sector(&self, lba: u64, buf: &mut [u8; SECTOR_SIZE]) -> Result<(), FatError> {
+        self.read_sectors(lba, buf)
+    }
+
+    /// Écrit un seul secteur.
+    fn write_sector(&mut self, lba: u64, buf: &[u8; SECTOR_SIZE]) -> Result<(), FatError> {
+        self.write_sectors(lba, buf)
+    }
+
+    /// Écrit sur le périphérique les secteurs gardés en mémoire (cache), sans forcer
+    /// leur écriture sur le support (par défaut : rien à faire).
+    fn write_back(&mut self) -> Result<(), FatError> {
+        Ok(())
+    }
+
+    /// Vide les éventuels tampons vers le support (par défaut : rien à faire).
+    ///
+    /// Un périphérique avec cache fait d’abord un [`BlockDevice::write_back`].
+    fn flush(&mut self) -> Result<(), FatError> {
+        Ok(())
+    }
+}
+
+/// Vérifie qu’un accès de `len` octets à partir du secteur `start` tient dans `count` secteurs.
+pub(crate) fn check_range(start: u64, len: usize, count: u64) -> Result<(), FatError> {
+    if !len.is_multiple_of(SECTOR_SIZE) {
+        return Err(FatError::OutOfBounds);
+    }
+    let sectors = (len / SECTOR_SIZE) as u64;
+    match start.checked_add(sectors) {
+        Some(end) if end <= count => Ok(()),
+        _ => Err(FatError::OutOfBounds),
+    }
+}
+
+impl BlockDevice for [u8] {
+    fn sector_count(&self) -> u64 {
+        (self.len() / SECTOR_SIZE) as u64
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.sector_count())?;
+        let off = start as usize * SECTOR_SIZE;
+        buf.copy_from_slice(&self[off..off + buf.len()]);
+        Ok(())
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.sector_count())?;
+        let off = start as usize * SECTOR_SIZE;
+        self[off..off + buf.len()].copy_from_slice(buf);
+        Ok(())
+    }
+}
+
+impl BlockDevice for Vec<u8> {
+    fn sector_count(&self) -> u64 {
+        self.as_slice().sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        self.as_slice().read_sectors(start, buf)
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        self.as_mut_slice().write_sectors(start, buf)
+    }
+}
+
+/// Accès partagé : lecture seule, toute écriture est refusée.
+impl<T: BlockDevice + ?Sized> BlockDevice for &T {
+    fn sector_count(&self) -> u64 {
+        (**self).sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        (**self).read_sectors(start, buf)
+    }
+
+    fn write_sectors(&mut self, _start: u64, _buf: &[u8]) -> Result<(), FatError> {
+        Err(FatError::Io)
+    }
+}
+
+impl<T: BlockDevice + ?Sized> BlockDevice for &mut T {
+    fn sector_count(&self) -> u64 {
+        (**self).sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        (**self).read_sectors(start, buf)
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        (**self).write_sectors(start, buf)
+    }
+
+    fn write_back(&mut self) -> Result<(), FatError> {
+        (**self).write_back()
+    }
+
+    fn flush(&mut self) -> Result<(), FatError> {
+        (**self).flush()
+    }
+}
+
+/// Image disque ouverte depuis le système de fichiers hôte.
+///
+/// Les lectures passent par `&File` (positionnement + lecture), ce qui permet
+/// de lire via `&self` sans charger l’image en mémoire.
+#[cfg(feature = "std")]
+impl BlockDevice for std::fs::File {
+    fn sector_count(&self) -> u64 {
+        self.metadata().map(|m| m.len() / SECTOR_SIZE as u64).unwrap_or(0)
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+        if !buf.len().is_multiple_of(SECTOR_SIZE) {
+            return Err(FatError::OutOfBounds);
+        }
+        let mut f: &std::fs::File = self;
+        f.seek(SeekFrom::Start(start * SECTOR_SIZE as u64))
+            .map_err(|_| FatError::Io)?;
+        // Pas de `metadata()` à chaque lecture : une lecture hors image échoue en EOF
+        f.read_exact(buf).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => FatError::OutOfBounds,
+            _ => FatError::Io,
+        })
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        use std::io::{Seek, SeekFrom, Write};
+
+        // Une écriture ne doit jamais agrandir l’image
+        check_range(start, buf.len(), self.sector_count())?;
+        self.seek(SeekFrom::Start(start * SECTOR_SIZE as u64))
+            .map_err(|_| FatError::Io)?;
+        self.write_all(buf).map_err(|_| FatError::Io)
+    }
+
+    fn flush(&mut self) -> Result<(), FatError> {
+        self.sync_data().map_err(|_| FatError::Io)
+    }
+}
+
+/// Lit `buf.len()` octets à partir de l’offset `offset` (en octets) du périphérique.
+///
+/// Seuls les secteurs couverts par la plage sont lus.
+pub(crate) fn read_bytes<D: BlockDevice + ?Sized>(
+    dev: &D,
+    offset: usize,
+    buf: &mut [u8],
+) -> Result<(), FatError> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    // Cas rapide : plage alignée sur les secteurs, on lit directement dans `buf`
+    if offset.is_multiple_of(SECTOR_SIZE) && buf.len().is_multiple_of(SECTOR_SIZE) {
+        return dev.read_sectors((offset / SECTOR_SIZE) as u64, buf);
+    }
+
+    let mut sector = [0u8; SECTOR_SIZE];
+    let mut done = 0usize;
+    while done < buf.len() {
+        let pos = offset + done;
+        let lba = (pos / SECTOR_SIZE) as u64;
+        let in_sector = pos % SECTOR_SIZE;
+        let take = core::cmp::min(SECTOR_SIZE - in_sector, buf.len() - done);
+
+        dev.read_sector(lba, &mut sector)?;
+        buf[done..done + take].copy_from_slice(&sector[in_sector..in_sector + take]);
+        done += take;
+    }
+    Ok(())
+}
+
+/// Écrit `data` à partir de l’offset `offset` (en octets) du périphérique.
+///
+/// Les secteurs partiellement couverts sont relus puis réécrits (read-modify-write).
+pub(crate) fn write_bytes<D: BlockDevice + ?Sized>(
+    dev: &mut D,
+    offset: usize,
+    data: &[u8],
+) -> Result<(), FatError> {
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    if offset.is_multiple_of(SECTOR_SIZE) && data.len().is_multiple_of(SECTOR_SIZE) {
+        return dev.write_sectors((offset / SECTOR_SIZE) as u64, data);
+    }
+
+    let mut sector = [0u8; SECTOR_SIZE];
+    let mut done = 0usize;
+    while done < data.len() {
+        let pos = offset + done;
+        let lba = (pos / SECTOR_SIZE) as u64;
+        let in_sector = pos % SECTOR_SIZE;
+        let take = core::cmp::min(SECTOR_SIZE - in_sector, data.len() - done);
+
+        if take < SECTOR_SIZE {
+            dev.read_sector(lba, &mut sector)?;
+        }
+        sector[in_sector..in_sector + take].copy_from_slice(&data[done..done + take]);
+        dev.write_sector(lba, &sector)?;
+        done += take;
+    }
+    Ok(())
+}
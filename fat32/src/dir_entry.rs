@@ -1,16 +1,32 @@
-//! Entrées de répertoire FAT32 (format court 8.3).
+//! Entrées de répertoire FAT32 (format court 8.3 + noms longs VFAT).
 //!
-//! Dans ce projet on ne gère pas les Long File Names (LFN).
-//! On parse donc uniquement les entrées “courtes” de 32 octets.
+//! Une entrée “courte” fait 32 octets et porte le nom 8.3, les attributs,
+//! le premier cluster et la taille.
+//!
+//! Les Long File Names (LFN) sont stockés juste avant l’entrée courte,
+//! sous forme d’une suite d’entrées spéciales (attribut `0x0F`) en ordre inverse.
+//! Chaque entrée LFN contient 13 caractères UCS-2 et un checksum du nom court.
 
 extern crate alloc;
 
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
+
+use crate::FatDateTime;
+
+/// Valeur de l’octet d’attributs pour une entrée LFN.
+pub(crate) const ATTR_LFN: u8 = 0x0F;
+
+/// Nombre de caractères UCS-2 portés par une entrée LFN.
+pub(crate) const LFN_CHARS_PER_ENTRY: usize = 13;
+
+/// Positions (dans les 32 octets) des 13 caractères UCS-2 d’une entrée LFN.
+pub(crate) const LFN_CHAR_OFFSETS: [usize; LFN_CHARS_PER_ENTRY] =
+    [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
 
 /// Attributs FAT d’une entrée de répertoire.
 ///
 /// Les bits viennent directement du champ `ATTR` (offset 11).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Attributes {
     /// Fichier en lecture seule.
     pub read_only: bool,
@@ -38,21 +54,46 @@ impl Attributes {
             archive: b & 0x20 != 0,
         }
     }
+
+    /// Octet brut correspondant (inverse de [`Attributes::from_byte`]).
+    ///
+    /// Les bits réservés `0x40` et `0x80`, ignorés par `from_byte`, valent 0.
+    pub fn to_byte(self) -> u8 {
+        [
+            (self.read_only, 0x01),
+            (self.hidden, 0x02),
+            (self.system, 0x04),
+            (self.volume_id, 0x08),
+            (self.directory, 0x10),
+            (self.archive, 0x20),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |b, (_, bit)| b | bit)
+    }
 }
 
-/// Entrée de répertoire FAT32 (nom court 8.3).
+/// Entrée de répertoire FAT32 (nom court 8.3, plus le nom long s’il existe).
 ///
-/// Exemple: `HELLO.TXT`, `DIR`, `A.BIN`.
+/// Exemple: `HELLO.TXT`, `DIR`, `PROGRA~1` / `Program Files`.
 #[derive(Debug, Clone)]
 pub struct DirEntry {
     /// Nom court reconstitué (ex: `HELLO.TXT`).
     pub name: String,
+    /// Nom long VFAT (ex: `config.json`), si une séquence LFN valide précède l’entrée.
+    pub long_name: Option<String>,
     /// Attributs FAT.
     pub attrs: Attributes,
     /// Premier cluster de la chaîne (0 si fichier vide dans notre écriture simple).
     pub first_cluster: u32,
     /// Taille du fichier en octets (0 pour un répertoire).
     pub size: u32,
+    /// Date de création (`None` si non renseignée).
+    pub created: Option<FatDateTime>,
+    /// Date de dernière modification (`None` si non renseignée).
+    pub modified: Option<FatDateTime>,
+    /// Date de dernier accès : le jour seulement, l’heure est toujours minuit.
+    pub accessed: Option<FatDateTime>,
 }
 
 impl DirEntry {
@@ -60,7 +101,10 @@ impl DirEntry {
     ///
     /// Retourne `None` si:
     /// - l’entrée est libre (`0x00`) ou supprimée (`0xE5`)
-    /// - l’entrée est un Volume ID
+    /// - l’entrée est un Volume ID (ce qui inclut les entrées LFN, attribut `0x0F`)
+    ///
+    /// `long_name` vaut toujours `None` ici : le nom long dépend des entrées
+    /// précédentes, il est rattaché par le listage de répertoire.
     pub fn parse(entry: &[u8]) -> Option<Self> {
         if entry.len() < 32 {
             return None;
@@ -98,11 +142,17 @@ impl DirEntry {
 
         let size = u32::from_le_bytes([entry[28], entry[29], entry[30], entry[31]]);
 
+        let word = |at: usize| u16::from_le_bytes([entry[at], entry[at + 1]]);
+
         Some(Self {
             name: full_name,
+            long_name: None,
             attrs,
             first_cluster,
             size,
+            created: FatDateTime::from_fat(word(16), word(14), entry[13]),
+            modified: FatDateTime::from_fat(word(24), word(22), 0),
+            accessed: FatDateTime::from_fat(word(18), 0, 0),
         })
     }
 
@@ -115,18 +165,259 @@ impl DirEntry {
     pub fn is_file(&self) -> bool {
         !self.attrs.directory
     }
+
+    /// Nom à afficher : le nom long s’il existe, sinon le nom court.
+    pub fn display_name(&self) -> &str {
+        self.long_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Indique si `name` désigne cette entrée.
+    ///
+    /// La comparaison est insensible à la casse et accepte
+    /// aussi bien le nom court (`PROGRA~1`) que le nom long (`Program Files`).
+    pub fn matches_name(&self, name: &str) -> bool {
+        if eq_ignore_case(&self.name, name) {
+            return true;
+        }
+        match &self.long_name {
+            Some(long) => eq_ignore_case(long, name),
+            None => false,
+        }
+    }
 }
 
-/// Décodage ASCII simple en supprimant les espaces de fin (padding FAT 8.3).
-fn decode_ascii_trim(bytes: &[u8]) -> String {
+/// Checksum du nom court (11 octets bruts) tel que stocké dans chaque entrée LFN.
+pub(crate) fn lfn_checksum(short_name: &[u8]) -> u8 {
+    let mut sum: u8 = 0;
+    for &b in &short_name[..11] {
+        sum = (sum >> 1).wrapping_add((sum & 1) << 7).wrapping_add(b);
+    }
+    sum
+}
+
+/// Indique si une entrée brute de 32 octets est une entrée LFN (non supprimée).
+pub(crate) fn is_lfn_entry(entry: &[u8]) -> bool {
+    entry.len() >= 32 && entry[0] != 0xE5 && entry[11] & 0x3F == ATTR_LFN
+}
+
+/// Nombre maximal d’entrées LFN dans une séquence (l’ordinal tient sur 5 bits).
+const MAX_LFN_ENTRIES: usize = 0x1F;
+
+/// Accumule une séquence d’entrées LFN pendant le parcours d’un répertoire.
+///
+/// Sur disque, les entrées LFN sont rangées de la dernière (ordinal | `0x40`)
+/// à la première (ordinal 1), puis vient l’entrée courte.
+/// Toute incohérence (ordinal inattendu, checksum différent) invalide la séquence :
+/// l’entrée courte sera alors exposée sans nom long, comme le fait Linux.
+///
+/// Les tampons sont de taille fixe : un parcours de répertoire n’alloue rien
+/// tant qu’on ne construit pas de [`DirEntry`].
+#[derive(Debug)]
+pub(crate) struct LfnCollector {
+    units: [u16; MAX_LFN_ENTRIES * LFN_CHARS_PER_ENTRY],
+    /// Nombre de caractères de la séquence en cours (13 par entrée).
+    len: usize,
+    /// Offsets (dans l’image) des entrées LFN de la séquence, ordre disque.
+    offsets: [usize; MAX_LFN_ENTRIES],
+    count: usize,
+    /// Prochain ordinal attendu (0 = rien en cours ou séquence terminée).
+    next_ord: u8,
+    checksum: u8,
+    /// Vrai si une séquence complète (jusqu’à l’ordinal 1) a été lue.
+    complete: bool,
+}
+
+impl LfnCollector {
+    /// Crée un collecteur vide.
+    pub(crate) fn new() -> Self {
+        Self {
+            units: [0; MAX_LFN_ENTRIES * LFN_CHARS_PER_ENTRY],
+            len: 0,
+            offsets: [0; MAX_LFN_ENTRIES],
+            count: 0,
+            next_ord: 0,
+            checksum: 0,
+            complete: false,
+        }
+    }
+
+    /// Abandonne la séquence en cours.
+    pub(crate) fn reset(&mut self) {
+        self.len = 0;
+        self.count = 0;
+        self.next_ord = 0;
+        self.checksum = 0;
+        self.complete = false;
+    }
+
+    /// Ajoute une entrée LFN brute (32 octets), lue à `offset`, à la séquence.
+    pub(crate) fn push(&mut self, entry: &[u8], offset: usize) {
+        let ord = entry[0];
+        let seq = ord & 0x1F;
+
+        if ord & 0x40 != 0 {
+            // Dernière entrée logique = première sur disque : nouvelle séquence.
+            self.reset();
+            if seq == 0 {
+                return;
+            }
+            self.len = seq as usize * LFN_CHARS_PER_ENTRY;
+            self.units[..self.len].fill(0xFFFF);
+            self.checksum = entry[13];
+        } else if self.next_ord == 0 || seq != self.next_ord || entry[13] != self.checksum {
+            self.reset();
+            return;
+        }
+
+        let base = (seq as usize - 1) * LFN_CHARS_PER_ENTRY;
+        for (i, &off) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            self.units[base + i] = u16::from_le_bytes([entry[off], entry[off + 1]]);
+        }
+
+        // Les ordinaux décroissent : au plus `MAX_LFN_ENTRIES` entrées par séquence
+        self.offsets[self.count] = offset;
+        self.count += 1;
+        self.next_ord = seq - 1;
+        self.complete = self.next_ord == 0;
+    }
+
+    /// Termine la séquence sur l’entrée courte `short_entry`.
+    ///
+    /// Retourne le nom long et les offsets de ses entrées si la séquence est complète
+    /// et que son checksum correspond au nom court.
+    /// Le collecteur est remis à zéro dans tous les cas.
+    pub(crate) fn finish(&mut self, short_entry: &[u8]) -> Option<(String, Vec<usize>)> {
+        let result = if self.belongs_to(short_entry) {
+            decode_ucs2(&self.units[..self.len]).map(|name| (name, self.offsets[..self.count].to_vec()))
+        } else {
+            None
+        };
+        self.reset();
+        result
+    }
+
+    /// Indique si la séquence en cours donne à `short_entry` le nom long `name`
+    /// (sans casse), sans rien allouer. Le collecteur n’est pas remis à zéro.
+    pub(crate) fn matches(&self, short_entry: &[u8], name: &str) -> bool {
+        if !self.belongs_to(short_entry) {
+            return false;
+        }
+        let units = trim_ucs2(&self.units[..self.len]);
+        !units.is_empty() && eq_ignore_case_chars(ucs2_chars(units), name)
+    }
+
+    /// Séquence complète et checksum égal à celui du nom court de `short_entry`.
+    fn belongs_to(&self, short_entry: &[u8]) -> bool {
+        self.complete && lfn_checksum(&short_entry[0..11]) == self.checksum
+    }
+}
+
+/// Indique si le nom court brut de `entry` (11 octets) s’écrit `name` (sans casse).
+///
+/// Même résultat que [`DirEntry::matches_name`] sur le nom court reconstitué,
+/// sans construire de `String`.
+pub(crate) fn short_name_matches(entry: &[u8], name: &str) -> bool {
+    let base = trim_padding(&entry[0..8]);
+    let ext = trim_padding(&entry[8..11]);
+    let dot: &[u8] = if ext.is_empty() { b"" } else { b"." };
+    let chars = base.iter().chain(dot).chain(ext).map(|&b| b as char);
+    eq_ignore_case_chars(chars, name)
+}
+
+/// Construit la séquence d’entrées LFN (ordre disque) pour `long`,
+/// rattachée au nom court brut `short` (11 octets) via son checksum.
+///
+/// L’appelant doit avoir validé `long` (voir `is_valid_long_name`).
+pub(crate) fn lfn_entries(long: &str, short: &[u8; 11]) -> Vec<[u8; 32]> {
+    let units: Vec<u16> = long.encode_utf16().collect();
+    let count = units.len().div_ceil(LFN_CHARS_PER_ENTRY);
+    let sum = lfn_checksum(short);
+
+    let mut out = Vec::with_capacity(count);
+    for seq in (1..=count).rev() {
+        let mut e = [0u8; 32];
+        e[0] = seq as u8 | if seq == count { 0x40 } else { 0 };
+        e[11] = ATTR_LFN;
+        e[13] = sum;
+
+        for (i, &off) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            let idx = (seq - 1) * LFN_CHARS_PER_ENTRY + i;
+            // Le nom est terminé par 0x0000 puis complété par 0xFFFF
+            let u = match idx.cmp(&units.len()) {
+                core::cmp::Ordering::Less => units[idx],
+                core::cmp::Ordering::Equal => 0x0000,
+                core::cmp::Ordering::Greater => 0xFFFF,
+            };
+            e[off..off + 2].copy_from_slice(&u.to_le_bytes());
+        }
+        out.push(e);
+    }
+    out
+}
+
+/// Indique si `name` est acceptable comme nom long VFAT.
+///
+/// Règles : 1 à 255 unités UTF-16, pas de caractère de contrôle,
+/// aucun de `"*/:<>?\|`, pas `.`/`..`, pas de point ou d’espace final.
+pub(crate) fn is_valid_long_name(name: &str) -> bool {
+    if name.is_empty() || name == "." || name == ".." {
+        return false;
+    }
+    if name.encode_utf16().count() > 255 {
+        return false;
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return false;
+    }
+    name.chars()
+        .all(|c| c >= ' ' && !matches!(c, '"' | '*' | '/' | ':' | '<' | '>' | '?' | '\\' | '|'))
+}
+
+/// Décode un nom UCS-2/UTF-16 (terminé par `0x0000`, complété par `0xFFFF`).
+fn decode_ucs2(units: &[u16]) -> Option<String> {
+    let units = trim_ucs2(units);
+    if units.is_empty() {
+        return None;
+    }
+    Some(ucs2_chars(units).collect())
+}
+
+/// Caractères utiles d’un nom UCS-2 : jusqu’au premier `0x0000` ou `0xFFFF`.
+fn trim_ucs2(units: &[u16]) -> &[u16] {
+    let end = units
+        .iter()
+        .position(|&u| u == 0x0000 || u == 0xFFFF)
+        .unwrap_or(units.len());
+    &units[..end]
+}
+
+/// Caractères d’un nom UTF-16 (un caractère invalide devient `U+FFFD`).
+fn ucs2_chars(units: &[u16]) -> impl Iterator<Item = char> + '_ {
+    core::char::decode_utf16(units.iter().copied())
+        .map(|ch| ch.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+}
+
+/// Comparaison de noms insensible à la casse (Unicode simple, suffisant pour les LFN).
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    eq_ignore_case_chars(a.chars(), b)
+}
+
+/// Comme `eq_ignore_case`, avec un nom donné caractère par caractère.
+fn eq_ignore_case_chars(a: impl Iterator<Item = char>, b: &str) -> bool {
+    a.flat_map(char::to_uppercase)
+        .eq(b.chars().flat_map(char::to_uppercase))
+}
+
+/// Octets d’un champ 8.3 sans les espaces de fin (padding).
+fn trim_padding(bytes: &[u8]) -> &[u8] {
     let mut end = bytes.len();
     while end > 0 && bytes[end - 1] == b' ' {
         end -= 1;
     }
+    &bytes[..end]
+}
 
-    let mut s = String::with_capacity(end);
-    for &b in &bytes[..end] {
-        s.push(b as char);
-    }
-    s
+/// Décodage ASCII simple en supprimant les espaces de fin (padding FAT 8.3).
+fn decode_ascii_trim(bytes: &[u8]) -> String {
+    trim_padding(bytes).iter().map(|&b| b as char).collect()
 }
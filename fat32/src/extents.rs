@@ -0,0 +1,210 @@
+//! Index des plages de clusters libres (“extents”).
+//!
+//! L’allocateur ne prend plus les N premiers clusters libres dans l’ordre de la FAT :
+//! il cherche la plus petite plage libre qui contient tout le fichier (best fit),
+//! et sinon compose la chaîne avec le moins de plages possible (les plus grandes d’abord).
+//! Un fichier écrit d’un bloc reste ainsi contigu sur le disque.
+//!
+//! Entre deux plages aussi bonnes l’une que l’autre, l’indice “prochain cluster libre”
+//! de FSInfo départage : la première plage à partir de l’indice gagne (en repartant
+//! du début du volume après la fin), comme chez les autres systèmes.
+//!
+//! L’index est construit une fois depuis la FAT, puis tenu à jour à chaque écriture
+//! d’entrée de FAT (un cluster qui passe à 0 est rendu, un autre est retiré).
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Plage de clusters libres consécutifs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Extent {
+    /// Premier cluster de la plage.
+    pub(crate) start: u32,
+    /// Nombre de clusters.
+    pub(crate) len: u32,
+}
+
+impl Extent {
+    fn end(self) -> u32 {
+        self.start + self.len
+    }
+
+    /// Distance depuis l’indice `hint`, en tournant : une plage qui contient `hint`
+    /// ou qui vient après est plus proche que toutes celles d’avant.
+    fn distance_from(self, hint: u32) -> u32 {
+        (self.end() - 1).wrapping_sub(hint)
+    }
+}
+
+/// Plages libres triées par cluster de départ, jamais adjacentes (toujours fusionnées).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FreeExtents {
+    extents: Vec<Extent>,
+}
+
+impl FreeExtents {
+    /// Ajoute un cluster libre en fin d’index (construction dans l’ordre croissant).
+    pub(crate) fn push(&mut self, cluster: u32) {
+        match self.extents.last_mut() {
+            Some(last) if last.end() == cluster => last.len += 1,
+            _ => self.extents.push(Extent {
+                start: cluster,
+                len: 1,
+            }),
+        }
+    }
+
+    /// Nombre total de clusters libres.
+    pub(crate) fn free_count(&self) -> u32 {
+        self.extents.iter().map(|e| e.len).sum()
+    }
+
+    /// Index de la première plage qui commence après `cluster`.
+    fn after(&self, cluster: u32) -> usize {
+        self.extents.partition_point(|e| e.start <= cluster)
+    }
+
+    /// `true` si `cluster` est dans une plage libre.
+    pub(crate) fn contains(&self, cluster: u32) -> bool {
+        let i = self.after(cluster);
+        i > 0 && cluster < self.extents[i - 1].end()
+    }
+
+    /// `true` si les `len` clusters à partir de `start` sont tous libres.
+    pub(crate) fn covers(&self, start: u32, len: u32) -> bool {
+        let i = self.after(start);
+        i > 0 && start as u64 + len as u64 <= self.extents[i - 1].end() as u64
+    }
+
+    /// Rend un cluster à l’index (fusion avec les plages voisines).
+    pub(crate) fn insert(&mut self, cluster: u32) {
+        if self.contains(cluster) {
+            return;
+        }
+
+        let i = self.after(cluster);
+        let joins_prev = i > 0 && self.extents[i - 1].end() == cluster;
+        let joins_next = i < self.extents.len() && self.extents[i].start == cluster + 1;
+
+        match (joins_prev, joins_next) {
+            (true, true) => {
+                self.extents[i - 1].len += 1 + self.extents[i].len;
+                self.extents.remove(i);
+            }
+            (true, false) => self.extents[i - 1].len += 1,
+            (false, true) => {
+                self.extents[i].start -= 1;
+                self.extents[i].len += 1;
+            }
+            (false, false) => self.extents.insert(
+                i,
+                Extent {
+                    start: cluster,
+                    len: 1,
+                },
+            ),
+        }
+    }
+
+    /// Retire un cluster de l’index (coupe la plage qui le contient si besoin).
+    pub(crate) fn remove(&mut self, cluster: u32) {
+        if !self.contains(cluster) {
+            return;
+        }
+
+        let i = self.after(cluster) - 1;
+        let e = self.extents[i];
+        if e.len == 1 {
+            self.extents.remove(i);
+        } else if cluster == e.start {
+            self.extents[i].start += 1;
+            self.extents[i].len -= 1;
+        } else if cluster == e.end() - 1 {
+            self.extents[i].len -= 1;
+        } else {
+            self.extents[i].len = cluster - e.start;
+            self.extents.insert(
+                i + 1,
+                Extent {
+                    start: cluster + 1,
+                    len: e.end() - cluster - 1,
+                },
+            );
+        }
+    }
+
+    /// Début de la plus petite plage d’au moins `needed` clusters; à taille égale,
+    /// la première à partir de l’indice `hint`.
+    pub(crate) fn best_fit(&self, needed: u32, hint: u32) -> Option<u32> {
+        self.extents
+            .iter()
+            .filter(|e| e.len >= needed)
+            .min_by_key(|e| (e.len, e.distance_from(hint)))
+            .map(|e| e.start)
+    }
+
+    /// Choisit `needed` clusters dans le moins de plages possible.
+    ///
+    /// Une plage qui contient tout (best fit) est prise en priorité; sinon les plus grandes
+    /// plages sont prises en entier, et la dernière partiellement. À taille égale, `hint`
+    /// départage. Les morceaux sont retournés dans l’ordre du disque, pour que la chaîne
+    /// avance toujours.
+    ///
+    /// Retour : `None` s’il n’y a pas assez de clusters libres.
+    pub(crate) fn pick(&self, needed: u32, hint: u32) -> Option<Vec<Extent>> {
+        if let Some(start) = self.best_fit(needed, hint) {
+            return Some(alloc::vec![Extent { start, len: needed }]);
+        }
+        if self.free_count() < needed {
+            return None;
+        }
+
+        let mut by_size: Vec<Extent> = self.extents.clone();
+        by_size.sort_by_key(|e| (core::cmp::Reverse(e.len), e.distance_from(hint)));
+
+        let mut picked = Vec::new();
+        let mut left = needed;
+        for e in by_size {
+            let take = core::cmp::min(e.len, left);
+            picked.push(Extent {
+                start: e.start,
+                len: take,
+            });
+            left -= take;
+            if left == 0 {
+                break;
+            }
+        }
+
+        picked.sort_by_key(|e| e.start);
+        Some(picked)
+    }
+}
+
+/// Fragmentation d’un fichier : nombre de clusters et de plages contiguës de sa chaîne.
+///
+/// Un fichier contigu a `extents == 1` (ou `0` s’il est vide).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fragmentation {
+    /// Nombre de clusters de la chaîne.
+    pub clusters: u32,
+    /// Nombre de plages de clusters consécutifs (1 + nombre de “sauts” de la chaîne).
+    pub extents: u32,
+}
+
+impl Fragmentation {
+    /// Calcule la fragmentation d’une chaîne de clusters.
+    pub(crate) fn of_chain(chain: &[u32]) -> Self {
+        let jumps = chain.windows(2).filter(|w| w[1] != w[0] + 1).count() as u32;
+        Self {
+            clusters: chain.len() as u32,
+            extents: if chain.is_empty() { 0 } else { jumps + 1 },
+        }
+    }
+
+    /// `true` si le fichier tient dans une seule plage (ou est vide).
+    pub fn is_contiguous(&self) -> bool {
+        self.extents <= 1
+    }
+}
@@ -0,0 +1,138 @@
+//! Type de FAT (12, 16 ou 32 bits) et encodage des entrées de la table.
+//!
+//! Le type ne se lit pas dans un champ : il se déduit du nombre de clusters de données
+//! (moins de 4085 : FAT12, moins de 65525 : FAT16, sinon FAT32).
+//!
+//! Le reste du crate manipule des valeurs d’entrée “normalisées” sur 28 bits :
+//! une fin de chaîne vaut toujours `0x0FFFFFFF` et un cluster défectueux `0x0FFFFFF7`,
+//! quel que soit le type. La conversion vers la largeur réelle se fait ici.
+
+use crate::FAT32_EOC;
+
+/// Valeur normalisée d’un cluster marqué défectueux.
+pub(crate) const BAD_CLUSTER: u32 = 0x0FFF_FFF7;
+
+/// Nombre de clusters à partir duquel un volume est FAT16.
+const FAT16_MIN_CLUSTERS: u32 = 4085;
+/// Nombre de clusters à partir duquel un volume est FAT32.
+pub(crate) const FAT32_MIN_CLUSTERS: u32 = 65525;
+
+/// Variante de FAT d’un volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    /// Entrées de 12 bits, deux entrées packées sur 3 octets.
+    Fat12,
+    /// Entrées de 16 bits.
+    Fat16,
+    /// Entrées de 32 bits dont 28 utiles.
+    Fat32,
+}
+
+impl FatType {
+    /// Déduit le type du nombre de clusters de données.
+    pub(crate) fn from_cluster_count(count: u32) -> Self {
+        if count < FAT16_MIN_CLUSTERS {
+            FatType::Fat12
+        } else if count < FAT32_MIN_CLUSTERS {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        }
+    }
+
+    /// Masque des bits utiles d’une entrée.
+    fn mask(self) -> u32 {
+        match self {
+            FatType::Fat12 => 0x0FFF,
+            FatType::Fat16 => 0xFFFF,
+            FatType::Fat32 => 0x0FFF_FFFF,
+        }
+    }
+
+    /// Bit “démonté proprement” de l’entrée FAT[1] : à 1 quand le volume est propre,
+    /// remis à 0 pendant qu’il est monté en écriture. FAT12 n’en a pas.
+    pub(crate) fn clean_shutdown_bit(self) -> Option<u32> {
+        match self {
+            FatType::Fat12 => None,
+            FatType::Fat16 => Some(0x8000),
+            FatType::Fat32 => Some(0x0800_0000),
+        }
+    }
+
+    /// Nombre d’entrées que peut contenir une FAT de `fat_bytes` octets.
+    pub(crate) fn entries_in(self, fat_bytes: u64) -> u64 {
+        match self {
+            FatType::Fat12 => fat_bytes * 2 / 3,
+            FatType::Fat16 => fat_bytes / 2,
+            FatType::Fat32 => fat_bytes / 4,
+        }
+    }
+
+    /// Offset (en octets, dans une copie de la FAT) et nombre d’octets à lire
+    /// pour l’entrée `cluster`.
+    ///
+    /// En FAT12, une entrée occupe 1,5 octet : on lit les 2 octets qui la contiennent.
+    pub(crate) fn entry_location(self, cluster: u32) -> (usize, usize) {
+        let c = cluster as usize;
+        match self {
+            FatType::Fat12 => (c + c / 2, 2),
+            FatType::Fat16 => (c * 2, 2),
+            FatType::Fat32 => (c * 4, 4),
+        }
+    }
+
+    /// Décode l’entrée `cluster` depuis les octets lus à `entry_location`,
+    /// et la normalise (fin de chaîne, cluster défectueux).
+    pub(crate) fn decode(self, cluster: u32, raw: &[u8]) -> u32 {
+        let v = match self {
+            FatType::Fat12 => {
+                let pair = u16::from_le_bytes([raw[0], raw[1]]) as u32;
+                if cluster & 1 == 1 {
+                    pair >> 4
+                } else {
+                    pair & 0x0FFF
+                }
+            }
+            FatType::Fat16 => u16::from_le_bytes([raw[0], raw[1]]) as u32,
+            FatType::Fat32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) & 0x0FFF_FFFF,
+        };
+
+        let mask = self.mask();
+        if v >= mask - 7 {
+            FAT32_EOC
+        } else if v == mask - 8 {
+            BAD_CLUSTER
+        } else {
+            v
+        }
+    }
+
+    /// Écrit la valeur normalisée `value` de l’entrée `cluster` dans `raw`
+    /// (les octets lus à `entry_location`).
+    ///
+    /// En FAT12, le demi-octet de l’entrée voisine est conservé.
+    pub(crate) fn encode(self, cluster: u32, value: u32, raw: &mut [u8]) {
+        let mask = self.mask();
+        let v = if value >= 0x0FFF_FFF8 {
+            mask
+        } else if value == BAD_CLUSTER {
+            mask - 8
+        } else {
+            value & mask
+        };
+
+        match self {
+            FatType::Fat12 => {
+                let pair = u16::from_le_bytes([raw[0], raw[1]]);
+                let pair = if cluster & 1 == 1 {
+                    (pair & 0x000F) | ((v as u16) << 4)
+                } else {
+                    (pair & 0xF000) | v as u16
+                };
+                raw[0..2].copy_from_slice(&pair.to_le_bytes());
+            }
+            FatType::Fat16 => raw[0..2].copy_from_slice(&(v as u16).to_le_bytes()),
+            FatType::Fat32 => raw[0..4].copy_from_slice(&v.to_le_bytes()),
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! Handle de fichier “streaming” sur un volume FAT32.
+//!
+//! Un [`File`] garde la position courante et le dernier cluster atteint dans la chaîne,
+//! ce qui permet de lire ou d’écrire un gros fichier morceau par morceau
+//! sans jamais le charger en entier (`read_file` / `write_file_by_path` travaillent
+//! sur tout le contenu d’un coup).
+//!
+//! Avec la feature `std`, `File` implémente `std::io::Read`, `Write` et `Seek`.
+
+extern crate alloc;
+
+use crate::{BlockDevice, Fat32Mut, FatError, LoopGuard, FAT32_EOC};
+
+/// Taille maximale d’un fichier FAT32 (le champ taille fait 32 bits).
+const MAX_FILE_SIZE: u64 = u32::MAX as u64;
+
+/// Position de départ d’un `seek` (équivalent `no_std` de `std::io::SeekFrom`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// Offset absolu depuis le début du fichier.
+    Start(u64),
+    /// Offset relatif à la fin du fichier.
+    End(i64),
+    /// Offset relatif à la position courante.
+    Current(i64),
+}
+
+/// Fichier ouvert en lecture/écriture sur un [`Fat32Mut`].
+///
+/// Obtenu via [`Fat32Mut::open`] ou [`Fat32Mut::create`]. Chaque écriture met à jour
+/// l’entrée de répertoire (taille, premier cluster), il n’y a rien à “fermer”.
+/// Dans [`Fat32Mut::replace_file`], le fichier n’a pas encore d’entrée : sa chaîne n’est
+/// reliée au répertoire qu’une fois entièrement écrite.
+#[derive(Debug)]
+pub struct File<'a, D: BlockDevice> {
+    fs: &'a mut Fat32Mut<D>,
+    /// Offset (en octets) de l’entrée courte du fichier dans l’image
+    /// (`None` tant que la chaîne n’est reliée à aucune entrée).
+    entry_offset: Option<usize>,
+    first_cluster: u32,
+    size: u32,
+    pos: u64,
+    /// Dernier cluster atteint : (index dans la chaîne, numéro de cluster).
+    cursor: Option<(u32, u32)>,
+    /// `false` pour une entrée en lecture seule (sans `MountOptions::force`).
+    writable: bool,
+}
+
+impl<'a, D: BlockDevice> File<'a, D> {
+    pub(crate) fn new(
+        fs: &'a mut Fat32Mut<D>,
+        entry_offset: usize,
+        first_cluster: u32,
+        size: u32,
+        writable: bool,
+    ) -> Self {
+        Self {
+            fs,
+            entry_offset: Some(entry_offset),
+            first_cluster,
+            size,
+            pos: 0,
+            cursor: None,
+            writable,
+        }
+    }
+
+    /// Fichier vide sans entrée de répertoire : les écritures construisent une chaîne
+    /// que l’appelant relie ensuite (voir [`File::into_chain`]).
+    pub(crate) fn detached(fs: &'a mut Fat32Mut<D>) -> Self {
+        Self {
+            fs,
+            entry_offset: None,
+            first_cluster: 0,
+            size: 0,
+            pos: 0,
+            cursor: None,
+            writable: true,
+        }
+    }
+
+    /// Premier cluster (0 si vide) et taille du fichier.
+    pub(crate) fn into_chain(self) -> (u32, u32) {
+        (self.first_cluster, self.size)
+    }
+
+    /// Taille actuelle du fichier, en octets.
+    pub fn len(&self) -> u64 {
+        self.size as u64
+    }
+
+    /// `true` si le fichier est vide.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Position courante (en octets depuis le début du fichier).
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Lit à partir de la position courante et avance d’autant.
+    ///
+    /// Paramètres :
+    /// - `buf` : destination; on lit au plus `buf.len()` octets.
+    ///
+    /// Retour : `Result<usize, FatError>` avec le nombre d’octets lus (`0` en fin de fichier).
+    ///
+    /// Erreurs :
+    /// - `CorruptChain` si la chaîne est plus courte que la taille annoncée
+    ///   ou passe par un cluster libre ou défectueux.
+    /// - `OutOfBounds`/`Io` si le périphérique échoue.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let mut file = rw.open("/HELLO.TXT")?;
+    /// let mut buf = [0u8; 64];
+    /// let n = file.read(&mut buf)?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, FatError> {
+        if self.pos >= self.len() || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let n = core::cmp::min(buf.len() as u64, self.len() - self.pos) as usize;
+        let cs = self.fs.cluster_size() as u64;
+        let mut done = 0usize;
+
+        while done < n {
+            let pos = self.pos + done as u64;
+            let cl = self.cluster_at((pos / cs) as u32)?;
+            let in_cl = (pos % cs) as usize;
+            let take = core::cmp::min(cs as usize - in_cl, n - done);
+
+            let off = self.fs.cluster_to_offset(cl)? + in_cl;
+            self.fs.read_bytes(off, &mut buf[done..done + take])?;
+            done += take;
+        }
+
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Écrit `data` à la position courante et avance d’autant.
+    ///
+    /// La chaîne de clusters est étendue si besoin. Si la position est après
+    /// la fin du fichier, le trou est rempli de zéros.
+    ///
+    /// Paramètres :
+    /// - `data` : octets à écrire.
+    ///
+    /// Retour : `Result<usize, FatError>` avec le nombre d’octets écrits (`data.len()`).
+    ///
+    /// Erreurs :
+    /// - `ReadOnly` si le fichier ou le volume est en lecture seule.
+    /// - `FileTooLarge` si le fichier dépasserait 4 Gio - 1.
+    /// - `NoSpaceLeft` si la FAT ne contient pas assez de clusters libres.
+    /// - `InvalidCluster`/`OutOfBounds`/`Io` si l’image est incohérente ou le périphérique échoue.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError, SeekFrom};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let mut log = rw.open("/LOG.TXT")?;
+    /// log.seek(SeekFrom::End(0))?;
+    /// log.write(b"boot ok\n")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn write(&mut self, data: &[u8]) -> Result<usize, FatError> {
+        self.check_writable()?;
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let end = self.pos + data.len() as u64;
+        if end > MAX_FILE_SIZE {
+            return Err(FatError::FileTooLarge);
+        }
+
+        // Trou après la fin du fichier : rempli de zéros par set_len
+        if self.pos > self.len() {
+            self.set_len(self.pos)?;
+        }
+
+        // FAT, données, puis entrée : une coupure laisse au pire une chaîne plus longue
+        // que la taille, ou des clusters perdus
+        let cs = self.fs.cluster_size() as u64;
+        self.ensure_clusters(end.div_ceil(cs) as u32)?;
+        self.fs.write_barrier()?;
+        self.write_in_chain(self.pos, data)?;
+        self.fs.write_barrier()?;
+
+        self.pos = end;
+        if end > self.len() {
+            self.size = end as u32;
+        }
+        self.store_entry()?;
+        Ok(data.len())
+    }
+
+    /// Déplace la position courante.
+    ///
+    /// Une position après la fin est autorisée : une écriture y crée un trou rempli de zéros.
+    ///
+    /// Erreurs :
+    /// - `OutOfBounds` si la position résultante serait négative.
+    ///
+    /// Panics : aucune.
+    pub fn seek(&mut self, from: SeekFrom) -> Result<u64, FatError> {
+        let (base, delta) = match from {
+            SeekFrom::Start(p) => {
+                self.pos = p;
+                return Ok(p);
+            }
+            SeekFrom::End(d) => (self.len(), d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+
+        self.pos = base
+            .checked_add_signed(delta)
+            .ok_or(FatError::OutOfBounds)?;
+        Ok(self.pos)
+    }
+
+    /// Tronque ou agrandit le fichier à `new_len` octets.
+    ///
+    /// En réduction, la chaîne est coupée (nouvel EOC) et les clusters en trop sont libérés.
+    /// En agrandissement, les nouveaux octets valent zéro. La position courante ne change pas.
+    ///
+    /// Erreurs :
+    /// - `ReadOnly` si le fichier ou le volume est en lecture seule.
+    /// - `FileTooLarge` si `new_len` dépasse 4 Gio - 1.
+    /// - `NoSpaceLeft` si la FAT ne contient pas assez de clusters libres.
+    /// - `InvalidCluster`/`OutOfBounds`/`Io` si l’image est incohérente ou le périphérique échoue.
+    ///
+    /// Panics : aucune.
+    pub fn set_len(&mut self, new_len: u64) -> Result<(), FatError> {
+        self.check_writable()?;
+        if new_len > MAX_FILE_SIZE {
+            return Err(FatError::FileTooLarge);
+        }
+
+        let cs = self.fs.cluster_size() as u64;
+        let keep = new_len.div_ceil(cs) as u32;
+
+        if new_len < self.len() {
+            // L’entrée d’abord : une chaîne plus longue que la taille reste valide
+            let first = self.first_cluster;
+            if keep == 0 {
+                self.first_cluster = 0;
+                self.cursor = None;
+            }
+            self.size = new_len as u32;
+            self.store_entry()?;
+            self.fs.write_barrier()?;
+            return self.cut_chain(first, keep);
+        }
+
+        if new_len > self.len() {
+            self.ensure_clusters(keep)?;
+            self.fs.write_barrier()?;
+
+            // Les octets après l’ancienne taille peuvent contenir n’importe quoi
+            let zeros = alloc::vec![0u8; cs as usize];
+            let mut pos = self.len();
+            while pos < new_len {
+                let n = core::cmp::min(cs - pos % cs, new_len - pos) as usize;
+                self.write_in_chain(pos, &zeros[..n])?;
+                pos += n as u64;
+            }
+            self.fs.write_barrier()?;
+        }
+
+        self.size = new_len as u32;
+        self.store_entry()
+    }
+
+    /// Réserve des clusters consécutifs jusqu’à couvrir `len` octets, sans changer la taille.
+    ///
+    /// Les clusters réservés sont pris juste après la fin de la chaîne si c’est possible.
+    ///
+    /// Erreurs :
+    /// - `ReadOnly` si le fichier ou le volume est en lecture seule.
+    /// - `FileTooLarge` si `len` dépasse 4 Gio - 1.
+    /// - `NoSpaceLeft` s’il n’existe pas de plage libre contiguë assez longue.
+    ///
+    /// Panics : aucune.
+    pub fn preallocate(&mut self, len: u64) -> Result<(), FatError> {
+        self.check_writable()?;
+        if len > MAX_FILE_SIZE {
+            return Err(FatError::FileTooLarge);
+        }
+
+        let cs = self.fs.cluster_size() as u64;
+        let count = len.div_ceil(cs) as u32;
+        let tail = self.chain_tail()?;
+        let have = tail.map_or(0, |(i, _)| i + 1);
+        if have >= count {
+            return Ok(());
+        }
+
+        let near = tail.map(|(_, last)| last + 1);
+        let new = self.fs.alloc_contiguous((count - have) as usize, near)?;
+        self.link_tail(tail, new[0])?;
+        self.fs.write_barrier()?;
+        self.store_entry()
+    }
+
+    /// Écrit sur le périphérique les secteurs gardés en cache (voir [`Fat32Mut::flush`]).
+    pub fn flush(&mut self) -> Result<(), FatError> {
+        self.fs.flush()
+    }
+
+    /// Refuse d’écrire dans un fichier en lecture seule.
+    fn check_writable(&self) -> Result<(), FatError> {
+        if self.writable {
+            Ok(())
+        } else {
+            Err(FatError::ReadOnly)
+        }
+    }
+
+    // ---------- internes ----------
+
+    /// Numéro du cluster d’index `index` dans la chaîne du fichier.
+    ///
+    /// On repart du dernier cluster atteint si possible, sinon du début de la chaîne.
+    fn cluster_at(&mut self, index: u32) -> Result<u32, FatError> {
+        let (mut i, mut cl) = match self.cursor {
+            Some((i, cl)) if i <= index => (i, cl),
+            _ => {
+                if self.first_cluster < 2 {
+                    return Err(FatError::InvalidCluster);
+                }
+                (0, self.first_cluster)
+            }
+        };
+
+        // `index` est borné par la taille du fichier : pas de boucle infinie possible
+        while i < index {
+            cl = self.fs.next_cluster(cl)?.ok_or(FatError::CorruptChain)?;
+            i += 1;
+        }
+
+        self.cursor = Some((i, cl));
+        Ok(cl)
+    }
+
+    /// Dernier cluster de la chaîne : (index, numéro), ou `None` si le fichier n’en a pas.
+    fn chain_tail(&mut self) -> Result<Option<(u32, u32)>, FatError> {
+        if self.first_cluster < 2 {
+            return Ok(None);
+        }
+
+        let (mut i, mut cl) = self.cursor.unwrap_or((0, self.first_cluster));
+        let mut guard = LoopGuard::new(cl, self.fs.bpb.cluster_count());
+        while let Some(next) = self.fs.next_cluster(cl)? {
+            guard.step(next)?;
+            cl = next;
+            i += 1;
+        }
+
+        self.cursor = Some((i, cl));
+        Ok(Some((i, cl)))
+    }
+
+    /// S’assure que la chaîne contient au moins `count` clusters (extension en fin de chaîne).
+    fn ensure_clusters(&mut self, count: u32) -> Result<(), FatError> {
+        let tail = self.chain_tail()?;
+        let have = tail.map_or(0, |(i, _)| i + 1);
+        if have >= count {
+            return Ok(());
+        }
+
+        let new = self.fs.alloc_chain((count - have) as usize)?;
+        self.link_tail(tail, new[0])
+    }
+
+    /// Raccroche une chaîne neuve qui commence à `first` après `tail` (ou en fait le début).
+    fn link_tail(&mut self, tail: Option<(u32, u32)>, first: u32) -> Result<(), FatError> {
+        match tail {
+            Some((_, last)) => self.fs.write_fat_entry_all(last, first)?,
+            None => {
+                self.first_cluster = first;
+                self.cursor = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ne garde que les `keep` premiers clusters de la chaîne qui commence à `first`
+    /// et libère le reste (toute la chaîne si `keep == 0`).
+    ///
+    /// L’entrée doit déjà porter la nouvelle taille (et le cluster 0 si `keep == 0`).
+    fn cut_chain(&mut self, first: u32, keep: u32) -> Result<(), FatError> {
+        if first < 2 {
+            return Ok(());
+        }
+
+        if keep == 0 {
+            return self.fs.free_chain(first);
+        }
+
+        let last = self.cluster_at(keep - 1)?;
+        let next = self.fs.read_fat_entry(last)?;
+        self.fs.write_fat_entry_all(last, FAT32_EOC)?;
+        if (2..0x0FFF_FFF8).contains(&next) {
+            self.fs.free_chain(next)?;
+        }
+        Ok(())
+    }
+
+    /// Écrit `data` à `pos` dans des clusters déjà alloués.
+    fn write_in_chain(&mut self, pos: u64, data: &[u8]) -> Result<(), FatError> {
+        let cs = self.fs.cluster_size() as u64;
+        let mut done = 0usize;
+
+        while done < data.len() {
+            let p = pos + done as u64;
+            let cl = self.cluster_at((p / cs) as u32)?;
+            let in_cl = (p % cs) as usize;
+            let take = core::cmp::min(cs as usize - in_cl, data.len() - done);
+
+            let off = self.fs.cluster_to_offset(cl)? + in_cl;
+            self.fs.write_bytes(off, &data[done..done + take])?;
+            done += take;
+        }
+        Ok(())
+    }
+
+    /// Reporte taille et premier cluster dans l’entrée de répertoire (s’il y en a une).
+    fn store_entry(&mut self) -> Result<(), FatError> {
+        let Some(offset) = self.entry_offset else {
+            return Ok(());
+        };
+        self.fs.set_entry_cluster(offset, self.first_cluster)?;
+        self.fs.write_bytes(offset + 28, &self.size.to_le_bytes())?;
+        self.fs.touch_entry(offset)
+    }
+}
+
+#[cfg(feature = "std")]
+fn to_io_error(e: FatError) -> std::io::Error {
+    use std::io::ErrorKind;
+
+    let kind = match e {
+        FatError::PathNotFound => ErrorKind::NotFound,
+        FatError::InvalidName => ErrorKind::InvalidInput,
+        FatError::OutOfBounds => ErrorKind::UnexpectedEof,
+        FatError::NoSpaceLeft => ErrorKind::StorageFull,
+        FatError::FileTooLarge => ErrorKind::FileTooLarge,
+        FatError::ReadOnly => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Other,
+    };
+    std::io::Error::new(kind, alloc::format!("{e:?}"))
+}
+
+#[cfg(feature = "std")]
+impl<D: BlockDevice> std::io::Read for File<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        File::read(self, buf).map_err(to_io_error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<D: BlockDevice> std::io::Write for File<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        File::write(self, buf).map_err(to_io_error)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        File::flush(self).map_err(to_io_error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<D: BlockDevice> std::io::Seek for File<'_, D> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        let from = match pos {
+            std::io::SeekFrom::Start(p) => SeekFrom::Start(p),
+            std::io::SeekFrom::End(d) => SeekFrom::End(d),
+            std::io::SeekFrom::Current(d) => SeekFrom::Current(d),
+        };
+        File::seek(self, from).map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))
+    }
+}
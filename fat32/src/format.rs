@@ -0,0 +1,281 @@
+//! Formatage d’un volume FAT32 neuf (l’équivalent de `mkfs.fat -F 32`).
+//!
+//! [`format`] occupe tout le périphérique et écrit :
+//! - le secteur de boot (BPB) au secteur 0 et sa copie de secours au secteur 6,
+//! - le secteur FSInfo au secteur 1 (copie au secteur 7),
+//! - chaque copie de la FAT, avec les deux entrées réservées (média, fin de chaîne)
+//!   et la chaîne de la racine,
+//! - une racine vide (cluster 2), avec l’entrée “nom de volume” s’il y en a un.
+//!
+//! La taille de cluster suit la table de Microsoft selon la capacité, et la taille
+//! de FAT est calculée avec la formule de la spécification (elle surestime de peu,
+//! jamais l’inverse).
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use crate::fat_type::FAT32_MIN_CLUSTERS;
+use crate::fs_info::FsInfo;
+use crate::{
+    is_short_name_char, BlockDevice, FatError, FAT32_EOC, FAT32_MAX_CLUSTERS, SECTOR_SIZE,
+};
+
+/// Secteurs réservés (boot, FSInfo, copies de secours), comme `mkfs.fat`.
+const RESERVED_SECTORS: u32 = 32;
+/// Secteur FSInfo.
+const FS_INFO_SECTOR: u64 = 1;
+/// Copie de secours du secteur de boot (et FSInfo juste après).
+const BACKUP_BOOT_SECTOR: u64 = 6;
+/// Descripteur de média “disque fixe”, recopié dans l’entrée 0 de la FAT.
+const MEDIA_FIXED: u8 = 0xF8;
+/// Premier cluster de la racine.
+const ROOT_CLUSTER: u32 = 2;
+/// Secteurs effacés par écriture pendant le formatage.
+const ZERO_BLOCK_SECTORS: u64 = 8;
+/// Nom de volume qui signifie “pas de nom” (aucune entrée dans la racine).
+const NO_NAME: &[u8; 11] = b"NO NAME    ";
+
+/// Paramètres de [`format`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Nom de volume (11 caractères au plus, mis en majuscules); `"NO NAME"` par défaut.
+    pub label: String,
+    /// Secteurs par cluster (puissance de deux jusqu’à 128), ou `None` pour le choisir
+    /// selon la capacité.
+    pub sectors_per_cluster: Option<u8>,
+    /// Nombre de copies de la FAT (2 par défaut).
+    pub num_fats: u8,
+    /// Numéro de série du volume (`0` par défaut; la CLI le tire de l’horloge).
+    pub volume_id: u32,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            label: String::from("NO NAME"),
+            sectors_per_cluster: None,
+            num_fats: 2,
+            volume_id: 0,
+        }
+    }
+}
+
+/// Géométrie calculée pour un volume.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    total_sectors: u32,
+    sectors_per_cluster: u8,
+    num_fats: u8,
+    sectors_per_fat: u32,
+    cluster_count: u32,
+}
+
+impl Layout {
+    /// Premier secteur de la zone de données (cluster 2).
+    fn data_start(&self) -> u64 {
+        RESERVED_SECTORS as u64 + self.num_fats as u64 * self.sectors_per_fat as u64
+    }
+}
+
+/// Formate `dev` en FAT32 sur toute sa capacité.
+///
+/// Tout ce qui se trouvait dans la zone réservée, les FAT et la racine est écrasé;
+/// le reste de la zone de données n’est pas effacé (les clusters sont libres dans la FAT).
+///
+/// Paramètres :
+/// - `dev` : périphérique à formater (`Vec<u8>`, `&mut [u8]`, fichier image...).
+/// - `options` : nom de volume, taille de cluster, nombre de FAT, numéro de série.
+///
+/// Retour : `Result<(), FatError>`; le volume se monte ensuite avec `Fat32Mut::from_device`.
+///
+/// Erreurs :
+/// - `InvalidName` si le nom de volume dépasse 11 caractères ou contient un caractère
+///   interdit dans un nom court.
+/// - `InvalidFatCount` si `num_fats` vaut 0.
+/// - `InvalidSectorsPerCluster` si la taille de cluster demandée n’est pas une puissance
+///   de deux entre 1 et 128.
+/// - `BufferTooSmall` si le périphérique ne peut pas contenir les 65525 clusters
+///   d’un volume FAT32 (~33 Mio avec des clusters de 512 octets).
+/// - `InvalidClusterCount` si la taille de cluster demandée donne trop de clusters.
+/// - `OutOfBounds`/`Io` si l’écriture échoue.
+///
+/// Panics : aucune.
+///
+/// Exemples :
+/// ```rust,no_run
+/// # use fat32_parser::{format, Fat32Mut, FatError, FormatOptions};
+/// let mut disk = vec![0u8; 64 * 1024 * 1024];
+/// let options = FormatOptions {
+///     label: "BOOT".into(),
+///     ..FormatOptions::default()
+/// };
+/// format(&mut disk, &options)?;
+/// let mut rw = Fat32Mut::new(&mut disk)?;
+/// rw.write_file_by_path("/HELLO.TXT", b"HELLO")?;
+/// # Ok::<(), FatError>(())
+/// ```
+pub fn format<D: BlockDevice + ?Sized>(
+    dev: &mut D,
+    options: &FormatOptions,
+) -> Result<(), FatError> {
+    let label = encode_label(&options.label)?;
+    let layout = compute_layout(dev.sector_count(), options)?;
+
+    // Zone réservée, FAT et racine : tout à zéro avant de poser les structures
+    // (par blocs de 4 Kio, pour rester léger sur le tas du noyau)
+    let zeros = alloc::vec![0u8; ZERO_BLOCK_SECTORS as usize * SECTOR_SIZE];
+    let end = layout.data_start() + layout.sectors_per_cluster as u64;
+    let mut lba = 0;
+    while lba < end {
+        let n = core::cmp::min(ZERO_BLOCK_SECTORS, end - lba) as usize;
+        dev.write_sectors(lba, &zeros[..n * SECTOR_SIZE])?;
+        lba += n as u64;
+    }
+
+    let boot = boot_sector(&layout, &label, options.volume_id);
+    dev.write_sector(0, &boot)?;
+    dev.write_sector(BACKUP_BOOT_SECTOR, &boot)?;
+
+    // La racine occupe déjà un cluster; l’allocation suivante commence au cluster 3
+    let fs_info = FsInfo {
+        offset: 0,
+        free_count: layout.cluster_count - 1,
+        next_free: ROOT_CLUSTER + 1,
+    }
+    .to_sector();
+    dev.write_sector(FS_INFO_SECTOR, &fs_info)?;
+    dev.write_sector(BACKUP_BOOT_SECTOR + FS_INFO_SECTOR, &fs_info)?;
+
+    // Entrées 0 (média), 1 (fin de chaîne, volume propre) et 2 (racine)
+    let mut fat = [0u8; SECTOR_SIZE];
+    fat[0..4].copy_from_slice(&(0x0FFF_FF00 | MEDIA_FIXED as u32).to_le_bytes());
+    fat[4..8].copy_from_slice(&FAT32_EOC.to_le_bytes());
+    fat[8..12].copy_from_slice(&FAT32_EOC.to_le_bytes());
+    for copy in 0..layout.num_fats as u64 {
+        dev.write_sector(
+            RESERVED_SECTORS as u64 + copy * layout.sectors_per_fat as u64,
+            &fat,
+        )?;
+    }
+
+    if &label != NO_NAME {
+        let mut root = [0u8; SECTOR_SIZE];
+        root[0..11].copy_from_slice(&label);
+        root[11] = 0x08;
+        dev.write_sector(layout.data_start(), &root)?;
+    }
+
+    dev.flush()
+}
+
+/// Nom de volume sur 11 octets (majuscules, complété par des espaces).
+fn encode_label(label: &str) -> Result<[u8; 11], FatError> {
+    let label = if label.is_empty() { "NO NAME" } else { label };
+    if label.len() > 11 || !label.bytes().all(|b| b == b' ' || is_short_name_char(b)) {
+        return Err(FatError::InvalidName);
+    }
+
+    let mut out = [b' '; 11];
+    for (i, b) in label.bytes().enumerate() {
+        out[i] = b.to_ascii_uppercase();
+    }
+    Ok(out)
+}
+
+/// Taille de cluster conseillée par Microsoft pour `total` secteurs de 512 octets.
+fn default_sectors_per_cluster(total: u64) -> u8 {
+    match total {
+        0..=532_480 => 1,              // jusqu’à 260 Mo : 512 o
+        532_481..=16_777_216 => 8,     // jusqu’à 8 Go : 4 Kio
+        16_777_217..=33_554_432 => 16, // jusqu’à 16 Go : 8 Kio
+        33_554_433..=67_108_864 => 32, // jusqu’à 32 Go : 16 Kio
+        _ => 64,                       // au-delà : 32 Kio
+    }
+}
+
+/// Calcule la taille de FAT et le nombre de clusters pour le périphérique.
+///
+/// Avec une taille de cluster automatique, on la divise par deux tant qu’il manque
+/// des clusters pour un volume FAT32.
+fn compute_layout(sector_count: u64, options: &FormatOptions) -> Result<Layout, FatError> {
+    if options.num_fats == 0 {
+        return Err(FatError::InvalidFatCount);
+    }
+    let total = core::cmp::min(sector_count, u32::MAX as u64) as u32;
+
+    let mut spc = match options.sectors_per_cluster {
+        Some(spc) if !spc.is_power_of_two() || spc > 128 => {
+            return Err(FatError::InvalidSectorsPerCluster)
+        }
+        Some(spc) => spc,
+        None => default_sectors_per_cluster(total as u64),
+    };
+
+    loop {
+        let layout = layout_for(total, spc, options.num_fats)?;
+        if layout.cluster_count >= FAT32_MIN_CLUSTERS {
+            if layout.cluster_count > FAT32_MAX_CLUSTERS {
+                return Err(FatError::InvalidClusterCount);
+            }
+            return Ok(layout);
+        }
+        if options.sectors_per_cluster.is_some() || spc == 1 {
+            return Err(FatError::BufferTooSmall);
+        }
+        spc /= 2;
+    }
+}
+
+/// Taille de FAT selon la spécification FAT32 (une entrée de 4 octets par cluster).
+fn layout_for(total: u32, spc: u8, num_fats: u8) -> Result<Layout, FatError> {
+    let usable = total
+        .checked_sub(RESERVED_SECTORS)
+        .ok_or(FatError::BufferTooSmall)? as u64;
+    let per_fat_sector = (256 * spc as u64 + num_fats as u64) / 2;
+    let sectors_per_fat = usable.div_ceil(per_fat_sector);
+
+    let fats = num_fats as u64 * sectors_per_fat;
+    if fats >= usable {
+        return Err(FatError::BufferTooSmall);
+    }
+    Ok(Layout {
+        total_sectors: total,
+        sectors_per_cluster: spc,
+        num_fats,
+        sectors_per_fat: sectors_per_fat as u32,
+        cluster_count: ((usable - fats) / spc as u64) as u32,
+    })
+}
+
+/// Secteur de boot FAT32 : saut, OEM, BPB, BPB étendu FAT32, signature.
+fn boot_sector(layout: &Layout, label: &[u8; 11], volume_id: u32) -> [u8; SECTOR_SIZE] {
+    let mut b = [0u8; SECTOR_SIZE];
+    b[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+    b[3..11].copy_from_slice(b"THEHEAP ");
+
+    b[11..13].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+    b[13] = layout.sectors_per_cluster;
+    b[14..16].copy_from_slice(&(RESERVED_SECTORS as u16).to_le_bytes());
+    b[16] = layout.num_fats;
+    // root_entry_count, total_sectors_16 et fat_size_16 restent à 0 en FAT32
+    b[21] = MEDIA_FIXED;
+    b[24..26].copy_from_slice(&63u16.to_le_bytes()); // secteurs par piste
+    b[26..28].copy_from_slice(&255u16.to_le_bytes()); // têtes
+    b[32..36].copy_from_slice(&layout.total_sectors.to_le_bytes());
+
+    b[36..40].copy_from_slice(&layout.sectors_per_fat.to_le_bytes());
+    b[44..48].copy_from_slice(&ROOT_CLUSTER.to_le_bytes());
+    b[48..50].copy_from_slice(&(FS_INFO_SECTOR as u16).to_le_bytes());
+    b[50..52].copy_from_slice(&(BACKUP_BOOT_SECTOR as u16).to_le_bytes());
+
+    b[64] = 0x80; // disque dur
+    b[66] = 0x29; // les trois champs suivants sont présents
+    b[67..71].copy_from_slice(&volume_id.to_le_bytes());
+    b[71..82].copy_from_slice(label);
+    b[82..90].copy_from_slice(b"FAT32   ");
+
+    b[510] = 0x55;
+    b[511] = 0xAA;
+    b
+}
@@ -0,0 +1,84 @@
+//! Secteur FSInfo d’un volume FAT32.
+//!
+//! Le BPB (offset 48) donne le numéro du secteur FSInfo, dans la zone réservée.
+//! Ce secteur contient deux indices que les OS maintiennent pour éviter de rescanner la FAT :
+//! - le nombre de clusters libres (`0xFFFFFFFF` = inconnu),
+//! - le prochain cluster libre probable, là où commencer une allocation (`0xFFFFFFFF` = inconnu).
+//!
+//! Ce ne sont que des indices : on les valide avant de s’en servir.
+
+/// Signature de tête (offset 0) : `"RRaA"`.
+const LEAD_SIG: u32 = 0x4161_5252;
+/// Signature de structure (offset 484) : `"rrAa"`.
+const STRUCT_SIG: u32 = 0x6141_7272;
+/// Signature de fin (offset 508).
+const TRAIL_SIG: u32 = 0xAA55_0000;
+
+/// Offset du compteur de clusters libres dans le secteur.
+pub(crate) const FREE_COUNT_OFFSET: usize = 488;
+
+/// Valeur “inconnu” des deux champs.
+pub(crate) const UNKNOWN: u32 = 0xFFFF_FFFF;
+
+/// Contenu utile du secteur FSInfo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FsInfo {
+    /// Offset (en octets) du secteur FSInfo dans l’image.
+    pub(crate) offset: usize,
+    /// Nombre de clusters libres, ou `UNKNOWN`.
+    pub(crate) free_count: u32,
+    /// Prochain cluster libre probable, ou `UNKNOWN`.
+    pub(crate) next_free: u32,
+}
+
+impl FsInfo {
+    /// Parse un secteur FSInfo (512 octets) situé à `offset` dans l’image.
+    ///
+    /// Retourne `None` si une des trois signatures est absente.
+    pub(crate) fn parse(sector: &[u8], offset: usize) -> Option<Self> {
+        if sector.len() < 512 {
+            return None;
+        }
+
+        let read = |at: usize| {
+            u32::from_le_bytes([sector[at], sector[at + 1], sector[at + 2], sector[at + 3]])
+        };
+
+        if read(0) != LEAD_SIG || read(484) != STRUCT_SIG || read(508) != TRAIL_SIG {
+            return None;
+        }
+
+        Some(Self {
+            offset,
+            free_count: read(FREE_COUNT_OFFSET),
+            next_free: read(FREE_COUNT_OFFSET + 4),
+        })
+    }
+
+    /// Secteur FSInfo complet (signatures + les deux champs), pour le formatage.
+    pub(crate) fn to_sector(self) -> [u8; 512] {
+        let mut sector = [0u8; 512];
+        sector[0..4].copy_from_slice(&LEAD_SIG.to_le_bytes());
+        sector[484..488].copy_from_slice(&STRUCT_SIG.to_le_bytes());
+        sector[FREE_COUNT_OFFSET..FREE_COUNT_OFFSET + 8].copy_from_slice(&self.to_bytes());
+        sector[508..512].copy_from_slice(&TRAIL_SIG.to_le_bytes());
+        sector
+    }
+
+    /// Les deux champs (compteur + indice), tels qu’écrits à `FREE_COUNT_OFFSET`.
+    pub(crate) fn to_bytes(self) -> [u8; 8] {
+        let mut out = [0u8; 8];
+        out[0..4].copy_from_slice(&self.free_count.to_le_bytes());
+        out[4..8].copy_from_slice(&self.next_free.to_le_bytes());
+        out
+    }
+
+    /// Compteur de clusters libres, s’il est connu et plausible.
+    pub(crate) fn known_free_count(&self, total_clusters: u32) -> Option<u32> {
+        if self.free_count == UNKNOWN || self.free_count > total_clusters {
+            None
+        } else {
+            Some(self.free_count)
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Journal d’écriture anticipée (“write-ahead log”) pour des mises à jour atomiques.
+//!
+//! Le journal est un fichier caché et système de la racine, `/JOURNAL.SYS`, créé par
+//! [`Fat32Mut::create_journal`] (ou à la première transaction). Une transaction
+//! ([`Fat32Mut::transaction`]) travaille sur un [`TxDevice`] qui garde en mémoire
+//! chaque secteur modifié au lieu de l’écrire; rien n’atteint le volume avant le commit.
+//!
+//! Le commit se fait en quatre temps, chaque étape vidée sur le support :
+//! 1. les secteurs modifiés sont copiés dans le journal, avec un descripteur
+//!    (secteur de destination + CRC32 de chaque secteur),
+//! 2. l’en-tête du journal est écrit en état “validé” (un seul secteur, avec son CRC32),
+//! 3. les secteurs sont écrits à leur place sur le volume,
+//! 4. l’en-tête repasse à “vide”.
+//!
+//! Au montage en écriture ([`Fat32Mut::from_device`]), un journal validé est rejoué
+//! (étape 3 puis 4) : une coupure pendant l’écriture à leur place est donc rattrapée.
+//! Une vue en lecture ([`crate::Fat32::from_device`], ou un montage `read_only`) ne peut
+//! pas écrire : elle lit le journal validé au montage et ses secteurs masquent ceux du
+//! volume, comme dans un [`TxDevice`]. Une transaction interrompue avant l’étape 2 n’a
+//! rien modifié sur le volume : elle est simplement abandonnée. Dans tous les cas,
+//! le volume lu reflète toute la transaction ou rien.
+//!
+//! Disposition du journal (en secteurs du fichier) : l’en-tête, puis les descripteurs
+//! (32 entrées de 16 octets par secteur), puis les copies des secteurs.
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::partition::crc32;
+use crate::{
+    read_fs_info, Attributes, BlockDevice, Fat32, Fat32Mut, FatError, FixedTime, SECTOR_SIZE,
+};
+
+/// Chemin du fichier journal.
+const JOURNAL_PATH: &str = "/JOURNAL.SYS";
+
+/// Taille du journal créé par défaut à la première transaction (64 Kio).
+const DEFAULT_JOURNAL_SECTORS: u32 = 128;
+
+/// Signature de l’en-tête.
+const MAGIC: &[u8; 8] = b"FAT32JNL";
+
+/// État “validé” : les copies du journal doivent être écrites à leur place.
+const STATE_COMMITTED: u32 = 1;
+
+/// Taille d’une entrée de descripteur : secteur de destination (u64), CRC32, réservé.
+const DESCRIPTOR_SIZE: usize = 16;
+
+/// Secteurs modifiés, par secteur de destination.
+type Overlay = BTreeMap<u64, Box<[u8; SECTOR_SIZE]>>;
+
+/// Lit `buf.len()` octets depuis le secteur `start` : les secteurs de `overlay`
+/// masquent ceux de `inner`.
+fn read_overlaid<D: BlockDevice + ?Sized>(
+    inner: &D,
+    overlay: &Overlay,
+    start: u64,
+    buf: &mut [u8],
+) -> Result<(), FatError> {
+    for (i, chunk) in buf.chunks_mut(SECTOR_SIZE).enumerate() {
+        let lba = start + i as u64;
+        match overlay.get(&lba) {
+            Some(data) => chunk.copy_from_slice(&data[..chunk.len()]),
+            None => inner.read_sectors(lba, chunk)?,
+        }
+    }
+    Ok(())
+}
+
+/// Périphérique vu par une transaction.
+///
+/// Les secteurs écrits restent en mémoire et masquent ceux du périphérique
+/// sous-jacent à la lecture; ils ne sont écrits sur le volume qu’au commit,
+/// en passant par le journal.
+pub struct TxDevice<'a, D> {
+    inner: &'a mut D,
+    overlay: Overlay,
+}
+
+impl<D: BlockDevice> TxDevice<'_, D> {
+    /// Nombre de secteurs modifiés par la transaction jusqu’ici.
+    pub fn dirty_sectors(&self) -> usize {
+        self.overlay.len()
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for TxDevice<'_, D> {
+    fn sector_count(&self) -> u64 {
+        self.inner.sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        read_overlaid(&*self.inner, &self.overlay, start, buf)
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        crate::device::check_range(start, buf.len(), self.sector_count())?;
+        for (i, chunk) in buf.chunks(SECTOR_SIZE).enumerate() {
+            let mut data = Box::new([0u8; SECTOR_SIZE]);
+            data.copy_from_slice(chunk);
+            self.overlay.insert(start + i as u64, data);
+        }
+        Ok(())
+    }
+}
+
+/// Journal validé trouvé au montage, dont les secteurs ne sont peut-être pas
+/// tous écrits à leur place.
+pub(crate) struct PendingJournal {
+    /// Secteur (du périphérique) de l’en-tête du journal.
+    header: u64,
+    /// Copies vérifiées (CRC32), par secteur de destination.
+    sectors: Overlay,
+}
+
+impl PendingJournal {
+    /// Vue de `inner` telle qu’elle sera une fois le journal rejoué.
+    pub(crate) fn view<'a, D: ?Sized>(&'a self, inner: &'a D) -> JournalView<'a, D> {
+        JournalView { inner, journal: self }
+    }
+}
+
+impl core::fmt::Debug for PendingJournal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PendingJournal")
+            .field("header", &self.header)
+            .field("sectors", &self.sectors.len())
+            .finish()
+    }
+}
+
+/// Périphérique en lecture seule : `inner` vu à travers un journal validé.
+pub(crate) struct JournalView<'a, D: ?Sized> {
+    inner: &'a D,
+    journal: &'a PendingJournal,
+}
+
+impl<D: BlockDevice + ?Sized> BlockDevice for JournalView<'_, D> {
+    fn sector_count(&self) -> u64 {
+        self.inner.sector_count()
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        read_overlaid(self.inner, &self.journal.sectors, start, buf)
+    }
+
+    fn write_sectors(&mut self, _start: u64, _buf: &[u8]) -> Result<(), FatError> {
+        Err(FatError::ReadOnly)
+    }
+}
+
+/// En-tête du journal : signature, état, nombre de secteurs, CRC32 des descripteurs,
+/// puis CRC32 de ces 20 octets.
+fn encode_header(state: u32, count: u32, descriptors_crc: u32) -> [u8; SECTOR_SIZE] {
+    let mut h = [0u8; SECTOR_SIZE];
+    h[0..8].copy_from_slice(MAGIC);
+    h[8..12].copy_from_slice(&state.to_le_bytes());
+    h[12..16].copy_from_slice(&count.to_le_bytes());
+    h[16..20].copy_from_slice(&descriptors_crc.to_le_bytes());
+    let crc = crc32(&h[0..20]);
+    h[20..24].copy_from_slice(&crc.to_le_bytes());
+    h
+}
+
+/// Décode un en-tête validé : `Some((count, descriptors_crc))`, `None` sinon
+/// (journal vide, jamais écrit ou en-tête déchiré).
+fn committed_header(h: &[u8]) -> Option<(usize, u32)> {
+    let u32_at = |i: usize| u32::from_le_bytes([h[i], h[i + 1], h[i + 2], h[i + 3]]);
+    if &h[0..8] != MAGIC || crc32(&h[0..20]) != u32_at(20) || u32_at(8) != STATE_COMMITTED {
+        return None;
+    }
+    Some((u32_at(12) as usize, u32_at(16)))
+}
+
+/// Nombre de secteurs de descripteurs pour `count` secteurs journalisés.
+fn descriptor_sectors(count: usize) -> usize {
+    (count * DESCRIPTOR_SIZE).div_ceil(SECTOR_SIZE)
+}
+
+impl<D: BlockDevice> Fat32Mut<D> {
+    /// Crée le fichier journal `/JOURNAL.SYS` (caché, système) de `sectors` secteurs.
+    ///
+    /// Une transaction de `n` secteurs modifiés occupe `n` secteurs du journal,
+    /// plus l’en-tête et un secteur de descripteurs par 32 secteurs.
+    ///
+    /// Paramètres :
+    /// - `sectors` : taille du journal en secteurs de 512 octets (au moins 3).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `AlreadyExists` si le journal existe déjà.
+    /// - `Other` si `sectors < 3`.
+    /// - `NoSpaceLeft` s’il n’y a pas la place sur le volume.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.create_journal(256)?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn create_journal(&mut self, sectors: u32) -> Result<(), FatError> {
+        if sectors < 3 {
+            return Err(FatError::Other);
+        }
+        if self.as_read().open_path(JOURNAL_PATH)?.is_some() {
+            return Err(FatError::AlreadyExists);
+        }
+
+        // Rempli de zéros : un en-tête nul est un journal vide
+        self.write_file_by_path(JOURNAL_PATH, &[])?;
+        self.set_len(JOURNAL_PATH, sectors as u64 * SECTOR_SIZE as u64)?;
+
+        // Caché et système, comme les fichiers réservés de DOS
+        let attrs = Attributes {
+            hidden: true,
+            system: true,
+            archive: true,
+            ..Attributes::default()
+        };
+        self.set_attributes(JOURNAL_PATH, attrs)
+    }
+
+    /// Exécute `f` comme une transaction : toutes ses écritures sont appliquées
+    /// ensemble, ou aucune.
+    ///
+    /// `f` reçoit une vue [`Fat32Mut`] dont les écritures restent en mémoire
+    /// ([`TxDevice`]). Si `f` réussit, les secteurs modifiés passent par le journal
+    /// avant d’être écrits à leur place; si `f` échoue, ils sont oubliés et le volume
+    /// n’a pas changé. Le journal est créé (64 Kio) s’il n’existe pas encore.
+    ///
+    /// Paramètres :
+    /// - `f` : les opérations de la transaction (ne doit pas toucher à `/JOURNAL.SYS`).
+    ///
+    /// Retour : la valeur rendue par `f`.
+    ///
+    /// Erreurs :
+    /// - l’erreur de `f` (la transaction est alors annulée).
+    /// - `JournalFull` si la transaction modifie plus de secteurs que le journal n’en contient
+    ///   (elle est annulée).
+    /// - `Other` si la transaction modifie le journal lui-même.
+    /// - `OutOfBounds`/`Io` si le périphérique échoue pendant le commit : le journal
+    ///   sera rejoué au prochain montage s’il a été validé.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.transaction(|tx| {
+    ///     tx.write_file_by_path("/BOOT/KERNEL.BIN", b"v2")?;
+    ///     tx.write_file_by_path("/BOOT/VERSION", b"2")
+    /// })?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Fat32Mut<TxDevice<'_, D>>) -> Result<T, FatError>,
+    ) -> Result<T, FatError> {
+        let journal = match self.as_read().journal_sectors()? {
+            Some(journal) => journal,
+            None => {
+                self.create_journal(DEFAULT_JOURNAL_SECTORS)?;
+                self.as_read().journal_sectors()?.ok_or(FatError::PathNotFound)?
+            }
+        };
+        // Le volume est marqué “sale” directement : la transaction n’y touche plus
+        self.mark_dirty()?;
+
+        let mut tx = Fat32Mut {
+            disk: TxDevice {
+                inner: &mut self.disk,
+                overlay: BTreeMap::new(),
+            },
+            bpb: self.bpb,
+            disk_len: self.disk_len,
+            fs_info: self.fs_info,
+            clock: core::mem::replace(&mut self.clock, Box::new(FixedTime::default())),
+            free_extents: self.free_extents.take(),
+            dirty: true,
+            options: self.options,
+            in_transaction: true,
+            journal: None,
+        };
+        let result = f(&mut tx);
+
+        let Fat32Mut {
+            disk: TxDevice { overlay, .. },
+            fs_info,
+            clock,
+            free_extents,
+            ..
+        } = tx;
+        self.clock = clock;
+
+        // Annulation : l’index des plages libres suivait la transaction, on le reconstruira
+        let value = result?;
+        self.commit_journal(&journal, &overlay)?;
+        self.fs_info = fs_info;
+        self.free_extents = free_extents;
+        Ok(value)
+    }
+
+    /// Rejoue le journal validé lu au montage (coupure pendant un commit) :
+    /// ses secteurs sont écrits à leur place, puis le journal est vidé.
+    pub(crate) fn replay_journal(&mut self) -> Result<(), FatError> {
+        match self.journal.take() {
+            Some(pending) => self.apply_journal(pending.header, &pending.sectors),
+            None => Ok(()),
+        }
+    }
+
+    // ---------- internes ----------
+
+    /// Écrit les secteurs d’une transaction réussie, en passant par le journal.
+    fn commit_journal(
+        &mut self,
+        journal: &[u64],
+        overlay: &Overlay,
+    ) -> Result<(), FatError> {
+        if overlay.is_empty() {
+            return Ok(());
+        }
+
+        let count = overlay.len();
+        let desc_len = descriptor_sectors(count);
+        if 1 + desc_len + count > journal.len() {
+            return Err(FatError::JournalFull);
+        }
+        if overlay.keys().any(|lba| journal.contains(lba)) {
+            return Err(FatError::Other);
+        }
+
+        // 1. copies + descripteurs
+        let mut descriptors = alloc::vec![0u8; desc_len * SECTOR_SIZE];
+        for (i, (lba, data)) in overlay.iter().enumerate() {
+            let d = &mut descriptors[i * DESCRIPTOR_SIZE..(i + 1) * DESCRIPTOR_SIZE];
+            d[0..8].copy_from_slice(&lba.to_le_bytes());
+            d[8..12].copy_from_slice(&crc32(&data[..]).to_le_bytes());
+            self.write_bytes(journal[1 + desc_len + i] as usize * SECTOR_SIZE, &data[..])?;
+        }
+        for (i, chunk) in descriptors.chunks(SECTOR_SIZE).enumerate() {
+            self.write_bytes(journal[1 + i] as usize * SECTOR_SIZE, chunk)?;
+        }
+        self.disk.flush()?;
+
+        // 2. validation : à partir d’ici, la transaction sera rejouée quoi qu’il arrive
+        let crc = crc32(&descriptors[..count * DESCRIPTOR_SIZE]);
+        let header = encode_header(STATE_COMMITTED, count as u32, crc);
+        self.write_bytes(journal[0] as usize * SECTOR_SIZE, &header)?;
+        self.disk.flush()?;
+
+        // 3. écriture à leur place, 4. journal vide
+        self.apply_journal(journal[0], overlay)
+    }
+
+    /// Écrit les secteurs d’un journal validé à leur place, puis vide l’en-tête `header`.
+    fn apply_journal(&mut self, header: u64, sectors: &Overlay) -> Result<(), FatError> {
+        for (lba, data) in sectors {
+            self.write_bytes(*lba as usize * SECTOR_SIZE, &data[..])?;
+        }
+        self.disk.flush()?;
+        self.write_bytes(header as usize * SECTOR_SIZE, &[0u8; SECTOR_SIZE])?;
+        self.disk.flush()
+    }
+}
+
+impl<D: BlockDevice> Fat32<D> {
+    /// `true` si un journal validé n’a pas encore été écrit à sa place sur le volume.
+    ///
+    /// Les lectures de cette vue voient déjà toute la transaction; le premier
+    /// montage en écriture ([`Fat32Mut::from_device`]) la rejouera sur le volume.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// if fs.has_pending_journal() {
+    ///     println!("transaction en attente, appliquée en mémoire");
+    /// }
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn has_pending_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Lit le journal validé du volume, s’il y en a un, et l’applique aux lectures
+    /// de cette vue (FSInfo compris, relu à travers le journal).
+    ///
+    /// Erreurs :
+    /// - `Io` si une copie du journal ne correspond plus à son CRC32.
+    pub(crate) fn load_journal(&mut self) -> Result<(), FatError> {
+        if let Some(pending) = self.read_pending_journal()? {
+            self.fs_info = read_fs_info(&pending.view(&self.disk), &self.bpb, self.disk_len);
+            self.journal = Some(Arc::new(pending));
+        }
+        Ok(())
+    }
+
+    /// Secteurs (du périphérique) occupés par le journal, dans l’ordre du fichier.
+    ///
+    /// Retour : `None` s’il n’y a pas de journal (ou un fichier trop petit pour en être un).
+    pub(crate) fn journal_sectors(&self) -> Result<Option<Vec<u64>>, FatError> {
+        let Some(entry) = self.open_path(JOURNAL_PATH)? else {
+            return Ok(None);
+        };
+        let count = entry.size as usize / SECTOR_SIZE;
+        if !entry.is_file() || entry.first_cluster < 2 || count < 3 {
+            return Ok(None);
+        }
+
+        let per_cluster = (self.cluster_size() / SECTOR_SIZE) as u64;
+        let mut sectors = Vec::with_capacity(count);
+        for cl in self.follow_chain(entry.first_cluster)? {
+            let first = (self.cluster_to_offset(cl)? / SECTOR_SIZE) as u64;
+            sectors.extend(first..first + per_cluster);
+        }
+        sectors.truncate(count);
+        Ok(Some(sectors))
+    }
+
+    /// Lit et vérifie un journal validé.
+    ///
+    /// Un journal vide, absent ou dont l’en-tête n’a pas été validé donne `None` :
+    /// la transaction correspondante n’avait encore rien écrit sur le volume.
+    ///
+    /// Erreurs :
+    /// - `Io` si une copie du journal ne correspond plus à son CRC32.
+    fn read_pending_journal(&self) -> Result<Option<PendingJournal>, FatError> {
+        let Some(journal) = self.journal_sectors()? else {
+            return Ok(None);
+        };
+
+        let mut header = [0u8; SECTOR_SIZE];
+        self.read_sector_at(journal[0], &mut header)?;
+        let Some((count, descriptors_crc)) = committed_header(&header) else {
+            return Ok(None);
+        };
+
+        let desc_len = descriptor_sectors(count);
+        if 1 + desc_len + count > journal.len() {
+            return Err(FatError::Io);
+        }
+        let mut descriptors = alloc::vec![0u8; desc_len * SECTOR_SIZE];
+        for (i, chunk) in descriptors.chunks_mut(SECTOR_SIZE).enumerate() {
+            let mut sector = [0u8; SECTOR_SIZE];
+            self.read_sector_at(journal[1 + i], &mut sector)?;
+            chunk.copy_from_slice(&sector);
+        }
+        if crc32(&descriptors[..count * DESCRIPTOR_SIZE]) != descriptors_crc {
+            return Err(FatError::Io);
+        }
+
+        let mut sectors = Overlay::new();
+        for (i, d) in descriptors.chunks(DESCRIPTOR_SIZE).take(count).enumerate() {
+            let lba = u64::from_le_bytes(d[0..8].try_into().unwrap());
+            let crc = u32::from_le_bytes(d[8..12].try_into().unwrap());
+            let mut sector = Box::new([0u8; SECTOR_SIZE]);
+            self.read_sector_at(journal[1 + desc_len + i], &mut sector)?;
+            if crc32(&sector[..]) != crc || lba >= self.disk.sector_count() {
+                return Err(FatError::Io);
+            }
+            sectors.insert(lba, sector);
+        }
+        Ok(Some(PendingJournal {
+            header: journal[0],
+            sectors,
+        }))
+    }
+
+    fn read_sector_at(&self, lba: u64, buf: &mut [u8; SECTOR_SIZE]) -> Result<(), FatError> {
+        self.read_bytes(lba as usize * SECTOR_SIZE, buf)
+    }
+}
@@ -1,37 +1,111 @@
 //! Parseur FAT32 (lecture + écriture simple).
 //!
-//! Ce crate manipule un volume FAT32 directement depuis un buffer mémoire.
+//! Ce crate manipule un volume FAT32 à travers un périphérique bloc ([`BlockDevice`]) :
+//! un buffer mémoire (`&[u8]` / `&mut [u8]`), ou un fichier image avec la feature `std`.
 //! Il permet :
 //! - de lister des répertoires et lire des fichiers (lecture),
-//! - de créer ou écraser un fichier 8.3 et écrire ses données (écriture simple),
-//!   en modifiant réellement le buffer du “disque”.
+//!   avec les noms longs VFAT (LFN) quand ils sont présents,
+//! - de créer ou écraser un fichier et écrire ses données (écriture simple),
+//!   en modifiant réellement le “disque” (buffer ou fichier image),
+//! - de créer des répertoires (avec leurs entrées `.` et `..`),
+//! - d’ouvrir un fichier comme un flux ([`File`]) pour le lire ou l’écrire par morceaux,
+//! - d’ouvrir un volume situé dans une partition MBR/GPT d’une image disque complète
+//!   ([`list_partitions`], [`PartitionDevice`]),
+//! - de lire les métadonnées d’une entrée et changer ses attributs
+//!   ([`Fat32::metadata`], [`Fat32Mut::set_attributes`]),
+//! - de vérifier et réparer un volume ([`Fat32::check`], [`Fat32Mut::repair`]),
+//! - de formater un volume FAT32 neuf ([`format`]),
+//! - de garder les secteurs récents en mémoire sur un vrai disque ([`CachedDevice`]).
+//!
+//! Les volumes FAT12 et FAT16 sont aussi gérés ([`FatType`]) : le type est déduit du
+//! nombre de clusters, les entrées de FAT sont encodées sur 12/16 bits, et la racine
+//! est alors une zone fixe placée juste après les FAT (elle ne peut pas grandir).
 //!
 //! Notes importantes :
-//! - Le cœur est en `no_std` (hors tests) et n’utilise que `core` et `alloc`.
-//! - L’écriture vise uniquement les noms courts FAT (format 8.3). Pas de LFN.
-//! - On ne gère pas la création de répertoires (le parent doit déjà exister).
+//! - Le cœur est en `no_std` (hors tests et feature `std`) et n’utilise que `core` et `alloc`.
+//! - Seuls les secteurs nécessaires sont lus : l’image n’a pas besoin de tenir en RAM.
+//! - À l’écriture, un nom qui n’est pas un 8.3 strict reçoit un alias court `~N`
+//!   et une séquence LFN, pour rester lisible par Linux/Windows.
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 extern crate alloc;
 
-use alloc::{string::String, vec::Vec};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 
+mod cache;
+mod check;
+mod device;
 mod dir_entry;
-
+mod extents;
+mod fat_type;
+mod file;
+mod format;
+mod fs_info;
+mod journal;
+mod metadata;
+mod partition;
+mod read_dir;
+mod time;
+
+use device::{read_bytes, write_bytes};
+use dir_entry::{is_valid_long_name, lfn_entries};
+use extents::FreeExtents;
+use fs_info::FsInfo;
+use journal::PendingJournal;
+
+pub use cache::{CacheConfig, CacheStats, CachedDevice};
+pub use check::{CheckReport, Problem};
+pub use device::{BlockDevice, SECTOR_SIZE};
 pub use dir_entry::{Attributes, DirEntry};
+pub use extents::Fragmentation;
+pub use fat_type::FatType;
+pub use file::{File, SeekFrom};
+pub use format::{format, FormatOptions};
+pub use journal::TxDevice;
+pub use metadata::Metadata;
+pub use partition::{list_partitions, Partition, PartitionDevice, PartitionKind};
+pub use read_dir::ReadDir;
+#[cfg(feature = "std")]
+pub use time::SystemClock;
+pub use time::{FatDateTime, FixedTime, TimeSource};
 
 /// Erreurs possibles lors de l’accès à un volume FAT32.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FatError {
-    /// Le buffer ne contient pas assez de données pour un volume valide.
+    /// Le buffer ne contient pas assez de données pour un volume valide
+    /// (moins d’un secteur, ou moins que le nombre total de secteurs du BPB).
     BufferTooSmall,
-    /// Les champs de l'en-tête ne correspondent pas à un volume FAT32 attendu.
+    /// Les champs de l'en-tête ne correspondent pas au type de FAT déduit du nombre
+    /// de clusters (ex: `root_entry_count` ou `fat_size_16` non nuls sur un volume FAT32).
     NotFat32,
+    /// Signature de secteur de boot `0x55AA` absente (octets 510..512).
+    BadBootSignature,
+    /// `bytes_per_sector` n’est pas 512, 1024, 2048 ou 4096.
+    InvalidBytesPerSector,
+    /// `sectors_per_cluster` n’est pas une puissance de deux entre 1 et 128.
+    InvalidSectorsPerCluster,
+    /// `reserved_sectors` vaut 0 (le BPB lui-même est réservé).
+    InvalidReservedSectors,
+    /// `num_fats` vaut 0.
+    InvalidFatCount,
+    /// `sectors_per_fat` vaut 0 ou est trop petit pour le nombre de clusters.
+    InvalidFatSize,
+    /// Nombre total de secteurs nul, ou trop petit pour contenir zone réservée et FAT.
+    InvalidTotalSectors,
+    /// Nombre de clusters nul ou au-delà du maximum FAT32 (0x0FFFFFF5).
+    InvalidClusterCount,
+    /// `root_entry_count` nul sur un volume FAT12/16 (la racine fixe doit exister).
+    InvalidRootEntryCount,
+    /// `root_cluster` ne désigne pas un cluster de données du volume.
+    InvalidRootCluster,
     /// Tentative de lecture/écriture en dehors du buffer.
     OutOfBounds,
     /// Numéro de cluster invalide (ex: < 2).
     InvalidCluster,
+    /// Chaîne de clusters incohérente : boucle, lien vers un cluster libre, défectueux
+    /// ou hors du volume, ou chaîne plus courte que la taille du fichier.
+    CorruptChain,
     /// On tente de lire un répertoire comme un fichier.
     NotAFile,
     /// On tente de lister un fichier comme un répertoire.
@@ -42,6 +116,25 @@ pub enum FatError {
     InvalidName,
     /// Plus de place (pas assez de clusters libres ou pas de slot de dir libre).
     NoSpaceLeft,
+    /// Une entrée du même nom existe déjà.
+    AlreadyExists,
+    /// Suppression d’un répertoire qui contient encore des entrées.
+    DirectoryNotEmpty,
+    /// Déplacement d’un répertoire dans sa propre sous-arborescence.
+    MoveIntoSubtree,
+    /// Table de partitions illisible (MBR incohérent, en-tête ou CRC32 GPT invalide).
+    InvalidPartitionTable,
+    /// La partition demandée n’existe pas (ou n’est pas une partition FAT).
+    PartitionNotFound,
+    /// Le périphérique a refusé ou échoué une lecture/écriture.
+    Io,
+    /// Le fichier dépasserait la taille maximale FAT32 (4 Gio - 1).
+    FileTooLarge,
+    /// La transaction modifie plus de secteurs que le journal ne peut en contenir.
+    JournalFull,
+    /// Écriture refusée : volume monté en lecture seule, ou entrée marquée lecture seule
+    /// (sans [`MountOptions::force`]).
+    ReadOnly,
     /// Erreur générique (ex: chemin relatif).
     Other,
 }
@@ -50,36 +143,141 @@ pub enum FatError {
 /// En pratique on considère EOC si `>= 0x0FFF_FFF8`.
 const FAT32_EOC: u32 = 0x0FFF_FFFF;
 
-/// Vue en lecture seule d’un volume FAT32 stocké dans un buffer mémoire.
+/// Garde-fou pour le parcours d’une chaîne de clusters.
+///
+/// Détection de boucle de Brent (mémoire constante) : la “tortue” saute sur le
+/// cluster courant à chaque puissance de 2 de pas; si la chaîne repasse par elle,
+/// c’est qu’elle boucle. Le nombre de pas est aussi borné par le nombre de clusters
+/// du volume, qu’aucune chaîne saine ne peut dépasser.
+struct LoopGuard {
+    tortoise: u32,
+    power: u32,
+    lap: u32,
+    left: u32,
+}
+
+impl LoopGuard {
+    /// Garde-fou pour une chaîne qui part de `start`, sur un volume de `clusters` clusters.
+    fn new(start: u32, clusters: u32) -> Self {
+        Self {
+            tortoise: start,
+            power: 1,
+            lap: 0,
+            left: clusters,
+        }
+    }
+
+    /// Enregistre le pas vers `next`.
+    ///
+    /// Erreurs : `CorruptChain` si la chaîne boucle.
+    fn step(&mut self, next: u32) -> Result<(), FatError> {
+        if next == self.tortoise || self.left == 0 {
+            return Err(FatError::CorruptChain);
+        }
+        self.left -= 1;
+        self.lap += 1;
+        if self.lap == self.power {
+            self.tortoise = next;
+            self.power = self.power.saturating_mul(2);
+            self.lap = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Attribut “archive” posé sur les fichiers qu’on écrit.
+const ATTR_ARCHIVE: u8 = 0x20;
+
+/// Attribut “répertoire”.
+const ATTR_DIRECTORY: u8 = 0x10;
+
+/// Entrée de répertoire accompagnée de sa position dans l’image.
+///
+/// Produite par [`ReadDir`]. Sert aussi aux opérations d’écriture,
+/// qui doivent réécrire l’entrée sur place.
+#[derive(Debug, Clone)]
+pub struct LocatedEntry {
+    /// Entrée décodée (nom long compris).
+    pub entry: DirEntry,
+    /// Offset (en octets) de l’entrée courte de 32 octets.
+    pub offset: usize,
+    /// Copie brute des 32 octets de l’entrée courte.
+    raw: [u8; 32],
+    /// Offsets des entrées LFN qui précèdent l’entrée courte (ordre disque).
+    lfn_offsets: Vec<usize>,
+}
+
+/// Vue en lecture seule d’un volume FAT32 stocké sur un périphérique `D`.
 ///
 /// Cette vue n’écrit jamais dans l’image.
 #[derive(Debug)]
-pub struct Fat32<'a> {
-    disk: &'a [u8],
-    bytes_per_sector: u16,
-    sectors_per_cluster: u8,
-    reserved_sectors: u16,
-    num_fats: u8,
-    sectors_per_fat: u32,
-    root_cluster: u32,
+pub struct Fat32<D> {
+    disk: D,
+    bpb: BpbParams,
+    /// Taille du périphérique en octets.
+    disk_len: usize,
+    /// Secteur FSInfo, s’il est présent et valide.
+    fs_info: Option<FsInfo>,
+    /// Journal validé pas encore rejoué : ses secteurs masquent ceux du volume à la lecture.
+    journal: Option<Arc<PendingJournal>>,
 }
 
-/// Vue en lecture/écriture d’un volume FAT32 stocké dans un buffer mémoire.
+/// Vue en lecture/écriture d’un volume FAT32 stocké sur un périphérique `D`.
 ///
-/// Les opérations modifient directement `disk`.
-/// Si tu sauvegardes ce buffer dans un fichier (`disk.img`), la modification est persistante.
-#[derive(Debug)]
-pub struct Fat32Mut<'a> {
-    disk: &'a mut [u8],
-    bytes_per_sector: u16,
-    sectors_per_cluster: u8,
-    reserved_sectors: u16,
-    num_fats: u8,
-    sectors_per_fat: u32,
-    root_cluster: u32,
+/// Les opérations modifient directement le périphérique (buffer ou fichier image).
+/// Si tu sauvegardes un buffer dans un fichier (`disk.img`), la modification est persistante.
+pub struct Fat32Mut<D> {
+    disk: D,
+    bpb: BpbParams,
+    /// Taille du périphérique en octets.
+    disk_len: usize,
+    /// Secteur FSInfo, s’il est présent et valide.
+    fs_info: Option<FsInfo>,
+    /// Horloge utilisée pour dater les entrées écrites.
+    clock: Box<dyn TimeSource>,
+    /// Index des plages libres, construit depuis la FAT à la première allocation.
+    free_extents: Option<FreeExtents>,
+    /// Le bit “démonté proprement” de FAT[1] a été effacé par cette session
+    /// (à la première écriture); `unmount` le remet.
+    dirty: bool,
+    /// Options choisies au montage.
+    options: MountOptions,
+    /// Vue d’une [`Fat32Mut::transaction`] : ses écritures sont déjà journalisées.
+    in_transaction: bool,
+    /// Journal validé appliqué en mémoire (montage en lecture seule seulement :
+    /// un montage en écriture le rejoue).
+    journal: Option<Arc<PendingJournal>>,
+}
+
+/// Options de montage d’un [`Fat32Mut`] ([`Fat32Mut::from_device_with`]).
+///
+/// Par défaut, le volume est modifiable et les entrées en lecture seule sont protégées.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MountOptions {
+    /// Refuse toute écriture sur le volume avec `FatError::ReadOnly` : ni données,
+    /// ni bit “démonté proprement”. Un journal validé n’est pas rejoué mais appliqué
+    /// en mémoire, comme pour [`Fat32`].
+    pub read_only: bool,
+    /// Autorise à écraser, modifier, supprimer ou remplacer (par `rename`)
+    /// les entrées dont l’attribut lecture seule est posé.
+    pub force: bool,
 }
 
-impl<'a> Fat32<'a> {
+impl<D: core::fmt::Debug> core::fmt::Debug for Fat32Mut<D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Fat32Mut")
+            .field("disk", &self.disk)
+            .field("bpb", &self.bpb)
+            .field("disk_len", &self.disk_len)
+            .field("fs_info", &self.fs_info)
+            .field("dirty", &self.dirty)
+            .field("options", &self.options)
+            .field("journal", &self.journal)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Fat32<&'a [u8]> {
     /// Construit une vue FAT32 en lecture seule à partir d’un buffer mémoire.
     ///
     /// Paramètres :
@@ -88,8 +286,9 @@ impl<'a> Fat32<'a> {
     /// Retour : `Result<Self, FatError>` avec une vue prête pour le listage et la lecture.
     ///
     /// Erreurs :
-    /// - `BufferTooSmall` si le buffer ne couvre pas les champs requis du BPB.
-    /// - `NotFat32` si les champs du BPB ne correspondent pas à un volume FAT32.
+    /// - `BufferTooSmall` si le buffer est plus petit qu’un secteur ou que le volume décrit.
+    /// - un variant BPB dédié (`BadBootSignature`, `InvalidBytesPerSector`,
+    ///   `InvalidClusterCount`, `InvalidRootCluster`...) si un champ du BPB est invalide.
     ///
     /// Panics : aucune.
     ///
@@ -102,16 +301,82 @@ impl<'a> Fat32<'a> {
     /// # Ok::<(), FatError>(())
     /// ```
     pub fn new(disk: &'a [u8]) -> Result<Self, FatError> {
-        let p = parse_bpb(disk)?;
-        Ok(Self {
+        Self::from_device(disk)
+    }
+}
+
+impl<D: BlockDevice> Fat32<D> {
+    /// Construit une vue FAT32 en lecture seule sur un périphérique bloc.
+    ///
+    /// Le BPB, FSInfo et l’en-tête de `/JOURNAL.SYS` sont lus ici; le reste est lu
+    /// à la demande. Un journal validé (coupure pendant un commit) n’est pas rejoué,
+    /// la vue n’écrit jamais : ses secteurs sont gardés en mémoire et lus à la place
+    /// de ceux du volume ([`Fat32::has_pending_journal`]).
+    ///
+    /// Paramètres :
+    /// - `disk` : périphérique contenant le volume (secteur 0 = BPB).
+    ///
+    /// Retour : `Result<Self, FatError>` avec une vue prête pour le listage et la lecture.
+    ///
+    /// Erreurs :
+    /// - `BufferTooSmall` si le périphérique est plus petit qu’un secteur ou que le volume décrit.
+    /// - un variant BPB dédié (`BadBootSignature`, `InvalidBytesPerSector`,
+    ///   `InvalidClusterCount`, `InvalidRootCluster`...) si un champ du BPB est invalide.
+    /// - `Io` si la lecture du périphérique échoue, ou si un journal validé est corrompu.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// let file = std::fs::File::open("disk.img").map_err(|_| FatError::Io)?;
+    /// let fs = Fat32::from_device(file)?;
+    /// let entries = fs.list_root()?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn from_device(disk: D) -> Result<Self, FatError> {
+        let bpb = read_bpb(&disk)?;
+        let disk_len = device_len(&disk);
+        let fs_info = read_fs_info(&disk, &bpb, disk_len);
+        let mut fs = Self {
             disk,
-            bytes_per_sector: p.bytes_per_sector,
-            sectors_per_cluster: p.sectors_per_cluster,
-            reserved_sectors: p.reserved_sectors,
-            num_fats: p.num_fats,
-            sectors_per_fat: p.sectors_per_fat,
-            root_cluster: p.root_cluster,
-        })
+            bpb,
+            disk_len,
+            fs_info,
+            journal: None,
+        };
+        fs.load_journal()?;
+        Ok(fs)
+    }
+
+    /// Accès au périphérique sous-jacent.
+    pub fn device(&self) -> &D {
+        &self.disk
+    }
+
+    /// Type de FAT du volume, déduit du nombre de clusters au montage.
+    pub fn fat_type(&self) -> FatType {
+        self.bpb.fat_type
+    }
+
+    /// `true` si le volume n’a pas été démonté proprement.
+    ///
+    /// Lit le bit “démonté proprement” de l’entrée FAT[1] de la première FAT : il est
+    /// effacé pendant qu’un [`Fat32Mut`] écrit et remis par [`Fat32Mut::unmount`].
+    /// Un volume “sale” mérite un passage de [`Fat32::check`].
+    ///
+    /// Retour : toujours `false` en FAT12 (pas de bit).
+    ///
+    /// Erreurs : `OutOfBounds`/`Io` si la FAT est illisible.
+    pub fn is_dirty(&self) -> Result<bool, FatError> {
+        let fat_type = self.bpb.fat_type;
+        let Some(bit) = fat_type.clean_shutdown_bit() else {
+            return Ok(false);
+        };
+        let (rel, len) = fat_type.entry_location(1);
+        let mut bytes = [0u8; 4];
+        self.read_bytes(self.bpb.fat_start_byte() + rel, &mut bytes[..len])?;
+        Ok(u32::from_le_bytes(bytes) & bit == 0)
     }
 
     /// Liste le contenu du répertoire racine.
@@ -133,7 +398,7 @@ impl<'a> Fat32<'a> {
     /// # Ok::<(), FatError>(())
     /// ```
     pub fn list_root(&self) -> Result<Vec<DirEntry>, FatError> {
-        self.list_dir_cluster(self.root_cluster)
+        self.list_dir_cluster(self.bpb.root_cluster)
     }
 
     /// Liste un répertoire à partir d’un chemin absolu (ex : `"/DIR"`).
@@ -171,10 +436,49 @@ impl<'a> Fat32<'a> {
         self.list_dir_cluster(entry.first_cluster)
     }
 
+    /// Parcourt un répertoire sans charger toutes ses entrées d’un coup.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu; `"/"` désigne la racine.
+    ///
+    /// Retour : un [`ReadDir`] qui lit le répertoire au fur et à mesure et donne
+    /// chaque entrée avec son offset dans l’image.
+    ///
+    /// Erreurs :
+    /// - `PathNotFound` si le chemin ne correspond à rien.
+    /// - `NotADirectory` si `path` cible un fichier.
+    /// - pendant l’itération : `CorruptChain`/`OutOfBounds`/`Io` si l’image est incohérente.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// for located in fs.read_dir("/DIR")? {
+    ///     let located = located?;
+    ///     println!("{} @ {:#x}", located.entry.display_name(), located.offset);
+    /// }
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn read_dir(&self, path: &str) -> Result<ReadDir<'_, D>, FatError> {
+        if path == "/" {
+            return Ok(ReadDir::new(self, self.bpb.root_cluster));
+        }
+
+        let entry = self.open_path(path)?.ok_or(FatError::PathNotFound)?;
+        if !entry.is_dir() {
+            return Err(FatError::NotADirectory);
+        }
+
+        Ok(ReadDir::new(self, entry.first_cluster))
+    }
+
     /// Lit un fichier à partir de son chemin absolu.
     ///
     /// Paramètres :
-    /// - `path` : chemin absolu d’un fichier (noms courts 8.3).
+    /// - `path` : chemin absolu d’un fichier (nom court 8.3 ou nom long).
     ///
     /// Retour :
     /// - `Ok(Some(bytes))` si le fichier existe.
@@ -183,6 +487,8 @@ impl<'a> Fat32<'a> {
     /// Erreurs :
     /// - `NotAFile` si `path` cible un répertoire.
     /// - `OutOfBounds`/`InvalidCluster` si la chaîne FAT est invalide.
+    /// - `CorruptChain` si la chaîne boucle, passe par un cluster libre ou défectueux,
+    ///   ou est plus courte que la taille du fichier.
     ///
     /// Panics : aucune.
     ///
@@ -221,7 +527,7 @@ impl<'a> Fat32<'a> {
     ///
     /// Panics : aucune.
     ///
-    /// Notes : recherche case‑insensitive pour les noms courts (8.3).
+    /// Notes : recherche case‑insensitive, sur le nom court (8.3) comme sur le nom long.
     ///
     /// Exemples :
     /// ```rust,no_run
@@ -240,24 +546,21 @@ impl<'a> Fat32<'a> {
             return Ok(None);
         }
 
-        let mut current_cluster = self.root_cluster;
+        let mut current_cluster = self.bpb.root_cluster;
         let mut last_entry: Option<DirEntry> = None;
 
         for part in path.split('/').filter(|s| !s.is_empty()) {
-            let target = normalize_name(part);
-            let entries = self.list_dir_cluster(current_cluster)?;
-
-            let mut found = None;
-            for e in entries {
-                if normalize_name(&e.name) == target {
-                    current_cluster = e.first_cluster;
-                    found = Some(e);
-                    break;
-                }
+            // Un composant intermédiaire qui n’est pas un répertoire : rien à trouver dessous
+            if last_entry.as_ref().is_some_and(|e| !e.is_dir()) {
+                return Ok(None);
             }
 
-            match found {
-                Some(e) => last_entry = Some(e),
+            // Le parcours du répertoire s’arrête à la première entrée qui correspond
+            match self.find_in_dir(current_cluster, part)? {
+                Some(located) => {
+                    current_cluster = located.entry.first_cluster;
+                    last_entry = Some(located.entry);
+                }
                 None => return Ok(None),
             }
         }
@@ -275,6 +578,8 @@ impl<'a> Fat32<'a> {
     /// Erreurs :
     /// - `NotAFile` si `entry` est un répertoire.
     /// - `OutOfBounds`/`InvalidCluster` si la chaîne FAT est invalide.
+    /// - `CorruptChain` si la chaîne boucle, passe par un cluster libre ou défectueux,
+    ///   ou est plus courte que la taille du fichier.
     ///
     /// Panics : aucune.
     ///
@@ -307,7 +612,12 @@ impl<'a> Fat32<'a> {
         let cluster_size = self.cluster_size();
         let mut out = Vec::with_capacity(remaining);
 
-        let chain = self.follow_chain(entry.first_cluster, 4096)?;
+        // La chaîne peut dépasser la taille (clusters préalloués), pas l’inverse
+        let chain = self.follow_chain(entry.first_cluster)?;
+        if chain.len() < div_ceil(remaining, cluster_size) {
+            return Err(FatError::CorruptChain);
+        }
+
         for cl in chain {
             let cluster = self.read_cluster(cl)?;
             let take = core::cmp::min(remaining, cluster_size);
@@ -323,23 +633,87 @@ impl<'a> Fat32<'a> {
         Ok(out)
     }
 
-    // ---------- internes (lecture) ----------
+    /// Nombre de clusters libres du volume.
+    ///
+    /// On utilise le compteur du secteur FSInfo s’il est connu et plausible;
+    /// sinon on compte les entrées libres de la FAT.
+    ///
+    /// Retour : `Result<u32, FatError>` avec le nombre de clusters libres.
+    ///
+    /// Erreurs :
+    /// - `Io` si la lecture du périphérique échoue.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// println!("{} clusters libres", fs.free_clusters()?);
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn free_clusters(&self) -> Result<u32, FatError> {
+        let max_cl = self.max_cluster_number()?;
+        if let Some(count) = self.fs_info.and_then(|i| i.known_free_count(max_cl - 1)) {
+            return Ok(count);
+        }
+        self.count_free_clusters()
+    }
 
-    fn bytes_per_sector(&self) -> usize {
-        self.bytes_per_sector as usize
+    /// Espace libre du volume, en octets (`free_clusters() * taille de cluster`).
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `free_clusters`.
+    ///
+    /// Panics : aucune.
+    pub fn free_bytes(&self) -> Result<u64, FatError> {
+        Ok(self.free_clusters()? as u64 * self.cluster_size() as u64)
     }
 
-    fn cluster_size(&self) -> usize {
-        self.bytes_per_sector() * self.sectors_per_cluster as usize
+    /// Fragmentation d’un fichier ou d’un répertoire : nombre de clusters et de plages contiguës.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu.
+    ///
+    /// Retour : [`Fragmentation`] (`extents == 1` pour une chaîne contiguë, `0` si vide).
+    ///
+    /// Erreurs :
+    /// - `PathNotFound` si le chemin ne correspond à rien.
+    /// - `InvalidCluster`/`OutOfBounds` si la chaîne FAT est invalide.
+    /// - `CorruptChain` si la chaîne boucle ou passe par un cluster libre ou défectueux.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// let frag = fs.fragmentation("/HELLO.TXT")?;
+    /// println!("{} clusters en {} plages", frag.clusters, frag.extents);
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn fragmentation(&self, path: &str) -> Result<Fragmentation, FatError> {
+        let entry = self.open_path(path)?.ok_or(FatError::PathNotFound)?;
+        if entry.first_cluster < 2 {
+            return Ok(Fragmentation::of_chain(&[]));
+        }
+        let chain = self.follow_chain(entry.first_cluster)?;
+        Ok(Fragmentation::of_chain(&chain))
     }
 
-    fn fat_start_byte(&self) -> usize {
-        self.reserved_sectors as usize * self.bytes_per_sector()
+    // ---------- internes (lecture) ----------
+
+    fn cluster_size(&self) -> usize {
+        self.bpb.cluster_size()
     }
 
-    fn data_start_byte(&self) -> usize {
-        self.fat_start_byte()
-            + (self.num_fats as usize * self.sectors_per_fat as usize) * self.bytes_per_sector()
+    /// Dernier cluster de données valide (`cluster_count + 1`).
+    ///
+    /// Le BPB a été validé au montage : la FAT et le périphérique couvrent tous ces clusters.
+    fn max_cluster_number(&self) -> Result<u32, FatError> {
+        Ok(self.bpb.cluster_count() + 1)
     }
 
     fn cluster_to_offset(&self, cluster: u32) -> Result<usize, FatError> {
@@ -347,100 +721,188 @@ impl<'a> Fat32<'a> {
             return Err(FatError::InvalidCluster);
         }
 
-        let index = (cluster - 2) as usize;
-        let offset = self.data_start_byte() + index * self.cluster_size();
+        let offset = self.bpb.cluster_offset(cluster);
 
-        if offset >= self.disk.len() {
+        if offset >= self.disk_len {
             return Err(FatError::OutOfBounds);
         }
 
         Ok(offset)
     }
 
-    fn read_cluster(&self, cluster: u32) -> Result<&[u8], FatError> {
-        let offset = self.cluster_to_offset(cluster)?;
-        let size = self.cluster_size();
-
-        if offset + size > self.disk.len() {
+    /// Lit `buf.len()` octets à l’offset `offset` de l’image.
+    fn read_bytes(&self, offset: usize, buf: &mut [u8]) -> Result<(), FatError> {
+        if offset + buf.len() > self.disk_len {
             return Err(FatError::OutOfBounds);
         }
+        match &self.journal {
+            Some(journal) => read_bytes(&journal.view(&self.disk), offset, buf),
+            None => read_bytes(&self.disk, offset, buf),
+        }
+    }
 
-        Ok(&self.disk[offset..offset + size])
+    fn read_cluster(&self, cluster: u32) -> Result<Vec<u8>, FatError> {
+        let offset = self.cluster_to_offset(cluster)?;
+        let mut data = alloc::vec![0u8; self.cluster_size()];
+        self.read_bytes(offset, &mut data)?;
+        Ok(data)
     }
 
+    /// Lit l’entrée FAT de `cluster`, normalisée quel que soit le type de FAT
+    /// (fin de chaîne ≥ `0x0FFFFFF8`).
     fn read_fat_entry(&self, cluster: u32) -> Result<u32, FatError> {
-        let fat_start = self.fat_start_byte();
-        let entry_offset = fat_start + cluster as usize * 4;
+        let fat_type = self.bpb.fat_type;
+        let (rel, len) = fat_type.entry_location(cluster);
 
-        if entry_offset + 4 > self.disk.len() {
-            return Err(FatError::OutOfBounds);
-        }
+        let mut bytes = [0u8; 4];
+        self.read_bytes(self.bpb.fat_start_byte() + rel, &mut bytes[..len])?;
+
+        Ok(fat_type.decode(cluster, &bytes[..len]))
+    }
 
-        let bytes = &self.disk[entry_offset..entry_offset + 4];
-        let val = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    /// Cluster qui suit `cluster` dans sa chaîne, ou `None` en fin de chaîne.
+    ///
+    /// Erreurs : `CorruptChain` si l’entrée de `cluster` est libre (0), marquée
+    /// défectueuse (`0x0FFFFFF7`) ou désigne un cluster hors du volume.
+    fn next_cluster(&self, cluster: u32) -> Result<Option<u32>, FatError> {
+        let next = self.read_fat_entry(cluster)?;
+        if next >= 0x0FFF_FFF8 {
+            return Ok(None);
+        }
 
-        Ok(val & 0x0FFF_FFFF)
+        // 0, 1, les valeurs réservées et BAD_CLUSTER sont toutes hors de 2..=max
+        if next < 2 || next > self.max_cluster_number()? {
+            return Err(FatError::CorruptChain);
+        }
+        Ok(Some(next))
     }
 
-    fn follow_chain(&self, start_cluster: u32, max_clusters: usize) -> Result<Vec<u32>, FatError> {
+    /// Chaîne complète qui part de `start_cluster`, jusqu’à la fin de chaîne.
+    ///
+    /// Le parcours est borné par le nombre de clusters du volume et s’arrête
+    /// sur une erreur si la chaîne boucle.
+    ///
+    /// Erreurs :
+    /// - `InvalidCluster` si `start_cluster < 2`.
+    /// - `CorruptChain` si la chaîne boucle ou passe par un cluster libre, défectueux ou hors du volume.
+    fn follow_chain(&self, start_cluster: u32) -> Result<Vec<u32>, FatError> {
         if start_cluster < 2 {
             return Err(FatError::InvalidCluster);
         }
+        if start_cluster > self.max_cluster_number()? {
+            return Err(FatError::CorruptChain);
+        }
 
-        let mut chain = Vec::new();
+        let mut guard = LoopGuard::new(start_cluster, self.bpb.cluster_count());
+        let mut chain = alloc::vec![start_cluster];
         let mut current = start_cluster;
 
-        for _ in 0..max_clusters {
-            chain.push(current);
-
-            let next = self.read_fat_entry(current)?;
-            if next >= 0x0FFF_FFF8 {
-                break;
-            }
-
-            if next < 2 {
-                return Err(FatError::InvalidCluster);
-            }
+        while let Some(next) = self.next_cluster(current)? {
+            guard.step(next)?;
+            chain.push(next);
             current = next;
         }
 
         Ok(chain)
     }
 
-    fn list_dir_cluster(&self, start_cluster: u32) -> Result<Vec<DirEntry>, FatError> {
-        let mut entries = Vec::new();
-        let chain = self.follow_chain(start_cluster, 4096)?;
-
-        let mut end_seen = false;
-
-        for cl in chain {
-            if end_seen {
-                break;
+    /// Compte les clusters libres en parcourant toute la FAT.
+    fn count_free_clusters(&self) -> Result<u32, FatError> {
+        let mut free = 0u32;
+        self.for_each_fat_entry(|_, value| {
+            if value == 0 {
+                free += 1;
             }
+        })?;
+        Ok(free)
+    }
 
-            let data = self.read_cluster(cl)?;
-            for chunk in data.chunks(32) {
-                if chunk.len() < 32 {
-                    break;
-                }
+    /// Appelle `f(cluster, valeur)` pour chaque cluster de données, dans l’ordre.
+    fn for_each_fat_entry(&self, f: impl FnMut(u32, u32)) -> Result<(), FatError> {
+        self.for_each_fat_entry_in(0, f)
+    }
 
-                // 0x00 = fin de répertoire (à partir de là, tout est libre)
-                if chunk[0] == 0x00 {
-                    end_seen = true;
-                    break;
-                }
+    /// Comme `for_each_fat_entry`, sur la copie `copy` de la FAT (0 = la première).
+    ///
+    /// La FAT est lue par blocs de 32 secteurs plutôt qu’entrée par entrée.
+    /// En FAT12 une entrée peut chevaucher deux blocs : on lit alors toute la FAT
+    /// (quelques Kio au plus).
+    fn for_each_fat_entry_in(&self, copy: usize, mut f: impl FnMut(u32, u32)) -> Result<(), FatError> {
+        let fat_type = self.bpb.fat_type;
+        let max_cl = self.max_cluster_number()?;
+        let fat_len = self.bpb.fat_bytes_len();
+        let fat0 = self.bpb.fat_start_byte() + copy * fat_len;
 
-                if let Some(e) = DirEntry::parse(chunk) {
-                    entries.push(e);
-                }
+        let block = match fat_type {
+            FatType::Fat12 => fat_len,
+            _ => core::cmp::min(32 * SECTOR_SIZE, fat_len),
+        };
+        let mut buf = alloc::vec![0u8; block];
+        let mut loaded = None;
+
+        for cl in 2..=max_cl {
+            let (rel, len) = fat_type.entry_location(cl);
+            let b = rel / block;
+            if loaded != Some(b) {
+                let n = core::cmp::min(block, fat_len - b * block);
+                self.read_bytes(fat0 + b * block, &mut buf[..n])?;
+                loaded = Some(b);
             }
+            let at = rel - b * block;
+            f(cl, fat_type.decode(cl, &buf[at..at + len]));
+        }
+        Ok(())
+    }
+
+    /// Zones (offset, longueur) occupées par un répertoire, dans l’ordre.
+    ///
+    /// `dir_cluster == 0` désigne la racine (c’est aussi la valeur de `..` dans
+    /// un sous-répertoire de la racine) : zone fixe après les FAT en FAT12/16,
+    /// chaîne de `root_cluster` en FAT32.
+    fn dir_regions(&self, dir_cluster: u32) -> Result<Vec<(usize, usize)>, FatError> {
+        if self.bpb.is_fixed_root(dir_cluster) {
+            return Ok(alloc::vec![(self.bpb.root_dir_start_byte(), self.bpb.root_dir_len())]);
         }
 
-        Ok(entries)
+        let cs = self.cluster_size();
+        self.follow_chain(self.bpb.dir_start_cluster(dir_cluster))?
+            .into_iter()
+            .map(|cl| Ok((self.cluster_to_offset(cl)?, cs)))
+            .collect()
+    }
+
+    fn list_dir_cluster(&self, start_cluster: u32) -> Result<Vec<DirEntry>, FatError> {
+        ReadDir::new(self, start_cluster)
+            .map(|located| located.map(|l| l.entry))
+            .collect()
+    }
+
+    /// Parcourt un répertoire et retourne ses entrées avec leur position dans l’image.
+    ///
+    /// Les séquences LFN valides sont rattachées à l’entrée courte qui les suit.
+    fn scan_dir(&self, start_cluster: u32) -> Result<Vec<LocatedEntry>, FatError> {
+        ReadDir::new(self, start_cluster).collect()
+    }
+
+    /// Parcourt les entrées contenues dans `regions` (zones d’un même répertoire, dans l’ordre).
+    fn scan_regions(&self, regions: Vec<(usize, usize)>) -> Result<Vec<LocatedEntry>, FatError> {
+        ReadDir::over_regions(self, regions).collect()
+    }
+
+    /// Cherche `name` (nom court ou long) dans le répertoire `dir_cluster`.
+    fn find_in_dir(&self, dir_cluster: u32, name: &str) -> Result<Option<LocatedEntry>, FatError> {
+        ReadDir::new(self, dir_cluster).find_name(name)
+    }
+}
+
+impl<D: BlockDevice> Fat32Mut<CachedDevice<D>> {
+    /// Compteurs du cache (lectures servies, manquées, évictions, écritures différées).
+    pub fn cache_stats(&self) -> CacheStats {
+        self.disk.stats()
     }
 }
 
-impl<'a> Fat32Mut<'a> {
+impl<'a> Fat32Mut<&'a mut [u8]> {
     /// Construit une vue FAT32 lecture/écriture sur un buffer mutable.
     ///
     /// Paramètres :
@@ -449,8 +911,9 @@ impl<'a> Fat32Mut<'a> {
     /// Retour : `Result<Self, FatError>` avec une vue prête pour l’écriture simple.
     ///
     /// Erreurs :
-    /// - `BufferTooSmall` si le buffer ne couvre pas les champs requis du BPB.
-    /// - `NotFat32` si les champs du BPB ne correspondent pas à un volume FAT32.
+    /// - `BufferTooSmall` si le buffer est plus petit qu’un secteur ou que le volume décrit.
+    /// - un variant BPB dédié (`BadBootSignature`, `InvalidBytesPerSector`,
+    ///   `InvalidClusterCount`, `InvalidRootCluster`...) si un champ du BPB est invalide.
     ///
     /// Panics : aucune.
     ///
@@ -463,860 +926,4114 @@ impl<'a> Fat32Mut<'a> {
     /// # Ok::<(), FatError>(())
     /// ```
     pub fn new(disk: &'a mut [u8]) -> Result<Self, FatError> {
-        let p = parse_bpb(&*disk)?;
-        Ok(Self {
-            disk,
-            bytes_per_sector: p.bytes_per_sector,
-            sectors_per_cluster: p.sectors_per_cluster,
-            reserved_sectors: p.reserved_sectors,
-            num_fats: p.num_fats,
-            sectors_per_fat: p.sectors_per_fat,
-            root_cluster: p.root_cluster,
-        })
+        Self::from_device(disk)
     }
+}
 
-    /// Donne une vue lecture seule sur le même buffer.
+impl<D: BlockDevice> Fat32Mut<D> {
+    /// Construit une vue FAT32 lecture/écriture sur un périphérique bloc.
     ///
-    /// Utile pour réutiliser `open_path` / `list_root` sans dupliquer la logique.
+    /// Paramètres :
+    /// - `disk` : périphérique modifiable contenant le volume (secteur 0 = BPB).
     ///
-    /// Exemples :
+    /// Retour : `Result<Self, FatError>` avec une vue prête pour l’écriture.
+    ///
+    /// Erreurs :
+    /// - `BufferTooSmall` si le périphérique est plus petit qu’un secteur ou que le volume décrit.
+    /// - un variant BPB dédié (`BadBootSignature`, `InvalidBytesPerSector`,
+    ///   `InvalidClusterCount`, `InvalidRootCluster`...) si un champ du BPB est invalide.
+    /// - `Io` si la lecture du périphérique échoue, ou si un journal validé ne peut pas être rejoué.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
     /// ```rust,no_run
     /// # use fat32_parser::{Fat32Mut, FatError};
-    /// # let mut disk = vec![0u8; 10 * 512];
-    /// let mut rw = Fat32Mut::new(&mut disk)?;
-    /// let ro = rw.as_read();
-    /// let _ = ro.list_root();
+    /// let file = std::fs::OpenOptions::new()
+    ///     .read(true)
+    ///     .write(true)
+    ///     .open("disk.img")
+    ///     .map_err(|_| FatError::Io)?;
+    /// let mut rw = Fat32Mut::from_device(file)?;
+    /// rw.write_file_by_path("/NEW.TXT", b"DATA")?;
     /// # Ok::<(), FatError>(())
     /// ```
-    pub fn as_read(&self) -> Fat32<'_> {
-        Fat32 {
-            disk: &*self.disk,
-            bytes_per_sector: self.bytes_per_sector,
-            sectors_per_cluster: self.sectors_per_cluster,
-            reserved_sectors: self.reserved_sectors,
-            num_fats: self.num_fats,
-            sectors_per_fat: self.sectors_per_fat,
-            root_cluster: self.root_cluster,
-        }
+    pub fn from_device(disk: D) -> Result<Self, FatError> {
+        Self::from_device_with(disk, MountOptions::default())
     }
 
-    /// Écrit un fichier (création ou overwrite) dans l’image FAT32.
+    /// Comme [`Fat32Mut::from_device`], avec des options de montage.
     ///
-    /// Paramètres :
-    /// - `path` : chemin absolu du fichier (noms courts 8.3).
-    /// - `content` : octets à écrire (fichier vide si `content.is_empty()`).
+    /// Paramètres :
+    /// - `disk` : périphérique contenant le volume.
+    /// - `options` : lecture seule globale, et `force` pour passer outre
+    ///   l’attribut lecture seule des entrées.
     ///
-    /// Retour : `Result<(), FatError>`.
+    /// Retour : `Result<Self, FatError>`.
     ///
-    /// Erreurs :
-    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
-    /// - `NotADirectory` si le parent n’est pas un répertoire.
-    /// - `PathNotFound` si le parent n’existe pas.
-    /// - `NotAFile` si une entrée existante est un répertoire.
-    /// - `NoSpaceLeft` si la FAT ne contient pas assez de clusters libres.
-    /// - `OutOfBounds`/`InvalidCluster` si l’image est incohérente.
+    /// Avec `options.read_only`, un journal validé n’est pas rejoué : il est appliqué
+    /// en mémoire, comme pour [`Fat32::from_device`], et le volume n’est pas modifié.
     ///
-    /// Panics : aucune.
+    /// Erreurs :
+    /// - mêmes erreurs que `from_device`.
     ///
-    /// Exemples :
+    /// Panics : aucune.
+    ///
+    /// Exemples :
     /// ```rust,no_run
-    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # use fat32_parser::{Fat32Mut, FatError, MountOptions};
     /// # let mut disk = vec![0u8; 10 * 512];
-    /// let mut rw = Fat32Mut::new(&mut disk)?;
-    /// rw.write_file_by_path("/LOG.TXT", b"ABC")?;
-    /// let ro = rw.as_read();
-    /// let content = ro.read_file_by_path("/LOG.TXT")?.unwrap();
-    /// assert_eq!(content, b"ABC");
+    /// let options = MountOptions { read_only: true, ..MountOptions::default() };
+    /// let mut ro = Fat32Mut::from_device_with(&mut disk, options)?;
+    /// assert_eq!(ro.remove_file("/HELLO.TXT"), Err(FatError::ReadOnly));
     /// # Ok::<(), FatError>(())
     /// ```
-    pub fn write_file_by_path(&mut self, path: &str, content: &[u8]) -> Result<(), FatError> {
-        if !path.starts_with('/') || path == "/" {
-            return Err(FatError::Other);
+    pub fn from_device_with(disk: D, options: MountOptions) -> Result<Self, FatError> {
+        let Fat32 {
+            disk,
+            bpb,
+            disk_len,
+            fs_info,
+            journal,
+        } = Fat32::from_device(disk)?;
+        let mut fs = Self {
+            disk,
+            bpb,
+            disk_len,
+            fs_info,
+            clock: Box::new(FixedTime::default()),
+            free_extents: None,
+            dirty: false,
+            options,
+            in_transaction: false,
+            journal,
+        };
+        // Une transaction validée mais pas encore écrite à sa place est terminée ici
+        if !options.read_only {
+            fs.replay_journal()?;
         }
+        Ok(fs)
+    }
 
-        let (parent_path, file_name) = split_parent(path)?;
-        let (name_raw, ext_raw) = encode_short_name_8_3(file_name)?;
+    /// Options de montage en vigueur.
+    pub fn options(&self) -> MountOptions {
+        self.options
+    }
 
-        let parent_cluster = if parent_path == "/" {
-            self.root_cluster
-        } else {
-            let entry = self
-                .as_read()
-                .open_path(parent_path)?
-                .ok_or(FatError::PathNotFound)?;
-            if !entry.is_dir() {
-                return Err(FatError::NotADirectory);
-            }
-            entry.first_cluster
-        };
+    /// Remplace l’horloge qui date les entrées écrites.
+    ///
+    /// Par défaut, les entrées sont datées de [`FatDateTime::EPOCH`] ([`FixedTime`]) :
+    /// le crate n’a pas d’horloge en `no_std`.
+    ///
+    /// Paramètres :
+    /// - `source` : horloge à utiliser (ex : `SystemClock` avec la feature `std`).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{FatDateTime, Fat32Mut, FatError, FixedTime};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let date = FatDateTime::new(2024, 5, 1, 12, 0, 0).unwrap();
+    /// rw.set_time_source(FixedTime(date));
+    /// rw.write_file_by_path("/NEW.TXT", b"DATA")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn set_time_source<T: TimeSource + 'static>(&mut self, source: T) {
+        self.clock = Box::new(source);
+    }
 
-        let (existing_off, existing_entry) =
-            self.find_dir_entry_offset_by_short_name(parent_cluster, &name_raw, &ext_raw)?;
+    /// Écrit sur le périphérique les secteurs modifiés gardés en cache
+    /// (`BlockDevice::write_back`), sans attendre qu’ils soient sur le support.
+    ///
+    /// Sans [`CachedDevice`], les écritures vont déjà directement au périphérique :
+    /// ne fait rien.
+    ///
+    /// Erreurs :
+    /// - `Io` si le périphérique échoue.
+    pub fn flush(&mut self) -> Result<(), FatError> {
+        self.disk.write_back()
+    }
+
+    /// Écrit les secteurs en cache puis vide les tampons du périphérique
+    /// (`BlockDevice::flush`) : au retour, les écritures sont sur le support.
+    ///
+    /// Erreurs :
+    /// - `Io` si le périphérique échoue.
+    pub fn sync(&mut self) -> Result<(), FatError> {
+        self.disk.flush()
+    }
+
+    /// Rend le périphérique sous-jacent, sans démonter le volume.
+    ///
+    /// S’il a été modifié, le volume reste marqué “sale” : préférer [`Fat32Mut::unmount`].
+    pub fn into_device(self) -> D {
+        self.disk
+    }
+
+    /// Démonte le volume et rend le périphérique.
+    ///
+    /// La première écriture d’une session efface le bit “démonté proprement” de
+    /// l’entrée FAT[1] (FAT16/32); `unmount` vide les tampons puis le remet à 1.
+    /// Un volume abandonné en cours d’écriture (coupure, panic) reste donc marqué,
+    /// ce que [`Fat32::is_dirty`] détecte au montage suivant.
+    ///
+    /// Retour : le périphérique sous-jacent.
+    ///
+    /// Erreurs :
+    /// - `Io` si le périphérique échoue (le volume reste alors marqué “sale”).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.write_file_by_path("/NEW.TXT", b"DATA")?;
+    /// rw.unmount()?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn unmount(mut self) -> Result<D, FatError> {
+        if self.dirty {
+            // Les données et la FAT doivent être sur le disque avant le bit “propre”
+            self.disk.flush()?;
+            self.set_clean_shutdown(true)?;
+        }
+        self.disk.flush()?;
+        Ok(self.disk)
+    }
+
+    /// Donne une vue lecture seule sur le même buffer.
+    ///
+    /// Utile pour réutiliser `open_path` / `list_root` sans dupliquer la logique.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let ro = rw.as_read();
+    /// let _ = ro.list_root();
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn as_read(&self) -> Fat32<&D> {
+        Fat32 {
+            disk: &self.disk,
+            bpb: self.bpb,
+            disk_len: self.disk_len,
+            fs_info: self.fs_info,
+            journal: self.journal.clone(),
+        }
+    }
+
+    /// Écrit un fichier (création ou overwrite) dans l’image FAT32.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier. Un nom qui n’est pas un 8.3 strict
+    ///   (ex : `config.json`) reçoit un alias court `~N` et une séquence LFN.
+    /// - `content` : octets à écrire (fichier vide si `content.is_empty()`).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `InvalidName` si le nom contient un caractère interdit (`*`, `?`, `:`...).
+    /// - `NotADirectory` si le parent n’est pas un répertoire.
+    /// - `PathNotFound` si le parent n’existe pas.
+    /// - `NotAFile` si une entrée existante est un répertoire.
+    /// - `ReadOnly` si le volume est monté en lecture seule, ou si le fichier existant
+    ///   est en lecture seule (sans `MountOptions::force`).
+    /// - `NoSpaceLeft` si la FAT ne contient pas assez de clusters libres
+    ///   (données, ou extension du répertoire parent).
+    /// - `OutOfBounds`/`InvalidCluster` si l’image est incohérente.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.write_file_by_path("/LOG.TXT", b"ABC")?;
+    /// let ro = rw.as_read();
+    /// let content = ro.read_file_by_path("/LOG.TXT")?.unwrap();
+    /// assert_eq!(content, b"ABC");
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn write_file_by_path(&mut self, path: &str, content: &[u8]) -> Result<(), FatError> {
+        let (parent_cluster, file_name, existing) = self.prepare_file_write(path)?;
+
+        // Ordre d’écriture : données, FAT, entrée, puis libération de l’ancienne chaîne.
+        // Une coupure à n’importe quel moment laisse l’ancien ou le nouveau fichier,
+        // au pire avec des clusters perdus (que `repair` récupère).
+        let first_cluster = if content.is_empty() {
+            0u32
+        } else {
+            let needed = div_ceil(content.len(), self.cluster_size());
+            let chain = self.pick_free(needed)?;
+            self.write_chain_data(&chain, content)?;
+            self.write_barrier()?;
+            self.link_allocated(&chain)?;
+            self.write_barrier()?;
+            chain[0]
+        };
+
+        self.install_chain(parent_cluster, file_name, existing, first_cluster, content.len() as u32)
+    }
+
+    /// Remplace (ou crée) un fichier avec ce que `fill` écrit, sans jamais exposer
+    /// un fichier vide ou à moitié écrit.
+    ///
+    /// `fill` reçoit un [`File`] vide qui n’est relié à aucune entrée : ses écritures
+    /// construisent une chaîne neuve. Une fois `fill` terminé, l’entrée de `path` est
+    /// réécrite pour pointer dessus (noms, attributs et date de création conservés),
+    /// puis l’ancienne chaîne est libérée, comme dans [`Fat32Mut::write_file_by_path`].
+    /// Une coupure pendant la copie laisse donc l’ancien fichier intact, au pire avec
+    /// des clusters perdus.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier (noms longs acceptés).
+    /// - `fill` : écrit le nouveau contenu (par morceaux, `seek`, `set_len`...).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `write_file_by_path`.
+    /// - l’erreur renvoyée par `fill` : la nouvelle chaîne est alors libérée
+    ///   et le fichier existant n’est pas modifié.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.replace_file("/KERNEL.BIN", |file| {
+    ///     for _ in 0..1024 {
+    ///         file.write(&[0xAA; 4096])?;
+    ///     }
+    ///     Ok(())
+    /// })?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn replace_file<F>(&mut self, path: &str, fill: F) -> Result<(), FatError>
+    where
+        F: FnOnce(&mut File<'_, D>) -> Result<(), FatError>,
+    {
+        let (parent_cluster, file_name, existing) = self.prepare_file_write(path)?;
+
+        let mut file = File::detached(self);
+        let filled = fill(&mut file);
+        let (first_cluster, size) = file.into_chain();
+        if let Err(e) = filled {
+            self.free_chain(first_cluster)?;
+            return Err(e);
+        }
+
+        self.write_barrier()?;
+        self.install_chain(parent_cluster, file_name, existing, first_cluster, size)
+    }
+
+    /// Vérifie qu’on peut écrire le fichier `path` : chemin, nom, parent, et l’entrée
+    /// existante s’il y en a une (pas un répertoire, pas en lecture seule).
+    ///
+    /// Retour : le cluster du parent, le nom du fichier et l’entrée existante.
+    fn prepare_file_write<'p>(
+        &self,
+        path: &'p str,
+    ) -> Result<(u32, &'p str, Option<LocatedEntry>), FatError> {
+        if !path.starts_with('/') || path == "/" {
+            return Err(FatError::Other);
+        }
+
+        let (parent_path, file_name) = split_parent(path)?;
+        if !is_valid_long_name(file_name) {
+            return Err(FatError::InvalidName);
+        }
+
+        let parent_cluster = self.resolve_dir_cluster(parent_path)?;
+        let existing = self.as_read().find_in_dir(parent_cluster, file_name)?;
+        if let Some(located) = &existing {
+            if located.entry.is_dir() {
+                return Err(FatError::NotAFile);
+            }
+            self.check_writable(&located.entry)?;
+        }
+        Ok((parent_cluster, file_name, existing))
+    }
+
+    /// Relie une chaîne déjà écrite et marquée dans la FAT au fichier `file_name` :
+    /// réécrit l’entrée `existing` (puis libère son ancienne chaîne) ou crée l’entrée.
+    fn install_chain(
+        &mut self,
+        parent_cluster: u32,
+        file_name: &str,
+        existing: Option<LocatedEntry>,
+        first_cluster: u32,
+        size: u32,
+    ) -> Result<(), FatError> {
+        match existing {
+            Some(located) => {
+                // On garde les noms (court + long), les attributs et la date de création déjà
+                // présents sur disque; l’entrée est réécrite d’un bloc (un seul secteur)
+                let mut short = [0u8; 11];
+                short.copy_from_slice(&located.raw[0..11]);
+                let attr = located.raw[11] | ATTR_ARCHIVE;
+                let mut e = self.dir_entry_bytes(&short, attr, first_cluster, size);
+                e[13..18].copy_from_slice(&located.raw[13..18]);
+                self.write_bytes(located.offset, &e)?;
+                self.write_barrier()?;
+
+                self.free_chain(located.entry.first_cluster)?;
+            }
+            None => {
+                if let Err(e) =
+                    self.create_dir_entry(parent_cluster, file_name, ATTR_ARCHIVE, first_cluster, size)
+                {
+                    // Pas de place dans le parent : on rend la chaîne déjà marquée dans la FAT
+                    self.free_chain(first_cluster)?;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Crée un répertoire vide.
+    ///
+    /// Un cluster est alloué pour le répertoire, puis initialisé avec les entrées
+    /// `.` (le répertoire lui-même) et `..` (le parent, `0` si le parent est la racine).
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du répertoire à créer (nom court ou long).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `InvalidName` si le nom contient un caractère interdit.
+    /// - `PathNotFound` si le parent n’existe pas.
+    /// - `NotADirectory` si le parent n’est pas un répertoire.
+    /// - `AlreadyExists` si une entrée du même nom existe déjà.
+    /// - `NoSpaceLeft` s’il n’y a plus de cluster libre ou de slot dans le parent.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.create_dir("/BOOT")?;
+    /// rw.write_file_by_path("/BOOT/KERNEL.BIN", b"...")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn create_dir(&mut self, path: &str) -> Result<(), FatError> {
+        if !path.starts_with('/') || path == "/" {
+            return Err(FatError::Other);
+        }
+
+        let (parent_path, dir_name) = split_parent(path)?;
+        if !is_valid_long_name(dir_name) {
+            return Err(FatError::InvalidName);
+        }
+
+        let parent_cluster = self.resolve_dir_cluster(parent_path)?;
+        if self.as_read().find_in_dir(parent_cluster, dir_name)?.is_some() {
+            return Err(FatError::AlreadyExists);
+        }
+
+        // Cluster du nouveau répertoire : `.` et `..`, le reste à zéro (fin de répertoire).
+        // Il est rempli avant d’être marqué dans la FAT, puis référencé par le parent.
+        let cluster = self.pick_free(1)?[0];
+        let dotdot = if parent_cluster == self.bpb.root_cluster {
+            0
+        } else {
+            parent_cluster
+        };
+        self.init_dir_cluster(cluster, dotdot)?;
+        self.write_barrier()?;
+        self.link_allocated(&[cluster])?;
+        self.write_barrier()?;
+
+        if let Err(e) = self.create_dir_entry(parent_cluster, dir_name, ATTR_DIRECTORY, cluster, 0) {
+            // Pas de place dans le parent : on rend le cluster
+            self.free_chain(cluster)?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Crée un répertoire et tous ses parents manquants (comme `mkdir -p`).
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du répertoire.
+    ///
+    /// Retour : `Ok(())` y compris si le répertoire existe déjà.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu.
+    /// - `NotADirectory` si un composant existant est un fichier.
+    /// - Les erreurs de [`Fat32Mut::create_dir`].
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.create_dir_all("/BOOT/CFG")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn create_dir_all(&mut self, path: &str) -> Result<(), FatError> {
+        if !path.starts_with('/') {
+            return Err(FatError::Other);
+        }
+
+        let mut current = String::new();
+        for part in path.split('/').filter(|s| !s.is_empty()) {
+            current.push('/');
+            current.push_str(part);
+
+            match self.as_read().open_path(&current)? {
+                Some(e) if e.is_dir() => continue,
+                Some(_) => return Err(FatError::NotADirectory),
+                None => self.create_dir(&current)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Supprime un fichier.
+    ///
+    /// L’entrée courte et ses entrées LFN sont marquées supprimées (`0xE5`),
+    /// puis la chaîne de clusters est libérée dans toutes les copies de la FAT.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier (nom court ou long).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `PathNotFound` si le fichier (ou son parent) n’existe pas.
+    /// - `NotAFile` si `path` cible un répertoire.
+    /// - `ReadOnly` si le volume ou le fichier est en lecture seule (sans `MountOptions::force`).
+    /// - `OutOfBounds`/`InvalidCluster` si l’image est incohérente.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.remove_file("/OLD.LOG")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn remove_file(&mut self, path: &str) -> Result<(), FatError> {
+        let (_, located) = self.locate(path)?;
+        if located.entry.is_dir() {
+            return Err(FatError::NotAFile);
+        }
+        self.check_writable(&located.entry)?;
+
+        self.delete_located(&located)
+    }
+
+    /// Supprime un répertoire vide.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du répertoire (ne peut pas être `"/"`).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `PathNotFound` si le répertoire (ou son parent) n’existe pas.
+    /// - `NotADirectory` si `path` cible un fichier.
+    /// - `DirectoryNotEmpty` s’il reste des entrées autres que `.` et `..`.
+    /// - `ReadOnly` si le volume ou le répertoire est en lecture seule (sans `MountOptions::force`).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.remove_dir("/TMP")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn remove_dir(&mut self, path: &str) -> Result<(), FatError> {
+        let (_, located) = self.locate(path)?;
+        if !located.entry.is_dir() {
+            return Err(FatError::NotADirectory);
+        }
+        self.check_writable(&located.entry)?;
+
+        if located.entry.first_cluster >= 2 {
+            let children = self.as_read().list_dir_cluster(located.entry.first_cluster)?;
+            if children.iter().any(|e| e.name != "." && e.name != "..") {
+                return Err(FatError::DirectoryNotEmpty);
+            }
+        }
+
+        self.delete_located(&located)
+    }
+
+    /// Renomme ou déplace une entrée (fichier ou répertoire), sans copier les données.
+    ///
+    /// L’entrée est recréée dans le répertoire de destination (même cluster, même taille,
+    /// mêmes attributs), puis l’ancienne est marquée supprimée.
+    /// Pour un répertoire qui change de parent, l’entrée `..` est mise à jour.
+    ///
+    /// Si `to` désigne un fichier existant et que `from` est un fichier, il est remplacé :
+    /// l’entrée de `from` est supprimée, celle de `to` est réécrite sur place pour pointer
+    /// sur ses données, puis l’ancienne chaîne de `to` est libérée (pratique pour basculer
+    /// `APP.NEW` sur `APP.BIN`).
+    ///
+    /// Si le volume a un journal (`/JOURNAL.SYS`, voir [`Fat32Mut::create_journal`]),
+    /// tout le renommage passe par une [`Fat32Mut::transaction`] : après une coupure,
+    /// on retrouve `APP.BIN` et `APP.NEW` d’avant, ou le seul nouveau `APP.BIN`.
+    /// Sans journal, l’opération n’est pas atomique. Une coupure pendant un remplacement
+    /// peut laisser l’ancien `APP.BIN` sans `APP.NEW`, le nouveau contenu n’étant plus
+    /// qu’une chaîne perdue que [`Fat32Mut::repair`] range dans `FOUND.000`; pendant un
+    /// déplacement, elle peut laisser les deux noms sur la même chaîne (`CrossLinked`).
+    ///
+    /// Paramètres :
+    /// - `from` : chemin absolu de l’entrée à déplacer.
+    /// - `to` : nouveau chemin absolu (le parent doit exister).
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si un des chemins n’est pas absolu ou vaut `"/"`.
+    /// - `PathNotFound` si `from` ou le parent de `to` n’existe pas.
+    /// - `InvalidName` si le nouveau nom contient un caractère interdit.
+    /// - `AlreadyExists` si `to` existe et que l’un des deux est un répertoire.
+    /// - `MoveIntoSubtree` si on déplace un répertoire dans lui-même ou un de ses descendants.
+    /// - `ReadOnly` si le volume, `from` ou le fichier remplacé est en lecture seule
+    ///   (sans `MountOptions::force`).
+    /// - `NoSpaceLeft` si le répertoire de destination n’a plus de slot libre.
+    /// - `JournalFull` si le journal est trop petit pour le renommage (rien n’est modifié).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.rename("/APP.NEW", "/APP.BIN")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), FatError> {
+        // Dans une transaction, les écritures sont déjà journalisées
+        if !self.in_transaction && self.as_read().journal_sectors()?.is_some() {
+            return self.transaction(|tx| tx.rename_entry(from, to));
+        }
+        self.rename_entry(from, to)
+    }
+
+    /// Renomme ou déplace une entrée directement sur le volume (voir [`Fat32Mut::rename`]).
+    fn rename_entry(&mut self, from: &str, to: &str) -> Result<(), FatError> {
+        let (src_parent, src) = self.locate(from)?;
+        self.check_writable(&src.entry)?;
+
+        if !to.starts_with('/') || to == "/" {
+            return Err(FatError::Other);
+        }
+        let (dst_parent_path, dst_name) = split_parent(to)?;
+        if !is_valid_long_name(dst_name) {
+            return Err(FatError::InvalidName);
+        }
+        let dst_parent = self.resolve_dir_cluster(dst_parent_path)?;
+
+        // Un répertoire ne peut pas devenir son propre descendant
+        if src.entry.is_dir() && src.entry.first_cluster >= 2 {
+            let mut current = String::new();
+            for part in dst_parent_path.split('/').filter(|s| !s.is_empty()) {
+                current.push('/');
+                current.push_str(part);
+                let e = self.as_read().open_path(&current)?.ok_or(FatError::PathNotFound)?;
+                if e.first_cluster == src.entry.first_cluster {
+                    return Err(FatError::MoveIntoSubtree);
+                }
+            }
+        }
+
+        let existing = self
+            .as_read()
+            .find_in_dir(dst_parent, dst_name)?
+            .filter(|e| e.offset != src.offset);
+
+        let mut raw = [0u8; 32];
+        raw.copy_from_slice(&src.raw);
+
+        match existing {
+            Some(dst) => {
+                if src.entry.is_dir() || dst.entry.is_dir() {
+                    return Err(FatError::AlreadyExists);
+                }
+                self.check_writable(&dst.entry)?;
+
+                // Remplacement : on garde les noms de la destination, le reste vient de la source.
+                // La source disparaît d’abord : sans journal, une coupure laisse l’ancienne
+                // destination et, au pire, la chaîne de la source perdue (que `repair` récupère),
+                // mais jamais deux entrées sur la même chaîne
+                self.mark_deleted(&src)?;
+                self.write_barrier()?;
+                self.write_bytes(dst.offset + 11, &raw[11..32])?;
+                self.write_barrier()?;
+                self.free_chain(dst.entry.first_cluster)?;
+            }
+            None => {
+                // Déplacement : la nouvelle entrée existe avant que l’ancienne disparaisse.
+                // Sans journal, une coupure entre les deux laisse deux entrées sur la même
+                // chaîne (`CrossLinked`), mais les données gardent toujours un nom
+                let off = self.create_dir_entry(
+                    dst_parent,
+                    dst_name,
+                    raw[11],
+                    src.entry.first_cluster,
+                    src.entry.size,
+                )?;
+                self.write_bytes(off + 11, &raw[11..32])?;
+                self.write_barrier()?;
+                self.mark_deleted(&src)?;
+            }
+        }
+
+        // `..` doit suivre le nouveau parent (0 = racine)
+        if src.entry.is_dir() && src.entry.first_cluster >= 2 && src_parent != dst_parent {
+            let dotdot = if dst_parent == self.bpb.root_cluster {
+                0
+            } else {
+                dst_parent
+            };
+            let dir_off = self.cluster_to_offset(src.entry.first_cluster)?;
+            let mut name = [0u8; 2];
+            self.read_bytes(dir_off + 32, &mut name)?;
+            if name == *b".." {
+                self.set_entry_cluster(dir_off + 32, dotdot)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ouvre un fichier existant pour le lire ou l’écrire par morceaux.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier.
+    ///
+    /// Retour : `Result<File<'_, D>, FatError>` positionné au début du fichier.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `PathNotFound` si le fichier (ou un parent) n’existe pas.
+    /// - `NotAFile` si `path` désigne un répertoire.
+    ///
+    /// Un fichier en lecture seule s’ouvre normalement, mais ses écritures échouent
+    /// avec `ReadOnly` (sauf avec `MountOptions::force`).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let mut file = rw.open("/HELLO.TXT")?;
+    /// let mut buf = [0u8; 512];
+    /// while file.read(&mut buf)? > 0 {}
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn open(&mut self, path: &str) -> Result<File<'_, D>, FatError> {
+        let (_, located) = self.locate(path)?;
+        if located.entry.is_dir() {
+            return Err(FatError::NotAFile);
+        }
+        // Toujours lisible; les écritures du handle vérifient l’attribut
+        let writable = self.check_writable(&located.entry).is_ok();
+        Ok(File::new(
+            self,
+            located.offset,
+            located.entry.first_cluster,
+            located.entry.size,
+            writable,
+        ))
+    }
+
+    /// Crée un fichier vide (ou vide un fichier existant) et l’ouvre en écriture.
+    ///
+    /// L’ancien contenu est libéré tout de suite : pour remplacer un fichier sans
+    /// risquer de le perdre en cas de coupure, utiliser [`Fat32Mut::replace_file`].
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier (noms longs acceptés).
+    ///
+    /// Retour : `Result<File<'_, D>, FatError>` positionné au début d’un fichier vide.
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `write_file_by_path`.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let mut file = rw.create("/BIG.BIN")?;
+    /// for _ in 0..1024 {
+    ///     file.write(&[0xAA; 4096])?;
+    /// }
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn create(&mut self, path: &str) -> Result<File<'_, D>, FatError> {
+        self.write_file_by_path(path, &[])?;
+        self.open(path)
+    }
+
+    /// Ajoute `data` à la fin d’un fichier existant.
+    ///
+    /// La chaîne existante est conservée : on complète le dernier cluster,
+    /// puis on chaîne seulement les clusters manquants. Seule la fin du fichier est écrite.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier.
+    /// - `data` : octets à ajouter.
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `open`.
+    /// - `NoSpaceLeft` s’il n’y a plus assez de clusters libres.
+    /// - `FileTooLarge` si le fichier dépasserait 4 Gio - 1.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.append_file("/LOG.TXT", b"boot ok\n")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn append_file(&mut self, path: &str, data: &[u8]) -> Result<(), FatError> {
+        let mut file = self.open(path)?;
+        file.seek(SeekFrom::End(0))?;
+        file.write(data).map(|_| ())
+    }
+
+    /// Tronque ou agrandit un fichier existant à `new_len` octets.
+    ///
+    /// En réduction, la chaîne est coupée au cluster qui contient le dernier octet gardé
+    /// (nouvel EOC) et la suite est libérée. En agrandissement, des clusters sont
+    /// chaînés en fin de fichier et les nouveaux octets valent zéro.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier.
+    /// - `new_len` : nouvelle taille en octets.
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `open`.
+    /// - `NoSpaceLeft` s’il n’y a plus assez de clusters libres.
+    /// - `FileTooLarge` si `new_len` dépasse 4 Gio - 1.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.set_len("/LOG.TXT", 0)?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn set_len(&mut self, path: &str, new_len: u64) -> Result<(), FatError> {
+        self.open(path)?.set_len(new_len)
+    }
+
+    /// Réserve des clusters consécutifs pour qu’un fichier puisse atteindre `len` octets,
+    /// sans changer sa taille visible.
+    ///
+    /// Les écritures suivantes jusqu’à `len` n’allouent plus rien et restent contiguës.
+    /// Une réduction (`set_len`) ou un overwrite libère la réserve.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier.
+    /// - `len` : taille (en octets) à couvrir par la chaîne.
+    ///
+    /// Retour : `Result<(), FatError>` (rien à faire si la chaîne couvre déjà `len`).
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `open`.
+    /// - `NoSpaceLeft` s’il n’existe pas de plage libre contiguë assez longue.
+    /// - `FileTooLarge` si `len` dépasse 4 Gio - 1.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.preallocate("/LOG.TXT", 1 << 20)?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn preallocate(&mut self, path: &str, len: u64) -> Result<(), FatError> {
+        self.open(path)?.preallocate(len)
+    }
+
+    /// Écrit `data` à l’offset `offset` d’un fichier existant, sans toucher au reste.
+    ///
+    /// Les clusters déjà alloués sont réécrits sur place; la chaîne n’est étendue
+    /// que si l’écriture dépasse la fin. Un `offset` au-delà de la fin laisse
+    /// un trou rempli de zéros, et la taille devient `offset + data.len()`.
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu du fichier.
+    /// - `offset` : position (en octets) de la première écriture.
+    /// - `data` : octets à écrire.
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - mêmes erreurs que `append_file`.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// rw.write_at("/HELLO.TXT", 1, b"ELP")?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn write_at(&mut self, path: &str, offset: u64, data: &[u8]) -> Result<(), FatError> {
+        let mut file = self.open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write(data).map(|_| ())
+    }
+
+    // ---------- internes (écriture) ----------
+
+    fn cluster_size(&self) -> usize {
+        self.bpb.cluster_size()
+    }
+
+    fn max_cluster_number(&self) -> Result<u32, FatError> {
+        self.as_read().max_cluster_number()
+    }
+
+    fn cluster_to_offset(&self, cluster: u32) -> Result<usize, FatError> {
+        self.as_read().cluster_to_offset(cluster)
+    }
+
+    fn read_bytes(&self, offset: usize, buf: &mut [u8]) -> Result<(), FatError> {
+        self.as_read().read_bytes(offset, buf)
+    }
+
+    /// Écrit `data` à l’offset `offset` de l’image (seuls les secteurs touchés sont réécrits).
+    ///
+    /// La première écriture de la session marque d’abord le volume “sale” dans FAT[1].
+    fn write_bytes(&mut self, offset: usize, data: &[u8]) -> Result<(), FatError> {
+        if self.options.read_only {
+            return Err(FatError::ReadOnly);
+        }
+        if offset + data.len() > self.disk_len {
+            return Err(FatError::OutOfBounds);
+        }
+        self.mark_dirty()?;
+        write_bytes(&mut self.disk, offset, data)
+    }
+
+    /// Barrière d’ordre : les écritures gardées en cache ([`CachedDevice`]) atteignent
+    /// le périphérique avant celles qui suivent. Sans cache, ne fait rien.
+    ///
+    /// Un cache écrit ses secteurs dans l’ordre des réserves et des secteurs, pas dans
+    /// celui des appels : chaque étape (données, FAT, entrée, libération) se termine donc
+    /// par une barrière, sinon une coupure pourrait laisser une entrée vers une chaîne libre.
+    pub(crate) fn write_barrier(&mut self) -> Result<(), FatError> {
+        self.disk.write_back()
+    }
+
+    /// Efface le bit “démonté proprement” de FAT[1], une fois par session.
+    fn mark_dirty(&mut self) -> Result<(), FatError> {
+        if self.options.read_only {
+            return Err(FatError::ReadOnly);
+        }
+        if !self.dirty {
+            // Le bit doit être sur le disque avant toute autre écriture de la session
+            self.set_clean_shutdown(false)?;
+            self.write_barrier()?;
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Met (`clean`) ou efface le bit “démonté proprement” de FAT[1], dans chaque copie.
+    ///
+    /// Les autres bits de l’entrée sont conservés. Sans effet en FAT12.
+    fn set_clean_shutdown(&mut self, clean: bool) -> Result<(), FatError> {
+        let fat_type = self.bpb.fat_type;
+        let Some(bit) = fat_type.clean_shutdown_bit() else {
+            return Ok(());
+        };
+        let (rel, len) = fat_type.entry_location(1);
+
+        for i in 0..self.bpb.num_fats as usize {
+            let off = self.bpb.fat_start_byte() + i * self.bpb.fat_bytes_len() + rel;
+            let mut bytes = [0u8; 4];
+            self.read_bytes(off, &mut bytes[..len])?;
+            let raw = u32::from_le_bytes(bytes);
+            let raw = if clean { raw | bit } else { raw & !bit };
+            // Écriture directe : ne doit pas repasser par le marquage de `write_bytes`
+            write_bytes(&mut self.disk, off, &raw.to_le_bytes()[..len])?;
+        }
+        Ok(())
+    }
+
+    fn read_fat_entry(&self, cluster: u32) -> Result<u32, FatError> {
+        self.as_read().read_fat_entry(cluster)
+    }
+
+    /// Écrit la valeur normalisée `value` dans l’entrée `cluster` de chaque copie de la FAT.
+    ///
+    /// En FAT12/16, on relit les octets de l’entrée pour conserver le voisin
+    /// (demi-octet partagé en FAT12).
+    fn write_fat_entry_all(&mut self, cluster: u32, value: u32) -> Result<(), FatError> {
+        let fat_type = self.bpb.fat_type;
+        let (rel, len) = fat_type.entry_location(cluster);
+
+        let fat0 = self.bpb.fat_start_byte();
+        let fat_len = self.bpb.fat_bytes_len();
+
+        for i in 0..self.bpb.num_fats as usize {
+            let off = fat0 + i * fat_len + rel;
+            let mut bytes = [0u8; 4];
+            if fat_type != FatType::Fat32 {
+                self.read_bytes(off, &mut bytes[..len])?;
+            }
+            fat_type.encode(cluster, value & 0x0FFF_FFFF, &mut bytes[..len]);
+            self.write_bytes(off, &bytes[..len])?;
+        }
+
+        // L’index des plages libres suit chaque changement d’état du cluster
+        if let Some(index) = self.free_extents.as_mut() {
+            if value == 0 {
+                index.insert(cluster);
+            } else {
+                index.remove(cluster);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_cluster(&self, cluster: u32) -> Result<Option<u32>, FatError> {
+        self.as_read().next_cluster(cluster)
+    }
+
+    fn follow_chain(&self, start_cluster: u32) -> Result<Vec<u32>, FatError> {
+        self.as_read().follow_chain(start_cluster)
+    }
+
+    fn free_chain(&mut self, start_cluster: u32) -> Result<(), FatError> {
+        if start_cluster < 2 {
+            return Ok(());
+        }
+        let chain = self.follow_chain(start_cluster)?;
+        for &cl in &chain {
+            self.write_fat_entry_all(cl, 0)?;
+        }
+        self.update_fs_info(chain.len() as i64, None)
+    }
+
+    /// Alloue `needed` clusters, chaînés dans l’ordre (dernier -> EOC).
+    ///
+    /// La plus petite plage libre qui contient tout est choisie (fichier contigu);
+    /// à défaut, la chaîne est composée du moins de plages possible.
+    fn alloc_chain(&mut self, needed: usize) -> Result<Vec<u32>, FatError> {
+        if needed == 0 {
+            return Ok(Vec::new());
+        }
+
+        let found = self.pick_free(needed)?;
+        self.link_allocated(&found)?;
+        Ok(found)
+    }
+
+    /// Choisit `needed` clusters libres comme `alloc_chain`, sans les marquer dans la FAT.
+    ///
+    /// Sert à écrire les données avant la FAT : l’appelant remplit les clusters puis
+    /// appelle `link_allocated`, sans autre allocation entre les deux.
+    ///
+    /// Erreurs :
+    /// - `NoSpaceLeft` s’il n’y a pas assez de clusters libres.
+    fn pick_free(&mut self, needed: usize) -> Result<Vec<u32>, FatError> {
+        let hint = self.next_free_hint()?;
+        let picked = self
+            .free_index()?
+            .pick(needed as u32, hint)
+            .ok_or(FatError::NoSpaceLeft)?;
+        Ok(picked
+            .iter()
+            .flat_map(|e| e.start..e.start + e.len)
+            .collect())
+    }
+
+    /// Alloue `needed` clusters aux numéros consécutifs, chaînés dans l’ordre.
+    ///
+    /// On essaie d’abord la plage qui commence à `near` (en général le cluster
+    /// qui suit la fin d’un fichier), puis la plus petite plage libre assez longue.
+    ///
+    /// Erreurs :
+    /// - `NoSpaceLeft` si aucune plage libre de `needed` clusters n’existe.
+    fn alloc_contiguous(&mut self, needed: usize, near: Option<u32>) -> Result<Vec<u32>, FatError> {
+        if needed == 0 {
+            return Ok(Vec::new());
+        }
+
+        let needed = needed as u32;
+        let hint = self.next_free_hint()?;
+        let index = self.free_index()?;
+        let start = near
+            .filter(|&c| index.covers(c, needed))
+            .or_else(|| index.best_fit(needed, hint))
+            .ok_or(FatError::NoSpaceLeft)?;
+
+        let found: Vec<u32> = (start..start + needed).collect();
+        self.link_allocated(&found)?;
+        Ok(found)
+    }
+
+    /// Indice “prochain cluster libre” de FSInfo, qui départage les plages libres
+    /// équivalentes; 2 (début du volume) s’il est absent, inconnu ou hors du volume.
+    fn next_free_hint(&self) -> Result<u32, FatError> {
+        let max_cl = self.max_cluster_number()?;
+        Ok(self
+            .fs_info
+            .map(|info| info.next_free)
+            .filter(|next| (2..=max_cl).contains(next))
+            .unwrap_or(2))
+    }
+
+    /// Index des plages libres, construit depuis la FAT au premier appel.
+    fn free_index(&mut self) -> Result<&mut FreeExtents, FatError> {
+        if self.free_extents.is_none() {
+            let mut index = FreeExtents::default();
+            self.as_read().for_each_fat_entry(|cl, value| {
+                if value == 0 {
+                    index.push(cl);
+                }
+            })?;
+            self.free_extents = Some(index);
+        }
+        Ok(self.free_extents.as_mut().unwrap())
+    }
+
+    /// Chaîne des clusters libres qui viennent d’être choisis (`cl[i] -> cl[i+1]`,
+    /// dernier -> EOC) et met à jour FSInfo.
+    ///
+    /// Les entrées sont écrites de la fin vers le début : interrompue, la FAT ne
+    /// contient qu’une fin de chaîne complète, jamais un lien vers un cluster libre.
+    fn link_allocated(&mut self, found: &[u32]) -> Result<(), FatError> {
+        for i in (0..found.len()).rev() {
+            let v = if i + 1 < found.len() { found[i + 1] } else { FAT32_EOC };
+            self.write_fat_entry_all(found[i], v)?;
+        }
+
+        let max_cl = self.max_cluster_number()?;
+        let last = found[found.len() - 1];
+        let next = if last < max_cl { last + 1 } else { 2 };
+        self.update_fs_info(-(found.len() as i64), Some(next))
+    }
+
+    /// Met à jour le secteur FSInfo après une allocation ou une libération.
+    ///
+    /// - `delta` : variation du nombre de clusters libres (le compteur n’est
+    ///   touché que s’il est connu et plausible),
+    /// - `next_free` : nouvel indice de prochain cluster libre, si on en a un.
+    fn update_fs_info(&mut self, delta: i64, next_free: Option<u32>) -> Result<(), FatError> {
+        let Some(mut info) = self.fs_info else {
+            return Ok(());
+        };
+
+        let total = self.max_cluster_number()? - 1;
+        if let Some(count) = info.known_free_count(total) {
+            info.free_count = (count as i64 + delta).clamp(0, total as i64) as u32;
+        }
+        if let Some(next) = next_free {
+            info.next_free = next;
+        }
+
+        if Some(info) != self.fs_info {
+            self.fs_info = Some(info);
+            self.write_bytes(info.offset + fs_info::FREE_COUNT_OFFSET, &info.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_chain_data(&mut self, chain: &[u32], content: &[u8]) -> Result<(), FatError> {
+        let cs = self.cluster_size();
+        let mut pos = 0usize;
+        let mut buf = alloc::vec![0u8; cs];
+
+        for &cl in chain {
+            let off = self.cluster_to_offset(cl)?;
+
+            let end = core::cmp::min(pos + cs, content.len());
+            let chunk = &content[pos..end];
+
+            // Nettoyage du reste du cluster (c’est plus propre pour les tests et pour “cat”)
+            buf[..chunk.len()].copy_from_slice(chunk);
+            buf[chunk.len()..].fill(0);
+            self.write_bytes(off, &buf)?;
+
+            pos = end;
+            if pos >= content.len() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Cluster de départ du répertoire `path` (la racine pour `"/"`).
+    fn resolve_dir_cluster(&self, path: &str) -> Result<u32, FatError> {
+        if path == "/" {
+            return Ok(self.bpb.root_cluster);
+        }
+
+        let entry = self
+            .as_read()
+            .open_path(path)?
+            .ok_or(FatError::PathNotFound)?;
+        if !entry.is_dir() {
+            return Err(FatError::NotADirectory);
+        }
+        Ok(entry.first_cluster)
+    }
+
+    /// Trouve l’entrée désignée par `path` et le cluster de son répertoire parent.
+    fn locate(&self, path: &str) -> Result<(u32, LocatedEntry), FatError> {
+        if !path.starts_with('/') || path == "/" {
+            return Err(FatError::Other);
+        }
+
+        let (parent_path, name) = split_parent(path)?;
+        let parent_cluster = self.resolve_dir_cluster(parent_path)?;
+        let located = self
+            .as_read()
+            .find_in_dir(parent_cluster, name)?
+            .ok_or(FatError::PathNotFound)?;
+        Ok((parent_cluster, located))
+    }
+
+    /// Refuse de modifier une entrée en lecture seule, sauf avec [`MountOptions::force`].
+    fn check_writable(&self, entry: &DirEntry) -> Result<(), FatError> {
+        if entry.attrs.read_only && !self.options.force {
+            return Err(FatError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Marque une entrée (et ses LFN) supprimée, puis libère sa chaîne de clusters.
+    fn delete_located(&mut self, located: &LocatedEntry) -> Result<(), FatError> {
+        self.mark_deleted(located)?;
+        self.write_barrier()?;
+        self.free_chain(located.entry.first_cluster)
+    }
+
+    /// Marque une entrée courte et ses entrées LFN supprimées (`0xE5`).
+    ///
+    /// La chaîne de clusters n’est pas touchée (utile pour un déplacement).
+    fn mark_deleted(&mut self, located: &LocatedEntry) -> Result<(), FatError> {
+        for &off in located.lfn_offsets.iter().chain(core::iter::once(&located.offset)) {
+            self.write_bytes(off, &[0xE5])?;
+        }
+        Ok(())
+    }
+
+    /// Réécrit le premier cluster (champs haut et bas) d’une entrée courte.
+    fn set_entry_cluster(&mut self, offset: usize, cluster: u32) -> Result<(), FatError> {
+        let hi = ((cluster >> 16) as u16).to_le_bytes();
+        let lo = ((cluster & 0xFFFF) as u16).to_le_bytes();
+        self.write_bytes(offset + 20, &hi)?;
+        self.write_bytes(offset + 26, &lo)
+    }
+
+    /// Date la modification (et l’accès) de l’entrée courte située à `offset`.
+    fn touch_entry(&mut self, offset: usize) -> Result<(), FatError> {
+        let (date, time, _) = self.clock.now().to_fat();
+        self.write_bytes(offset + 18, &date.to_le_bytes())?;
+        let mut stamp = [0u8; 4];
+        stamp[0..2].copy_from_slice(&time.to_le_bytes());
+        stamp[2..4].copy_from_slice(&date.to_le_bytes());
+        self.write_bytes(offset + 22, &stamp)
+    }
+
+    /// Crée une nouvelle entrée `name` dans le répertoire `dir_cluster`.
+    ///
+    /// Si `name` n’est pas un 8.3 strict, on génère un alias court unique (`~N`)
+    /// et on écrit la séquence LFN juste avant l’entrée courte,
+    /// dans des slots consécutifs du répertoire.
+    ///
+    /// Retourne l’offset de l’entrée courte écrite.
+    fn create_dir_entry(
+        &mut self,
+        dir_cluster: u32,
+        name: &str,
+        attr: u8,
+        first_cluster: u32,
+        size: u32,
+    ) -> Result<usize, FatError> {
+        let taken: Vec<[u8; 11]> = self
+            .as_read()
+            .scan_dir(dir_cluster)?
+            .iter()
+            .map(|located| {
+                let mut short = [0u8; 11];
+                short.copy_from_slice(&located.raw[0..11]);
+                short
+            })
+            .collect();
+
+        let (short, needs_lfn) = short_name_for(name, &taken)?;
+        let lfn = if needs_lfn {
+            lfn_entries(name, &short)
+        } else {
+            Vec::new()
+        };
+
+        let (slots, end_after) = self.find_free_dir_slots(dir_cluster, lfn.len() + 1)?;
+
+        // Si on consomme le 0x00 (end-of-dir), on remet un 0x00 juste après, *avant*
+        // d’écrire l’entrée : une coupure ne rend jamais visibles les slots qui suivent.
+        if let Some(next) = end_after {
+            self.write_bytes(next, &[0x00])?;
+            self.write_barrier()?;
+        }
+
+        for (raw, &off) in lfn.iter().zip(slots.iter()) {
+            self.write_bytes(off, raw)?;
+        }
+        if !lfn.is_empty() {
+            self.write_barrier()?;
+        }
+        let short_off = slots[slots.len() - 1];
+        self.write_dir_entry_at_offset(short_off, &short, attr, first_cluster, size)?;
+
+        Ok(short_off)
+    }
+
+    /// Trouve `count` slots libres consécutifs dans un répertoire.
+    ///
+    /// Si la chaîne du répertoire est pleine, on l’agrandit avec des clusters neufs
+    /// (remplis de `0x00`) chaînés dans la FAT après le dernier cluster.
+    ///
+    /// Retourne:
+    /// - les offsets des slots dans l’image, dans l’ordre du répertoire
+    /// - `end_after`: le slot qui suit le dernier réservé, si on a consommé
+    ///   la fin de répertoire (`0x00`) et qu’il faut la reporter plus loin
+    fn find_free_dir_slots(
+        &mut self,
+        dir_cluster: u32,
+        count: usize,
+    ) -> Result<(Vec<usize>, Option<usize>), FatError> {
+        let cs = self.cluster_size();
+        let regions = self.as_read().dir_regions(dir_cluster)?;
+
+        let mut run: Vec<usize> = Vec::with_capacity(count);
+        let mut end_seen = false;
+
+        for &(off, len) in &regions {
+            let mut data = alloc::vec![0u8; len];
+            self.read_bytes(off, &mut data)?;
+
+            for (i, slot) in (off..off + len).step_by(32).enumerate() {
+                if run.len() == count {
+                    return Ok((run, if end_seen { Some(slot) } else { None }));
+                }
+
+                // Après un 0x00, tout le reste du répertoire est libre
+                let first = data[i * 32];
+                if end_seen || first == 0x00 {
+                    end_seen = true;
+                    run.push(slot);
+                } else if first == 0xE5 {
+                    run.push(slot);
+                } else {
+                    run.clear();
+                }
+            }
+        }
+
+        // Le dernier slot du répertoire complète la séquence : rien à étendre
+        if run.len() == count {
+            return Ok((run, None));
+        }
+
+        // La racine fixe (FAT12/16) a une taille figée au formatage
+        if self.bpb.is_fixed_root(dir_cluster) {
+            return Err(FatError::NoSpaceLeft);
+        }
+
+        // Plus de place : on étend le répertoire. Les slots libres en fin de chaîne
+        // restent dans `run`, la séquence continue dans le cluster suivant.
+        let chain = self.follow_chain(self.bpb.dir_start_cluster(dir_cluster))?;
+        let mut last = *chain.last().ok_or(FatError::InvalidCluster)?;
+        while run.len() < count {
+            let cl = self.pick_free(1)?[0];
+            self.zero_cluster(cl)?;
+            self.write_barrier()?;
+            self.link_allocated(&[cl])?;
+            self.write_barrier()?;
+            self.write_fat_entry_all(last, cl)?;
+            self.write_barrier()?;
+            last = cl;
+
+            let off = self.cluster_to_offset(cl)?;
+            for slot in (off..off + cs).step_by(32) {
+                if run.len() == count {
+                    break;
+                }
+                run.push(slot);
+            }
+        }
+
+        // Le cluster neuf est déjà rempli de 0x00 : la fin de répertoire est en place
+        Ok((run, None))
+    }
+
+    /// Met tous les octets d’un cluster à zéro.
+    fn zero_cluster(&mut self, cluster: u32) -> Result<(), FatError> {
+        let off = self.cluster_to_offset(cluster)?;
+        let zeros = alloc::vec![0u8; self.cluster_size()];
+        self.write_bytes(off, &zeros)
+    }
+
+    /// Remplit un cluster de répertoire neuf : `.`, `..`, puis des zéros.
+    fn init_dir_cluster(&mut self, cluster: u32, parent_cluster: u32) -> Result<(), FatError> {
+        self.zero_cluster(cluster)?;
+
+        let off = self.cluster_to_offset(cluster)?;
+        self.write_dir_entry_at_offset(off, b".          ", ATTR_DIRECTORY, cluster, 0)?;
+        self.write_dir_entry_at_offset(off + 32, b"..         ", ATTR_DIRECTORY, parent_cluster, 0)?;
+        Ok(())
+    }
+
+    fn write_dir_entry_at_offset(
+        &mut self,
+        offset: usize,
+        short: &[u8; 11],
+        attr: u8,
+        first_cluster: u32,
+        size: u32,
+    ) -> Result<(), FatError> {
+        let e = self.dir_entry_bytes(short, attr, first_cluster, size);
+        self.write_bytes(offset, &e)
+    }
+
+    /// Entrée courte de 32 octets, datée de l’heure courante.
+    fn dir_entry_bytes(&self, short: &[u8; 11], attr: u8, first_cluster: u32, size: u32) -> [u8; 32] {
+        let hi = ((first_cluster >> 16) as u16).to_le_bytes();
+        let lo = ((first_cluster & 0xFFFF) as u16).to_le_bytes();
+        let size_bytes = size.to_le_bytes();
+
+        let mut e = [0u8; 32];
+
+        // Name + ext
+        e[0..11].copy_from_slice(short);
+
+        // Attributs : archive (fichier) ou répertoire
+        e[11] = attr;
+
+        // Création, accès et modification : tous à la date courante
+        let (date, time, hundredths) = self.clock.now().to_fat();
+        e[13] = hundredths;
+        e[14..16].copy_from_slice(&time.to_le_bytes());
+        e[16..18].copy_from_slice(&date.to_le_bytes());
+        e[18..20].copy_from_slice(&date.to_le_bytes());
+
+        // First cluster high
+        e[20] = hi[0];
+        e[21] = hi[1];
+
+        e[22..24].copy_from_slice(&time.to_le_bytes());
+        e[24..26].copy_from_slice(&date.to_le_bytes());
+
+        // First cluster low
+        e[26] = lo[0];
+        e[27] = lo[1];
+
+        // Size
+        e[28..32].copy_from_slice(&size_bytes);
+
+        e
+    }
+}
+
+// ---------- helpers BPB + path + nom 8.3 ----------
+
+#[derive(Debug, Clone, Copy)]
+/// Paramètres du BPB nécessaires pour naviguer dans le volume
+///
+/// On ne lit que ce qui sert à calculer les offsets et tailles comme par exemple : 
+/// taille de secteur, taille de cluster, FAT, cluster racine...
+struct BpbParams {
+    fat_type: FatType,
+    bytes_per_sector: u16,
+    sectors_per_cluster: u8,
+    reserved_sectors: u16,
+    num_fats: u8,
+    sectors_per_fat: u32,
+    /// Entrées de la racine fixe (FAT12/16), `0` en FAT32.
+    root_entry_count: u16,
+    /// Premier cluster de la racine en FAT32, `0` en FAT12/16 (racine fixe).
+    root_cluster: u32,
+    /// Secteur FSInfo (dans la zone réservée), `0` ou `0xFFFF` si absent.
+    fs_info_sector: u16,
+    cluster_count: u32,
+}
+
+/// Nombre maximal de clusters d’un volume FAT32.
+const FAT32_MAX_CLUSTERS: u32 = 0x0FFF_FFF5;
+
+/// Parse le BPB du secteur 0 et extrait les paramètres utiles.
+///
+/// Chaque champ est validé avant usage, avec une erreur qui dit lequel est faux :
+/// une image aléatoire est refusée ici plutôt que d’échouer plus loin en `OutOfBounds`.
+/// Le type de FAT se déduit du nombre de clusters de données.
+///
+/// Paramètres :
+/// - `disk` : au moins le premier secteur (512 octets) du volume.
+/// - `device_len` : taille du périphérique en octets, comparée au nombre total de secteurs.
+fn parse_bpb(disk: &[u8], device_len: usize) -> Result<BpbParams, FatError> {
+    if disk.len() < 512 {
+        return Err(FatError::BufferTooSmall);
+    }
+
+    let b = &disk[0..512];
+
+    if b[510] != 0x55 || b[511] != 0xAA {
+        return Err(FatError::BadBootSignature);
+    }
+
+    let bytes_per_sector = u16::from_le_bytes([b[11], b[12]]);
+    let sectors_per_cluster = b[13];
+    let reserved_sectors = u16::from_le_bytes([b[14], b[15]]);
+    let num_fats = b[16];
+    let root_entry_count = u16::from_le_bytes([b[17], b[18]]);
+    let total_sectors_16 = u16::from_le_bytes([b[19], b[20]]);
+    let fat_size_16 = u16::from_le_bytes([b[22], b[23]]);
+    let total_sectors_32 = u32::from_le_bytes([b[32], b[33], b[34], b[35]]);
+
+    if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096) {
+        return Err(FatError::InvalidBytesPerSector);
+    }
+    if !sectors_per_cluster.is_power_of_two() || sectors_per_cluster > 128 {
+        return Err(FatError::InvalidSectorsPerCluster);
+    }
+    if reserved_sectors == 0 {
+        return Err(FatError::InvalidReservedSectors);
+    }
+    if num_fats == 0 {
+        return Err(FatError::InvalidFatCount);
+    }
+
+    // Taille de FAT : champ 16 bits (FAT12/16) s’il est non nul, sinon champ FAT32
+    let sectors_per_fat = if fat_size_16 != 0 {
+        fat_size_16 as u32
+    } else {
+        u32::from_le_bytes([b[36], b[37], b[38], b[39]])
+    };
+    if sectors_per_fat == 0 {
+        return Err(FatError::InvalidFatSize);
+    }
+
+    // Nombre total de secteurs : champ 16 bits s’il est non nul, sinon 32 bits
+    let total_sectors = if total_sectors_16 != 0 {
+        total_sectors_16 as u32
+    } else {
+        total_sectors_32
+    };
+    if total_sectors == 0 {
+        return Err(FatError::InvalidTotalSectors);
+    }
+    if total_sectors as u64 * bytes_per_sector as u64 > device_len as u64 {
+        return Err(FatError::BufferTooSmall);
+    }
+
+    // Racine fixe (FAT12/16) : arrondie au secteur
+    let root_dir_sectors = (root_entry_count as u64 * 32).div_ceil(bytes_per_sector as u64);
+    let meta_sectors =
+        reserved_sectors as u64 + num_fats as u64 * sectors_per_fat as u64 + root_dir_sectors;
+    if meta_sectors >= total_sectors as u64 {
+        return Err(FatError::InvalidTotalSectors);
+    }
+
+    let cluster_count = (total_sectors as u64 - meta_sectors) / sectors_per_cluster as u64;
+    if cluster_count == 0 || cluster_count > FAT32_MAX_CLUSTERS as u64 {
+        return Err(FatError::InvalidClusterCount);
+    }
+    let cluster_count = cluster_count as u32;
+    let fat_type = FatType::from_cluster_count(cluster_count);
+
+    let (root_cluster, fs_info_sector) = if fat_type == FatType::Fat32 {
+        // Champs propres à FAT12/16 : toujours nuls en FAT32
+        if root_entry_count != 0 || fat_size_16 != 0 {
+            return Err(FatError::NotFat32);
+        }
+        let root_cluster = u32::from_le_bytes([b[44], b[45], b[46], b[47]]);
+        if root_cluster < 2 || root_cluster > cluster_count + 1 {
+            return Err(FatError::InvalidRootCluster);
+        }
+        (root_cluster, u16::from_le_bytes([b[48], b[49]]))
+    } else {
+        if fat_size_16 == 0 {
+            return Err(FatError::InvalidFatSize);
+        }
+        if root_entry_count == 0 {
+            return Err(FatError::InvalidRootEntryCount);
+        }
+        (0, 0)
+    };
+
+    // La FAT doit avoir une entrée par cluster, plus les deux réservées
+    let fat_bytes = sectors_per_fat as u64 * bytes_per_sector as u64;
+    if fat_type.entries_in(fat_bytes) < cluster_count as u64 + 2 {
+        return Err(FatError::InvalidFatSize);
+    }
+
+    Ok(BpbParams {
+        fat_type,
+        bytes_per_sector,
+        sectors_per_cluster,
+        reserved_sectors,
+        num_fats,
+        sectors_per_fat,
+        root_entry_count,
+        root_cluster,
+        fs_info_sector,
+        cluster_count,
+    })
+}
+
+/// Lit le secteur 0 du périphérique et parse le BPB.
+fn read_bpb<D: BlockDevice + ?Sized>(disk: &D) -> Result<BpbParams, FatError> {
+    if disk.sector_count() < 1 {
+        return Err(FatError::BufferTooSmall);
+    }
+    let mut sector = [0u8; SECTOR_SIZE];
+    disk.read_sector(0, &mut sector)?;
+    parse_bpb(&sector, device_len(disk))
+}
+
+/// Lit et valide le secteur FSInfo désigné par le BPB.
+///
+/// Un FSInfo absent, hors zone réservée ou sans ses signatures est ignoré :
+/// ce n’est qu’une aide, le volume reste utilisable sans.
+fn read_fs_info<D: BlockDevice + ?Sized>(
+    disk: &D,
+    bpb: &BpbParams,
+    disk_len: usize,
+) -> Option<FsInfo> {
+    let sector = bpb.fs_info_sector;
+    if bpb.fat_type != FatType::Fat32 || sector == 0 || sector == 0xFFFF || sector >= bpb.reserved_sectors {
+        return None;
+    }
+
+    let offset = sector as usize * bpb.bytes_per_sector();
+    if offset + 512 > disk_len {
+        return None;
+    }
+    let mut buf = [0u8; 512];
+    read_bytes(disk, offset, &mut buf).ok()?;
+    FsInfo::parse(&buf, offset)
+}
+
+/// Taille du périphérique en octets.
+fn device_len<D: BlockDevice + ?Sized>(disk: &D) -> usize {
+    (disk.sector_count() as usize).saturating_mul(SECTOR_SIZE)
+}
+
+impl BpbParams {
+    fn bytes_per_sector(&self) -> usize {
+        self.bytes_per_sector as usize
+    }
+
+    fn cluster_size(&self) -> usize {
+        self.bytes_per_sector() * self.sectors_per_cluster as usize
+    }
+
+    fn fat_start_byte(&self) -> usize {
+        self.reserved_sectors as usize * self.bytes_per_sector()
+    }
+
+    fn fat_bytes_len(&self) -> usize {
+        self.sectors_per_fat as usize * self.bytes_per_sector()
+    }
+
+    /// Début de la racine fixe (FAT12/16) : juste après les FAT.
+    fn root_dir_start_byte(&self) -> usize {
+        self.fat_start_byte() + self.num_fats as usize * self.fat_bytes_len()
+    }
+
+    /// Taille de la racine fixe en octets, arrondie au secteur (`0` en FAT32).
+    fn root_dir_len(&self) -> usize {
+        (self.root_entry_count as usize * 32).div_ceil(self.bytes_per_sector()) * self.bytes_per_sector()
+    }
+
+    fn data_start_byte(&self) -> usize {
+        self.root_dir_start_byte() + self.root_dir_len()
+    }
+
+    /// Nombre de clusters de données du volume.
+    fn cluster_count(&self) -> u32 {
+        self.cluster_count
+    }
+
+    /// `true` si `dir_cluster` désigne la racine fixe d’un volume FAT12/16.
+    fn is_fixed_root(&self, dir_cluster: u32) -> bool {
+        dir_cluster == 0 && self.fat_type != FatType::Fat32
+    }
+
+    /// Premier cluster de la chaîne d’un répertoire (`0` = racine).
+    fn dir_start_cluster(&self, dir_cluster: u32) -> u32 {
+        if dir_cluster == 0 {
+            self.root_cluster
+        } else {
+            dir_cluster
+        }
+    }
+
+    /// Offset (en octets) du premier octet du cluster `cluster` (>= 2).
+    fn cluster_offset(&self, cluster: u32) -> usize {
+        self.data_start_byte() + (cluster - 2) as usize * self.cluster_size()
+    }
+}
+
+/// Découpe `"/A/B/C.TXT"` en (`"/A/B"`, `"C.TXT"`).
+fn split_parent(path: &str) -> Result<(&str, &str), FatError> {
+    let path = path.trim_end_matches('/');
+    if path == "/" {
+        return Err(FatError::Other);
+    }
+
+    let idx = path.rfind('/').ok_or(FatError::Other)?;
+    let parent = if idx == 0 { "/" } else { &path[..idx] };
+    let name = &path[idx + 1..];
+
+    if name.is_empty() {
+        return Err(FatError::Other);
+    }
+    Ok((parent, name))
+}
+
+/// Encode un nom en format court 8.3, s’il en est un.
+///
+/// Exemples :
+/// - `"HELLO.TXT"` -> `"HELLO   TXT"`
+/// - `"DIR"`       -> `"DIR     "` + `"   "`
+///
+/// Retourne `None` si le nom n’est pas un 8.3 strict :
+/// - 1 point max (séparateur extension), base <= 8, ext <= 3
+/// - uniquement des caractères autorisés dans un nom court
+///
+/// Les minuscules sont acceptées et passées en majuscules.
+fn encode_short_name_8_3(name: &str) -> Option<[u8; 11]> {
+    let mut base = name;
+    let mut ext = "";
+
+    if let Some(dot) = name.rfind('.') {
+        base = &name[..dot];
+        ext = &name[dot + 1..];
+    }
+
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 {
+        return None;
+    }
+
+    let mut out = [b' '; 11];
+
+    for (i, ch) in base.bytes().enumerate() {
+        if !is_short_name_char(ch) {
+            return None;
+        }
+        out[i] = ch.to_ascii_uppercase();
+    }
+
+    for (i, ch) in ext.bytes().enumerate() {
+        if !is_short_name_char(ch) {
+            return None;
+        }
+        out[8 + i] = ch.to_ascii_uppercase();
+    }
+
+    Some(out)
+}
+
+/// Caractères autorisés dans un nom court (hors espace de padding).
+fn is_short_name_char(ch: u8) -> bool {
+    ch.is_ascii_alphanumeric() || b"$%'-_@~`!(){}^#&".contains(&ch)
+}
+
+/// Choisit le nom court (11 octets bruts) d’une nouvelle entrée `name`.
+///
+/// Retourne aussi `true` si un nom long (LFN) doit accompagner l’entrée :
+/// - nom 8.3 en majuscules -> nom court seul
+/// - nom 8.3 avec des minuscules -> même nom court + LFN (pour garder la casse)
+/// - sinon -> alias `BASE~N.EXT` unique dans `taken` + LFN
+fn short_name_for(name: &str, taken: &[[u8; 11]]) -> Result<([u8; 11], bool), FatError> {
+    if let Some(short) = encode_short_name_8_3(name) {
+        let has_lower = name.bytes().any(|b| b.is_ascii_lowercase());
+        return Ok((short, has_lower));
+    }
+
+    // Nom de base : on retire les points de tête, puis on sépare sur le dernier point
+    let trimmed = name.trim_start_matches('.');
+    let (base_part, ext_part) = match trimmed.rfind('.') {
+        Some(dot) => (&trimmed[..dot], &trimmed[dot + 1..]),
+        None => (trimmed, ""),
+    };
+
+    let base: Vec<u8> = base_part.chars().filter_map(short_alias_char).take(8).collect();
+    let ext: Vec<u8> = ext_part.chars().filter_map(short_alias_char).take(3).collect();
+    let base = if base.is_empty() { alloc::vec![b'_'] } else { base };
+
+    for n in 1..=999_999u32 {
+        let candidate = short_name_with_tail(&base, &ext, n);
+        if !taken.contains(&candidate) {
+            return Ok((candidate, true));
+        }
+    }
+
+    Err(FatError::NoSpaceLeft)
+}
+
+/// Convertit un caractère de nom long en caractère d’alias court.
+///
+/// Les espaces et les points sont supprimés, les caractères interdits
+/// dans un nom court (non ASCII, `+,;=[]`...) deviennent `_`.
+fn short_alias_char(c: char) -> Option<u8> {
+    if c == ' ' || c == '.' {
+        return None;
+    }
+    if c.is_ascii() && is_short_name_char(c as u8) {
+        Some((c as u8).to_ascii_uppercase())
+    } else {
+        Some(b'_')
+    }
+}
+
+/// Assemble `BASE~N` + `EXT` en nom court brut de 11 octets.
+fn short_name_with_tail(base: &[u8], ext: &[u8], n: u32) -> [u8; 11] {
+    let mut digits = [0u8; 10];
+    let mut len = 0;
+    let mut v = n;
+    loop {
+        digits[len] = b'0' + (v % 10) as u8;
+        len += 1;
+        v /= 10;
+        if v == 0 {
+            break;
+        }
+    }
+
+    let keep = core::cmp::min(base.len(), 8 - (len + 1));
+
+    let mut out = [b' '; 11];
+    out[..keep].copy_from_slice(&base[..keep]);
+    out[keep] = b'~';
+    for i in 0..len {
+        out[keep + 1 + i] = digits[len - 1 - i];
+    }
+    out[8..8 + ext.len()].copy_from_slice(ext);
+    out
+}
+
+/// Division entière avec arrondi vers le haut.
+fn div_ceil(a: usize, b: usize) -> usize {
+    if b == 0 { 0 } else { a.div_ceil(b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Secteurs de FAT de l’image de test (512 * 128 entrées couvrent tous les clusters).
+    const TEST_FAT_SECTORS: usize = 512;
+    /// Clusters de données de l’image de test : le minimum pour un volume FAT32.
+    const TEST_CLUSTERS: usize = 65525;
+    /// Taille de l’image de test, en secteurs.
+    const TEST_SECTORS: usize = 1 + TEST_FAT_SECTORS + TEST_CLUSTERS;
+
+    /// Offset (en octets) du cluster `cluster` dans l’image de test.
+    fn cluster_off(cluster: u32) -> usize {
+        (1 + TEST_FAT_SECTORS + cluster as usize - 2) * 512
+    }
+
+    /// Plus petit volume FAT32 valide, en mémoire :
+    /// - 1 secteur BPB
+    /// - 1 FAT de 512 secteurs
+    /// - cluster 2 = racine : HELLO.TXT + DIR
+    /// - cluster 3 = contenu "HELLO"
+    /// - cluster 4 = répertoire DIR (vide : `.` et `..`)
+    ///
+    /// Les clusters 5.. sont libres, ce qui permet de tester l’écriture.
+    /// L’image fait ~33 Mio mais `vec![0; n]` ne touche que les pages écrites.
+    fn build_test_image() -> Vec<u8> {
+        const SECTOR_SIZE: usize = 512;
+        let mut disk = vec![0u8; SECTOR_SIZE * TEST_SECTORS];
+
+        // BPB
+        {
+            let b = &mut disk[0..SECTOR_SIZE];
+
+            // bytes_per_sector = 512
+            b[11] = 0x00;
+            b[12] = 0x02;
+
+            // sectors_per_cluster = 1
+            b[13] = 0x01;
+
+            // reserved_sectors = 1
+            b[14] = 0x01;
+            b[15] = 0x00;
+
+            // num_fats = 1
+            b[16] = 0x01;
+
+            // total_sectors_32
+            b[32..36].copy_from_slice(&(TEST_SECTORS as u32).to_le_bytes());
+
+            // sectors_per_fat = 512
+            b[36] = 0x00;
+            b[37] = 0x02;
+            b[38] = 0x00;
+            b[39] = 0x00;
+
+            // root_cluster = 2
+            b[44] = 0x02;
+            b[45] = 0x00;
+            b[46] = 0x00;
+            b[47] = 0x00;
+
+            // signature du secteur de boot
+            b[510] = 0x55;
+            b[511] = 0xAA;
+        }
+
+        // FAT (secteur 1)
+        {
+            let fat_start = SECTOR_SIZE;
+            let fat = &mut disk[fat_start..fat_start + SECTOR_SIZE];
+
+            let eoc_bytes = FAT32_EOC.to_le_bytes();
+
+            // cluster 2 (root) -> EOC
+            fat[2 * 4..2 * 4 + 4].copy_from_slice(&eoc_bytes);
+            // cluster 3 (HELLO.TXT) -> EOC
+            fat[3 * 4..3 * 4 + 4].copy_from_slice(&eoc_bytes);
+            // cluster 4 (DIR) -> EOC
+            fat[4 * 4..4 * 4 + 4].copy_from_slice(&eoc_bytes);
+
+            // clusters 5.. = 0 -> libres
+        }
+
+        // root dir = cluster 2
+        {
+            let root_off = cluster_off(2);
+            let dir = &mut disk[root_off..root_off + SECTOR_SIZE];
+
+            // HELLO.TXT
+            let mut hello = [0u8; 32];
+            hello[0..8].copy_from_slice(b"HELLO   ");
+            hello[8..11].copy_from_slice(b"TXT");
+            hello[11] = 0x20; // archive file
+            // first_cluster = 3
+            hello[26] = 0x03;
+            hello[27] = 0x00;
+            // size = 5
+            hello[28] = 5;
+            dir[0..32].copy_from_slice(&hello);
+
+            // DIR
+            let mut subdir = [0u8; 32];
+            subdir[0..8].copy_from_slice(b"DIR     ");
+            subdir[8..11].copy_from_slice(b"   ");
+            subdir[11] = 0x10; // directory
+            // first_cluster = 4
+            subdir[26] = 0x04;
+            subdir[27] = 0x00;
+            dir[32..64].copy_from_slice(&subdir);
+
+            // end of dir
+            dir[64] = 0x00;
+        }
+
+        // cluster 3 data
+        {
+            let off = cluster_off(3);
+            disk[off..off + 5].copy_from_slice(b"HELLO");
+        }
+
+        // cluster 4 data (DIR empty)
+        {
+            let off = cluster_off(4);
+            put_dot_entries(&mut disk[off..off + 64], 4, 0);
+        }
+
+        disk
+    }
+
+    /// Écrit les entrées `.` et `..` d’un répertoire au début de `dir`.
+    fn put_dot_entries(dir: &mut [u8], cluster: u16, parent: u16) {
+        let dots = [(b".          ", cluster), (b"..         ", parent)];
+        for (i, (name, cl)) in dots.into_iter().enumerate() {
+            let e = &mut dir[i * 32..i * 32 + 32];
+            e[0..11].copy_from_slice(name);
+            e[11] = 0x10;
+            e[26..28].copy_from_slice(&cl.to_le_bytes());
+        }
+    }
+
+    /// Écrit la valeur brute `value` dans l’entrée FAT de `cluster` (image de test FAT32).
+    fn set_fat_raw(disk: &mut [u8], cluster: u32, value: u32) {
+        let off = 512 + cluster as usize * 4;
+        disk[off..off + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn fat_entry_raw(disk: &[u8], cluster: u32) -> u32 {
+        // Dans notre image de test: reserved=1, bytes_per_sector=512 donc FAT start = 512.
+        let fat_start = 512usize;
+        let off = fat_start + cluster as usize * 4;
+        let bytes = &disk[off..off + 4];
+        let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        v & 0x0FFF_FFFF
+    }
+
+    /// Ajoute à la racine de l’image de test `config.json` (alias `CONFIG~1.JSO`)
+    /// à la place du marqueur de fin, en pointant sur le contenu de HELLO.TXT.
+    fn add_lfn_file(disk: &mut [u8], corrupt_checksum: bool) {
+        let short = *b"CONFIG~1JSO";
+        let mut entries = lfn_entries("config.json", &short);
+        if corrupt_checksum {
+            entries[0][13] ^= 0xFF;
+        }
+
+        let mut file = [0u8; 32];
+        file[0..11].copy_from_slice(&short);
+        file[11] = 0x20;
+        file[26] = 0x03;
+        file[28] = 5;
+        entries.push(file);
+
+        let mut off = cluster_off(2) + 64;
+        for e in &entries {
+            disk[off..off + 32].copy_from_slice(e);
+            off += 32;
+        }
+        disk[off] = 0x00;
+    }
+
+    #[test]
+    fn new_on_too_small_buffer_fails() {
+        let tiny = [0u8; 128];
+        let err = Fat32::new(&tiny).unwrap_err();
+        assert_eq!(err, FatError::BufferTooSmall);
+    }
+
+    #[test]
+    fn parse_bpb_reports_the_invalid_field() {
+        let base = build_test_image();
+        let check = |patch: &dyn Fn(&mut [u8]), expected: FatError| {
+            let mut sector = base[..512].to_vec();
+            patch(&mut sector);
+            assert_eq!(parse_bpb(&sector, base.len()).unwrap_err(), expected);
+        };
+
+        // Octets quelconques non nuls : refusés dès la signature
+        check(&|b| b.fill(0x5A), FatError::BadBootSignature);
+        check(&|b| b[11..13].copy_from_slice(&768u16.to_le_bytes()), FatError::InvalidBytesPerSector);
+        check(&|b| b[13] = 3, FatError::InvalidSectorsPerCluster);
+        check(&|b| b[14] = 0, FatError::InvalidReservedSectors);
+        check(&|b| b[16] = 0, FatError::InvalidFatCount);
+        // Champ FAT16 non nul alors que le nombre de clusters est celui d’un FAT32
+        check(&|b| b[22] = 1, FatError::NotFat32);
+        // Racine fixe : moins de clusters, donc FAT16 sans `fat_size_16`
+        check(&|b| b[17] = 0x10, FatError::InvalidFatSize);
+        check(&|b| b[37] = 0x01, FatError::InvalidFatSize);
+        check(&|b| b[32..36].fill(0), FatError::InvalidTotalSectors);
+        check(&|b| b[13] = 2, FatError::InvalidFatSize);
+        check(
+            &|b| {
+                b[13] = 128;
+                b[19..21].copy_from_slice(&514u16.to_le_bytes());
+            },
+            FatError::InvalidClusterCount,
+        );
+        check(
+            &|b| {
+                b[22] = 1;
+                b[19..21].copy_from_slice(&102u16.to_le_bytes());
+            },
+            FatError::InvalidRootEntryCount,
+        );
+        check(&|b| b[44] = 0, FatError::InvalidRootCluster);
+        check(&|b| b[44..48].copy_from_slice(&70000u32.to_le_bytes()), FatError::InvalidRootCluster);
+
+        // Volume annoncé plus grand que le buffer
+        assert_eq!(
+            Fat32::new(&base[..base.len() - 512]).unwrap_err(),
+            FatError::BufferTooSmall
+        );
+        assert!(parse_bpb(&base[..512], base.len()).is_ok());
+    }
+
+    #[test]
+    fn list_root_and_read_file() {
+        let disk = build_test_image();
+        let fs = Fat32::new(&disk).expect("Fat32::new failed");
 
-        // Overwrite: on libère l’ancienne chaîne
-        if let Some(e) = existing_entry.as_ref() {
-            if e.is_dir() {
-                return Err(FatError::NotAFile);
-            }
-            if e.first_cluster >= 2 {
-                self.free_chain(e.first_cluster)?;
-            }
-        }
+        let root = fs.list_root().expect("list_root failed");
+        assert_eq!(root.len(), 2);
 
-        // Allocation des clusters nécessaires
-        let first_cluster = if content.is_empty() {
-            0u32
-        } else {
-            let needed = div_ceil(content.len(), self.cluster_size());
-            let chain = self.alloc_chain(needed)?;
-            self.write_chain_data(&chain, content)?;
-            chain[0]
-        };
+        let hello = root
+            .iter()
+            .find(|e| e.name == "HELLO.TXT")
+            .expect("HELLO.TXT missing");
+        let dir = root.iter().find(|e| e.name == "DIR").expect("DIR missing");
 
-        // Écriture / mise à jour de l’entrée de répertoire
-        let size = content.len() as u32;
+        assert!(hello.is_file());
+        assert!(dir.is_dir());
 
-        match existing_off {
-            Some(off) => {
-                self.write_dir_entry_at_offset(off, &name_raw, &ext_raw, first_cluster, size)?;
-            }
-            None => {
-                let (free_off, was_end_marker, entry_end_in_disk) =
-                    self.find_free_dir_entry_slot(parent_cluster)?;
-                self.write_dir_entry_at_offset(
-                    free_off,
-                    &name_raw,
-                    &ext_raw,
-                    first_cluster,
-                    size,
-                )?;
+        let content = fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap();
+        assert_eq!(content, b"HELLO");
+    }
 
-                // Si on a remplacé un 0x00 (end-of-dir), on remet un 0x00 juste après
-                // (si ça rentre dans le buffer). Ça garde un répertoire “propre”.
-                if was_end_marker {
-                    let next = free_off + 32;
-                    if next < entry_end_in_disk {
-                        self.disk[next] = 0x00;
-                    }
-                }
-            }
-        }
+    #[test]
+    fn list_dir_on_file_returns_not_a_directory() {
+        let disk = build_test_image();
+        let fs = Fat32::new(&disk).unwrap();
 
-        Ok(())
+        let err = fs.list_dir_path("/HELLO.TXT").unwrap_err();
+        assert_eq!(err, FatError::NotADirectory);
     }
 
-    // ---------- internes (écriture) ----------
+    #[test]
+    fn read_file_on_directory_via_open_path_returns_not_a_file() {
+        let disk = build_test_image();
+        let fs = Fat32::new(&disk).unwrap();
 
-    fn bytes_per_sector(&self) -> usize {
-        self.bytes_per_sector as usize
+        let entry = fs.open_path("/DIR").unwrap().unwrap();
+        assert!(entry.is_dir());
+
+        let err = fs.read_file(&entry).unwrap_err();
+        assert_eq!(err, FatError::NotAFile);
     }
 
-    fn cluster_size(&self) -> usize {
-        self.bytes_per_sector() * self.sectors_per_cluster as usize
+    #[test]
+    fn open_path_is_case_insensitive_for_short_names() {
+        let disk = build_test_image();
+        let fs = Fat32::new(&disk).unwrap();
+
+        let entry = fs.open_path("/hello.txt").unwrap().unwrap();
+        assert_eq!(entry.name, "HELLO.TXT");
+        assert!(entry.is_file());
     }
 
-    fn fat_start_byte(&self) -> usize {
-        self.reserved_sectors as usize * self.bytes_per_sector()
+    #[test]
+    fn list_root_exposes_long_file_names() {
+        let mut disk = build_test_image();
+        add_lfn_file(&mut disk, false);
+        let fs = Fat32::new(&disk).unwrap();
+
+        let root = fs.list_root().unwrap();
+        assert_eq!(root.len(), 3);
+
+        let cfg = root.iter().find(|e| e.name == "CONFIG~1.JSO").unwrap();
+        assert_eq!(cfg.long_name.as_deref(), Some("config.json"));
+        assert_eq!(cfg.display_name(), "config.json");
+
+        let hello = root.iter().find(|e| e.name == "HELLO.TXT").unwrap();
+        assert!(hello.long_name.is_none());
     }
 
-    fn fat_bytes_len(&self) -> usize {
-        self.sectors_per_fat as usize * self.bytes_per_sector()
+    #[test]
+    fn open_path_matches_long_and_short_names() {
+        let mut disk = build_test_image();
+        add_lfn_file(&mut disk, false);
+        let fs = Fat32::new(&disk).unwrap();
+
+        let by_long = fs.open_path("/Config.JSON").unwrap().unwrap();
+        let by_short = fs.open_path("/config~1.jso").unwrap().unwrap();
+        assert_eq!(by_long.name, by_short.name);
+
+        let content = fs.read_file_by_path("/config.json").unwrap().unwrap();
+        assert_eq!(content, b"HELLO");
     }
 
-    fn data_start_byte(&self) -> usize {
-        self.fat_start_byte()
-            + (self.num_fats as usize * self.sectors_per_fat as usize) * self.bytes_per_sector()
+    #[test]
+    fn lfn_with_bad_checksum_is_ignored() {
+        let mut disk = build_test_image();
+        add_lfn_file(&mut disk, true);
+        let fs = Fat32::new(&disk).unwrap();
+
+        let e = fs.open_path("/CONFIG~1.JSO").unwrap().unwrap();
+        assert!(e.long_name.is_none());
+        assert!(fs.open_path("/config.json").unwrap().is_none());
     }
 
-    /// Dernier cluster valide, borné à la fois par:
-    /// - la taille de la zone data
-    /// - le nombre d’entrées disponibles dans la FAT
-    fn max_cluster_number(&self) -> Result<u32, FatError> {
-        let data_start = self.data_start_byte();
-        if data_start >= self.disk.len() {
-            return Err(FatError::OutOfBounds);
-        }
+    #[test]
+    fn write_create_new_file_and_read_back() {
+        let mut disk = build_test_image();
 
-        let cs = self.cluster_size();
-        if cs == 0 {
-            return Err(FatError::NotFat32);
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/NEW.TXT", b"ABC").unwrap();
         }
 
-        let data_len = self.disk.len() - data_start;
-        let data_clusters = (data_len / cs) as u32;
-        if data_clusters == 0 {
-            return Err(FatError::NotFat32);
-        }
-        let last_by_data = 2 + data_clusters - 1;
+        let ro = Fat32::new(&disk).unwrap();
+        let content = ro.read_file_by_path("/NEW.TXT").unwrap().unwrap();
+        assert_eq!(content, b"ABC");
+    }
 
-        let fat_entries = (self.fat_bytes_len() / 4) as u32;
-        if fat_entries < 3 {
-            return Err(FatError::NotFat32);
+    #[test]
+    fn write_overwrite_existing_file() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/HELLO.TXT", b"HELLO WORLD").unwrap();
         }
-        let last_by_fat = fat_entries - 1;
 
-        Ok(core::cmp::min(last_by_data, last_by_fat))
+        let ro = Fat32::new(&disk).unwrap();
+        let content = ro.read_file_by_path("/HELLO.TXT").unwrap().unwrap();
+        assert_eq!(content, b"HELLO WORLD");
     }
 
-    fn cluster_to_offset(&self, cluster: u32) -> Result<usize, FatError> {
-        if cluster < 2 {
-            return Err(FatError::InvalidCluster);
-        }
-        let index = (cluster - 2) as usize;
-        let offset = self.data_start_byte() + index * self.cluster_size();
-        if offset >= self.disk.len() {
-            return Err(FatError::OutOfBounds);
-        }
-        Ok(offset)
+    #[test]
+    fn write_rejects_invalid_long_name() {
+        let mut disk = build_test_image();
+
+        let res = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/BAD*NAME.TXT", b"x")
+        };
+
+        assert_eq!(res.unwrap_err(), FatError::InvalidName);
     }
 
-    fn read_fat_entry(&self, cluster: u32) -> Result<u32, FatError> {
-        let fat_start = self.fat_start_byte();
-        let entry_offset = fat_start + cluster as usize * 4;
-        if entry_offset + 4 > self.disk.len() {
-            return Err(FatError::OutOfBounds);
+    #[test]
+    fn write_long_name_creates_lfn_and_unique_aliases() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/kernel-log-2026.txt", b"LOG1").unwrap();
+            rw.write_file_by_path("/kernel-log-2027.txt", b"LOG2").unwrap();
+            rw.write_file_by_path("/config.json", b"{}").unwrap();
         }
-        let bytes = &self.disk[entry_offset..entry_offset + 4];
-        let val = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-        Ok(val & 0x0FFF_FFFF)
-    }
 
-    fn write_fat_entry_all(&mut self, cluster: u32, value: u32) -> Result<(), FatError> {
-        let val = value & 0x0FFF_FFFF;
-        let bytes = val.to_le_bytes();
+        let ro = Fat32::new(&disk).unwrap();
+        let root = ro.list_root().unwrap();
 
-        let fat0 = self.fat_start_byte();
-        let fat_len = self.fat_bytes_len();
+        let log1 = root.iter().find(|e| e.display_name() == "kernel-log-2026.txt").unwrap();
+        let log2 = root.iter().find(|e| e.display_name() == "kernel-log-2027.txt").unwrap();
+        let cfg = root.iter().find(|e| e.display_name() == "config.json").unwrap();
+        assert_eq!(log1.name, "KERNEL~1.TXT");
+        assert_eq!(log2.name, "KERNEL~2.TXT");
+        assert_eq!(cfg.name, "CONFIG~1.JSO");
 
-        for i in 0..self.num_fats as usize {
-            let base = fat0 + i * fat_len;
-            let off = base + cluster as usize * 4;
-            if off + 4 > self.disk.len() {
-                return Err(FatError::OutOfBounds);
-            }
-            self.disk[off..off + 4].copy_from_slice(&bytes);
+        assert_eq!(ro.read_file_by_path("/kernel-log-2027.txt").unwrap().unwrap(), b"LOG2");
+        assert_eq!(ro.read_file_by_path("/KERNEL~1.TXT").unwrap().unwrap(), b"LOG1");
+    }
+
+    #[test]
+    fn write_lowercase_short_name_keeps_case_in_lfn() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/notes.md", b"x").unwrap();
         }
 
-        Ok(())
+        let ro = Fat32::new(&disk).unwrap();
+        let e = ro.open_path("/NOTES.MD").unwrap().unwrap();
+        assert_eq!(e.name, "NOTES.MD");
+        assert_eq!(e.long_name.as_deref(), Some("notes.md"));
     }
 
-    fn follow_chain(&self, start_cluster: u32, max_clusters: usize) -> Result<Vec<u32>, FatError> {
-        if start_cluster < 2 {
-            return Err(FatError::InvalidCluster);
+    #[test]
+    fn overwrite_by_long_name_reuses_entry() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/config.json", b"v1").unwrap();
+            rw.write_file_by_path("/CONFIG.JSON", b"version 2").unwrap();
         }
 
-        let mut result = Vec::new();
-        let mut current = start_cluster;
+        let ro = Fat32::new(&disk).unwrap();
+        let root = ro.list_root().unwrap();
+        assert_eq!(root.len(), 3);
+        assert_eq!(ro.read_file_by_path("/config.json").unwrap().unwrap(), b"version 2");
+    }
 
-        for _ in 0..max_clusters {
-            result.push(current);
-            let next = self.read_fat_entry(current)?;
-            if next >= 0x0FFF_FFF8 {
-                break;
-            }
-            if next < 2 {
-                return Err(FatError::InvalidCluster);
-            }
-            current = next;
-        }
+    #[test]
+    fn write_fails_when_parent_directory_missing() {
+        let mut disk = build_test_image();
+
+        let res = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/NOPE/FILE.TXT", b"x")
+        };
 
-        Ok(result)
+        assert_eq!(res.unwrap_err(), FatError::PathNotFound);
     }
 
-    fn free_chain(&mut self, start_cluster: u32) -> Result<(), FatError> {
-        if start_cluster < 2 {
-            return Ok(());
-        }
-        let chain = self.follow_chain(start_cluster, 4096)?;
-        for cl in chain {
-            self.write_fat_entry_all(cl, 0)?;
+    #[test]
+    fn create_dir_writes_dot_entries() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.create_dir("/BOOT").unwrap();
+            rw.create_dir("/DIR/Sub Folder").unwrap();
+            rw.write_file_by_path("/BOOT/KERNEL.BIN", b"K").unwrap();
         }
-        Ok(())
+
+        let ro = Fat32::new(&disk).unwrap();
+        let boot = ro.open_path("/BOOT").unwrap().unwrap();
+        assert!(boot.is_dir());
+        assert_eq!(boot.size, 0);
+
+        let entries = ro.list_dir_path("/BOOT").unwrap();
+        let dot = entries.iter().find(|e| e.name == ".").unwrap();
+        let dotdot = entries.iter().find(|e| e.name == "..").unwrap();
+        assert!(dot.is_dir() && dotdot.is_dir());
+        assert_eq!(dot.first_cluster, boot.first_cluster);
+        assert_eq!(dotdot.first_cluster, 0, "`..` d’un enfant de la racine vaut 0");
+        assert_eq!(ro.read_file_by_path("/BOOT/KERNEL.BIN").unwrap().unwrap(), b"K");
+
+        let sub = ro.list_dir_path("/DIR/sub folder").unwrap();
+        let dotdot = sub.iter().find(|e| e.name == "..").unwrap();
+        assert_eq!(dotdot.first_cluster, 4);
     }
 
-    fn alloc_chain(&mut self, needed: usize) -> Result<Vec<u32>, FatError> {
-        if needed == 0 {
-            return Ok(Vec::new());
+    #[test]
+    fn create_dir_all_creates_missing_parents() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.create_dir_all("/BOOT/CFG").unwrap();
+            // idempotent
+            rw.create_dir_all("/BOOT/CFG").unwrap();
+            assert_eq!(rw.create_dir("/BOOT").unwrap_err(), FatError::AlreadyExists);
+            assert_eq!(
+                rw.create_dir_all("/HELLO.TXT/X").unwrap_err(),
+                FatError::NotADirectory
+            );
         }
 
-        let max_cl = self.max_cluster_number()?;
-        let mut found = Vec::with_capacity(needed);
+        let ro = Fat32::new(&disk).unwrap();
+        assert!(ro.open_path("/BOOT/CFG").unwrap().unwrap().is_dir());
+    }
 
-        // Scan simple : cluster libre = entrée FAT == 0
-        for cl in 2..=max_cl {
-            if self.read_fat_entry(cl)? == 0 {
-                found.push(cl);
-                if found.len() == needed {
-                    break;
-                }
-            }
-        }
+    #[test]
+    fn remove_file_marks_lfn_entries_and_frees_chain() {
+        let mut disk = build_test_image();
 
-        if found.len() != needed {
-            return Err(FatError::NoSpaceLeft);
-        }
+        let first_cluster = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/kernel-log-2026.txt", &[b'L'; 600]).unwrap();
+            let e = rw.as_read().open_path("/kernel-log-2026.txt").unwrap().unwrap();
+            rw.remove_file("/kernel-log-2026.txt").unwrap();
+            e.first_cluster
+        };
 
-        // Chaînage : cl[i] -> cl[i+1], dernier -> EOC
-        for i in 0..found.len() {
-            let v = if i + 1 < found.len() { found[i + 1] } else { FAT32_EOC };
-            self.write_fat_entry_all(found[i], v)?;
+        assert_eq!(fat_entry_raw(&disk, first_cluster), 0);
+        assert_eq!(fat_entry_raw(&disk, first_cluster + 1), 0);
+
+        // 2 entrées LFN + 1 entrée courte, juste après HELLO.TXT et DIR
+        let root = cluster_off(2);
+        for slot in 2..5 {
+            assert_eq!(disk[root + slot * 32], 0xE5);
         }
 
-        Ok(found)
+        let ro = Fat32::new(&disk).unwrap();
+        assert!(ro.open_path("/kernel-log-2026.txt").unwrap().is_none());
+        assert!(ro.open_path("/KERNEL~1.TXT").unwrap().is_none());
+        assert_eq!(ro.list_root().unwrap().len(), 2);
     }
 
-    fn write_chain_data(&mut self, chain: &[u32], content: &[u8]) -> Result<(), FatError> {
-        let cs = self.cluster_size();
-        let mut pos = 0usize;
+    #[test]
+    fn remove_dir_refuses_non_empty_directory() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
 
-        for &cl in chain {
-            let off = self.cluster_to_offset(cl)?;
-            if off + cs > self.disk.len() {
-                return Err(FatError::OutOfBounds);
-            }
+        rw.create_dir("/TMP").unwrap();
+        rw.write_file_by_path("/TMP/A.TXT", b"a").unwrap();
 
-            let end = core::cmp::min(pos + cs, content.len());
-            let chunk = &content[pos..end];
+        assert_eq!(rw.remove_dir("/TMP").unwrap_err(), FatError::DirectoryNotEmpty);
+        assert_eq!(rw.remove_file("/TMP").unwrap_err(), FatError::NotAFile);
+        assert_eq!(rw.remove_dir("/TMP/A.TXT").unwrap_err(), FatError::NotADirectory);
 
-            self.disk[off..off + chunk.len()].copy_from_slice(chunk);
+        rw.remove_file("/TMP/A.TXT").unwrap();
+        rw.remove_dir("/TMP").unwrap();
+        rw.remove_dir("/DIR").unwrap();
 
-            // Nettoyage du reste du cluster (c’est plus propre pour les tests et pour “cat”)
-            for b in &mut self.disk[off + chunk.len()..off + cs] {
-                *b = 0;
-            }
+        let ro = rw.as_read();
+        assert!(ro.open_path("/TMP").unwrap().is_none());
+        assert_eq!(ro.list_root().unwrap().len(), 1);
+        assert_eq!(rw.remove_file("/NOPE.TXT").unwrap_err(), FatError::PathNotFound);
+    }
 
-            pos = end;
-            if pos >= content.len() {
-                break;
-            }
-        }
+    #[test]
+    fn rename_replaces_existing_file_and_frees_its_chain() {
+        let mut disk = build_test_image();
 
-        Ok(())
-    }
+        let (old_cluster, new_cluster) = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/APP.BIN", b"old").unwrap();
+            rw.write_file_by_path("/APP.NEW", b"new version").unwrap();
+            let ro = rw.as_read();
+            let old = ro.open_path("/APP.BIN").unwrap().unwrap().first_cluster;
+            let new = ro.open_path("/APP.NEW").unwrap().unwrap().first_cluster;
 
-    fn find_dir_entry_offset_by_short_name(
-        &self,
-        dir_cluster: u32,
-        name_raw: &[u8; 8],
-        ext_raw: &[u8; 3],
-    ) -> Result<(Option<usize>, Option<DirEntry>), FatError> {
-        let cs = self.cluster_size();
-        let chain = self.follow_chain(dir_cluster, 4096)?;
+            rw.rename("/APP.NEW", "/APP.BIN").unwrap();
+            (old, new)
+        };
 
-        for &cl in &chain {
-            let off = self.cluster_to_offset(cl)?;
-            if off + cs > self.disk.len() {
-                return Err(FatError::OutOfBounds);
-            }
+        assert_eq!(fat_entry_raw(&disk, old_cluster), 0);
+        assert!(fat_entry_raw(&disk, new_cluster) >= 0x0FFF_FFF8);
 
-            let data = &self.disk[off..off + cs];
-            for (i, chunk) in data.chunks(32).enumerate() {
-                if chunk.len() < 32 {
-                    break;
-                }
-                if chunk[0] == 0x00 {
-                    // fin de répertoire
-                    return Ok((None, None));
-                }
-                if chunk[0] == 0xE5 {
-                    continue;
-                }
+        let ro = Fat32::new(&disk).unwrap();
+        assert!(ro.open_path("/APP.NEW").unwrap().is_none());
+        let e = ro.open_path("/APP.BIN").unwrap().unwrap();
+        assert_eq!(e.first_cluster, new_cluster);
+        assert_eq!(ro.read_file_by_path("/APP.BIN").unwrap().unwrap(), b"new version");
+    }
 
-                // match strict sur les octets 8.3
-                if chunk[0..8] == name_raw[..] && chunk[8..11] == ext_raw[..] {
-                    let abs_off = off + i * 32;
-                    let parsed = DirEntry::parse(chunk);
-                    return Ok((Some(abs_off), parsed));
-                }
-            }
+    #[test]
+    fn rename_moves_directory_and_fixes_dotdot() {
+        let mut disk = build_test_image();
+
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.create_dir("/CFG").unwrap();
+            rw.write_file_by_path("/CFG/A.TXT", b"a").unwrap();
+            rw.rename("/CFG", "/DIR/Config Files").unwrap();
+            rw.rename("/HELLO.TXT", "/DIR/hello world.txt").unwrap();
         }
 
-        Ok((None, None))
+        let ro = Fat32::new(&disk).unwrap();
+        assert!(ro.open_path("/CFG").unwrap().is_none());
+        assert!(ro.open_path("/HELLO.TXT").unwrap().is_none());
+        assert_eq!(ro.read_file_by_path("/DIR/Config Files/A.TXT").unwrap().unwrap(), b"a");
+        assert_eq!(ro.read_file_by_path("/DIR/hello world.txt").unwrap().unwrap(), b"HELLO");
+
+        let entries = ro.list_dir_path("/DIR/config files").unwrap();
+        let dotdot = entries.iter().find(|e| e.name == "..").unwrap();
+        assert_eq!(dotdot.first_cluster, 4);
     }
 
-    /// Trouve un slot libre dans un répertoire.
-    ///
-    /// Retourne:
-    /// - l’offset dans `disk`
-    /// - `was_end_marker`: vrai si c’était un `0x00` (fin de répertoire)
-    /// - `entry_end_in_disk`: limite du cluster dans le buffer (pour écrire un 0x00 après)
-    fn find_free_dir_entry_slot(&self, dir_cluster: u32) -> Result<(usize, bool, usize), FatError> {
-        let cs = self.cluster_size();
-        let chain = self.follow_chain(dir_cluster, 4096)?;
+    #[test]
+    fn rename_refuses_moving_directory_into_itself() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
 
-        for &cl in &chain {
-            let off = self.cluster_to_offset(cl)?;
-            let cluster_end = off + cs;
+        rw.create_dir_all("/A/B").unwrap();
+        assert_eq!(rw.rename("/A", "/A/B/C").unwrap_err(), FatError::MoveIntoSubtree);
+        assert_eq!(rw.rename("/A", "/A/X").unwrap_err(), FatError::MoveIntoSubtree);
+        assert_eq!(rw.rename("/A", "/DIR").unwrap_err(), FatError::AlreadyExists);
 
-            if cluster_end > self.disk.len() {
-                return Err(FatError::OutOfBounds);
-            }
+        // simple changement de casse sur la même entrée
+        rw.rename("/A", "/a").unwrap();
+        let e = rw.as_read().open_path("/A/B").unwrap();
+        assert!(e.is_some());
+    }
 
-            let data = &self.disk[off..cluster_end];
-            for (i, chunk) in data.chunks(32).enumerate() {
-                if chunk.len() < 32 {
-                    break;
-                }
+    #[test]
+    fn full_directory_grows_its_cluster_chain() {
+        let mut disk = build_test_image();
 
-                if chunk[0] == 0x00 {
-                    return Ok((off + i * 32, true, cluster_end));
-                }
-                if chunk[0] == 0xE5 {
-                    return Ok((off + i * 32, false, cluster_end));
-                }
+        // Racine = 1 cluster de 16 slots, dont 2 utilisés : 40 fichiers vides
+        // demandent 2 clusters de répertoire supplémentaires.
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            for i in 0..40 {
+                rw.write_file_by_path(&format!("/F{i}.TXT"), b"").unwrap();
             }
         }
 
-        // Version simple: on n’alloue pas de nouveau cluster de répertoire.
-        Err(FatError::NoSpaceLeft)
-    }
+        let second = fat_entry_raw(&disk, 2);
+        assert!((2..0x0FFF_FFF8).contains(&second), "la racine doit avoir grandi");
+        let third = fat_entry_raw(&disk, second);
+        assert!((2..0x0FFF_FFF8).contains(&third));
+        assert!(fat_entry_raw(&disk, third) >= 0x0FFF_FFF8);
 
-    fn write_dir_entry_at_offset(
-        &mut self,
-        offset: usize,
-        name_raw: &[u8; 8],
-        ext_raw: &[u8; 3],
-        first_cluster: u32,
-        size: u32,
-    ) -> Result<(), FatError> {
-        if offset + 32 > self.disk.len() {
-            return Err(FatError::OutOfBounds);
-        }
+        let ro = Fat32::new(&disk).unwrap();
+        assert_eq!(ro.list_root().unwrap().len(), 42);
+        assert!(ro.open_path("/F39.TXT").unwrap().is_some());
+    }
 
-        let hi = ((first_cluster >> 16) as u16).to_le_bytes();
-        let lo = ((first_cluster & 0xFFFF) as u16).to_le_bytes();
-        let size_bytes = size.to_le_bytes();
+    #[test]
+    fn overwrite_frees_old_clusters_in_fat() {
+        let mut disk = build_test_image();
 
-        let e = &mut self.disk[offset..offset + 32];
+        // 600 bytes => 2 clusters (cluster_size=512)
+        let big = vec![0x41u8; 600];
 
-        // Name + ext
-        e[0..8].copy_from_slice(name_raw);
-        e[8..11].copy_from_slice(ext_raw);
+        let (first_cluster, second_cluster) = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/BIG.TXT", &big).unwrap();
 
-        // Attributs : archive (fichier)
-        e[11] = 0x20;
+            let ro = rw.as_read();
+            let e = ro.open_path("/BIG.TXT").unwrap().unwrap();
+            assert_eq!(e.size as usize, big.len());
+            assert!(e.first_cluster >= 2);
 
-        // Champs “date/heure” et divers : on met à zéro (écriture simple)
-        for b in &mut e[12..20] {
-            *b = 0;
-        }
+            let c1 = e.first_cluster;
+            let c2 = fat_entry_raw(&disk, c1);
+            assert!(c2 >= 2, "le fichier BIG.TXT devrait chaîner sur un 2e cluster");
+            assert!(fat_entry_raw(&disk, c2) >= 0x0FFF_FFF8, "le 2e cluster devrait être EOC");
 
-        // First cluster high
-        e[20] = hi[0];
-        e[21] = hi[1];
+            (c1, c2)
+        };
 
-        for b in &mut e[22..26] {
-            *b = 0;
+        // Overwrite en fichier vide => doit libérer les anciens clusters
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/BIG.TXT", b"").unwrap();
         }
 
-        // First cluster low
-        e[26] = lo[0];
-        e[27] = lo[1];
+        // FAT entries doivent être à 0 (libres)
+        assert_eq!(fat_entry_raw(&disk, first_cluster), 0);
+        assert_eq!(fat_entry_raw(&disk, second_cluster), 0);
 
-        // Size
-        e[28..32].copy_from_slice(&size_bytes);
+        let ro = Fat32::new(&disk).unwrap();
+        let e = ro.open_path("/BIG.TXT").unwrap().unwrap();
+        assert_eq!(e.size, 0);
+        assert_eq!(e.first_cluster, 0);
+    }
 
-        Ok(())
+    /// Périphérique de test qui compte les secteurs lus et écrits.
+    struct CountingDevice {
+        data: Vec<u8>,
+        reads: core::cell::Cell<u64>,
+        writes: u64,
     }
-}
 
-// ---------- helpers BPB + path + nom 8.3 ----------
+    impl BlockDevice for CountingDevice {
+        fn sector_count(&self) -> u64 {
+            self.data.sector_count()
+        }
 
-#[derive(Clone, Copy)]
-/// Paramètres du BPB nécessaires pour naviguer dans le volume
-///
-/// On ne lit que ce qui sert à calculer les offsets et tailles comme par exemple : 
-/// taille de secteur, taille de cluster, FAT, cluster racine...
-struct BpbParams {
-    bytes_per_sector: u16,
-    sectors_per_cluster: u8,
-    reserved_sectors: u16,
-    num_fats: u8,
-    sectors_per_fat: u32,
-    root_cluster: u32,
-}
+        fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+            self.reads.set(self.reads.get() + (buf.len() / SECTOR_SIZE) as u64);
+            self.data.read_sectors(start, buf)
+        }
 
-/// Parse le BPB du secteur 0 et extrait les paramètres utiles.
-///
-/// Effectue des vérifications minimales pour éviter un état incohérent.
-fn parse_bpb(disk: &[u8]) -> Result<BpbParams, FatError> {
-    if disk.len() < 512 {
-        return Err(FatError::BufferTooSmall);
+        fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+            self.writes += (buf.len() / SECTOR_SIZE) as u64;
+            self.data.write_sectors(start, buf)
+        }
     }
 
-    let b = &disk[0..512];
+    #[test]
+    fn block_device_reads_only_needed_sectors() {
+        let dev = CountingDevice {
+            data: build_test_image(),
+            reads: core::cell::Cell::new(0),
+            writes: 0,
+        };
 
-    let bytes_per_sector = u16::from_le_bytes([b[11], b[12]]);
-    let sectors_per_cluster = b[13];
-    let reserved_sectors = u16::from_le_bytes([b[14], b[15]]);
-    let num_fats = b[16];
-    let sectors_per_fat = u32::from_le_bytes([b[36], b[37], b[38], b[39]]);
-    let root_cluster = u32::from_le_bytes([b[44], b[45], b[46], b[47]]);
+        let fs = Fat32::from_device(dev).unwrap();
+        let content = fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap();
+        assert_eq!(content, b"HELLO");
 
-    // Checks minimalistes pour éviter un état incohérent
-    if bytes_per_sector == 0 || sectors_per_cluster == 0 || num_fats == 0 {
-        return Err(FatError::NotFat32);
-    }
-    if sectors_per_fat == 0 {
-        return Err(FatError::NotFat32);
+        // BPB + FAT + racine + données : quelques secteurs sur 66038
+        let reads = fs.device().reads.get();
+        assert!(reads < 10, "{reads} secteurs lus");
     }
 
-    Ok(BpbParams {
-        bytes_per_sector,
-        sectors_per_cluster,
-        reserved_sectors,
-        num_fats,
-        sectors_per_fat,
-        root_cluster,
-    })
-}
+    #[test]
+    fn vec_device_supports_writes_and_shared_ref_refuses_them() {
+        let mut dev = build_test_image().to_vec();
+        {
+            let mut rw = Fat32Mut::from_device(&mut dev).unwrap();
+            rw.write_file_by_path("/NEW.TXT", b"DATA").unwrap();
+        }
 
-/// Normalise un nom pour comparer facilement (on passe en majuscule).
-fn normalize_name(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        out.push(ch.to_ascii_uppercase());
-    }
-    out
-}
+        let ro = Fat32::from_device(&dev).unwrap();
+        assert_eq!(ro.read_file_by_path("/NEW.TXT").unwrap().unwrap(), b"DATA");
 
-/// Découpe `"/A/B/C.TXT"` en (`"/A/B"`, `"C.TXT"`).
-fn split_parent(path: &str) -> Result<(&str, &str), FatError> {
-    let path = path.trim_end_matches('/');
-    if path == "/" {
-        return Err(FatError::Other);
+        let mut shared = Fat32Mut::from_device(&dev).unwrap();
+        assert_eq!(
+            shared.write_file_by_path("/OTHER.TXT", b"X"),
+            Err(FatError::Io)
+        );
     }
 
-    let idx = path.rfind('/').ok_or(FatError::Other)?;
-    let parent = if idx == 0 { "/" } else { &path[..idx] };
-    let name = &path[idx + 1..];
-
-    if name.is_empty() {
-        return Err(FatError::Other);
-    }
-    Ok((parent, name))
-}
+    #[test]
+    fn file_handle_reads_by_chunks_and_seeks() {
+        let mut disk = build_test_image();
+        let content: Vec<u8> = (0..1300u32).map(|i| (i % 251) as u8).collect();
 
-/// Encode un nom en format court 8.3.
-///
-/// Exemples :
-/// - `"HELLO.TXT"` -> name=`"HELLO   "`, ext=`"TXT"`
-/// - `"DIR"`       -> name=`"DIR     "`, ext=`"   "`
-///
-/// Limites volontaires :
-/// - ASCII uniquement
-/// - 1 point max (séparateur extension)
-/// - base <= 8, ext <= 3
-/// - pas de `.` dans la base ou l’extension
-fn encode_short_name_8_3(name: &str) -> Result<([u8; 8], [u8; 3]), FatError> {
-    let mut base = name;
-    let mut ext = "";
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.write_file_by_path("/DATA.BIN", &content).unwrap();
 
-    if let Some(dot) = name.rfind('.') {
-        base = &name[..dot];
-        ext = &name[dot + 1..];
-    }
+        let mut file = rw.open("/DATA.BIN").unwrap();
+        assert_eq!(file.len(), 1300);
 
-    if base.is_empty() || base.len() > 8 || ext.len() > 3 {
-        return Err(FatError::InvalidName);
-    }
-    if base.contains('.') || ext.contains('.') {
-        return Err(FatError::InvalidName);
-    }
+        let mut out = Vec::new();
+        let mut buf = [0u8; 100];
+        loop {
+            let n = file.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, content);
 
-    let mut n = [b' '; 8];
-    let mut e = [b' '; 3];
+        // Retour en arrière puis lecture à cheval sur deux clusters
+        file.seek(SeekFrom::Start(500)).unwrap();
+        let n = file.read(&mut buf[..40]).unwrap();
+        assert_eq!(n, 40);
+        assert_eq!(&buf[..40], &content[500..540]);
+        assert_eq!(file.seek(SeekFrom::End(-10)).unwrap(), 1290);
+        assert_eq!(file.read(&mut buf).unwrap(), 10);
 
-    for (i, ch) in base.bytes().enumerate() {
-        if !ch.is_ascii() || ch == b'/' {
-            return Err(FatError::InvalidName);
-        }
-        n[i] = ch.to_ascii_uppercase();
+        assert_eq!(rw.open("/DIR").unwrap_err(), FatError::NotAFile);
     }
 
-    for (i, ch) in ext.bytes().enumerate() {
-        if !ch.is_ascii() || ch == b'/' {
-            return Err(FatError::InvalidName);
-        }
-        e[i] = ch.to_ascii_uppercase();
-    }
+    #[test]
+    fn file_handle_write_extends_chain_and_set_len_truncates() {
+        let mut disk = build_test_image();
 
-    Ok((n, e))
-}
+        let (first, second) = {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            let mut file = rw.create("/LOG.TXT").unwrap();
+            file.write(b"AB").unwrap();
+
+            // Écriture après la fin : trou rempli de zéros, chaîne étendue à 3 clusters
+            file.seek(SeekFrom::Start(1100)).unwrap();
+            file.write(b"Z").unwrap();
+            assert_eq!(file.len(), 1101);
+
+            let e = rw.as_read().open_path("/LOG.TXT").unwrap().unwrap();
+            assert_eq!(e.size, 1101);
+            let data = rw.as_read().read_file(&e).unwrap();
+            assert_eq!(&data[..2], b"AB");
+            assert!(data[2..1100].iter().all(|&b| b == 0));
+            assert_eq!(data[1100], b'Z');
+
+            let second = fat_entry_raw(rw.as_read().device(), e.first_cluster);
+
+            let mut file = rw.open("/LOG.TXT").unwrap();
+            file.set_len(10).unwrap();
+            (e.first_cluster, second)
+        };
 
-/// Division entière avec arrondi vers le haut.
-fn div_ceil(a: usize, b: usize) -> usize {
-    if b == 0 { 0 } else { a.div_ceil(b) }
-}
+        // La chaîne est coupée après le premier cluster, le reste est libéré
+        assert!(fat_entry_raw(&disk, first) >= 0x0FFF_FFF8);
+        assert_eq!(fat_entry_raw(&disk, second), 0);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let ro = Fat32::new(&disk).unwrap();
+        let content = ro.read_file_by_path("/LOG.TXT").unwrap().unwrap();
+        assert_eq!(content, b"AB\0\0\0\0\0\0\0\0");
+    }
 
-    /// Mini volume FAT32 en mémoire :
-    /// - 1 secteur BPB
-    /// - 1 secteur FAT
-    /// - data cluster (sector) 2 = racine : HELLO.TXT + DIR
-    /// - data cluster (sector) 3 = contenu "HELLO"
-    /// - data cluster (sector) 4 = répertoire DIR (vide)
-    ///
-    /// Les clusters 5.. sont libres, ce qui permet de tester l’écriture.
-    fn build_test_image() -> [u8; 5120] {
-        const SECTOR_SIZE: usize = 512;
-        const NUM_SECTORS: usize = 10;
-        let mut disk = [0u8; SECTOR_SIZE * NUM_SECTORS];
+    #[test]
+    fn file_handle_implements_std_io() {
+        use std::io::{Read, Seek, Write};
 
-        // BPB
-        {
-            let b = &mut disk[0..SECTOR_SIZE];
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
 
-            // bytes_per_sector = 512
-            b[11] = 0x00;
-            b[12] = 0x02;
+        let mut file = rw.create("/copy.txt").unwrap();
+        std::io::copy(&mut &b"streamed content"[..], &mut file).unwrap();
+        file.rewind().unwrap();
 
-            // sectors_per_cluster = 1
-            b[13] = 0x01;
+        let mut text = String::new();
+        file.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "streamed content");
 
-            // reserved_sectors = 1
-            b[14] = 0x01;
-            b[15] = 0x00;
+        file.write_all(b"!").unwrap();
+        assert_eq!(file.len(), 17);
+    }
 
-            // num_fats = 1
-            b[16] = 0x01;
+    /// Image de test avec un secteur FSInfo : on insère un secteur réservé (n°1)
+    /// devant la FAT. Les numéros de cluster ne changent pas, la FAT est à 1024.
+    fn build_test_image_with_fs_info(free_count: u32, next_free: u32) -> Vec<u8> {
+        let base = build_test_image();
+        let mut disk = vec![0u8; base.len() + 512];
+        disk[..512].copy_from_slice(&base[..512]);
+        disk[1024..].copy_from_slice(&base[512..]);
+
+        // reserved_sectors = 2, fs_info_sector = 1, un secteur de plus au total
+        disk[14] = 2;
+        disk[48] = 1;
+        disk[32..36].copy_from_slice(&(TEST_SECTORS as u32 + 1).to_le_bytes());
+
+        let info = &mut disk[512..1024];
+        info[0..4].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+        info[484..488].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+        info[488..492].copy_from_slice(&free_count.to_le_bytes());
+        info[492..496].copy_from_slice(&next_free.to_le_bytes());
+        info[508..512].copy_from_slice(&0xAA55_0000u32.to_le_bytes());
+        disk
+    }
 
-            // sectors_per_fat = 1
-            b[36] = 0x01;
-            b[37] = 0x00;
-            b[38] = 0x00;
-            b[39] = 0x00;
+    fn fs_info_fields(disk: &[u8]) -> (u32, u32) {
+        let f = |at: usize| u32::from_le_bytes(disk[at..at + 4].try_into().unwrap());
+        (f(512 + 488), f(512 + 492))
+    }
 
-            // root_cluster = 2
-            b[44] = 0x02;
-            b[45] = 0x00;
-            b[46] = 0x00;
-            b[47] = 0x00;
-        }
+    #[test]
+    fn fs_info_counters_follow_allocation() {
+        // Clusters libres : 5..=65526; l’indice pointe sur 100
+        let mut disk = build_test_image_with_fs_info(65522, 100);
 
-        // FAT (secteur 1)
         {
-            let fat_start = SECTOR_SIZE;
-            let fat = &mut disk[fat_start..fat_start + SECTOR_SIZE];
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            assert_eq!(rw.as_read().free_clusters().unwrap(), 65522);
+            assert_eq!(rw.as_read().free_bytes().unwrap(), 65522 * 512);
 
-            let eoc_bytes = FAT32_EOC.to_le_bytes();
+            // Une seule plage libre : le fichier la commence, et l’indice passe derrière lui
+            rw.write_file_by_path("/A.TXT", &[1u8; 600]).unwrap();
+            let e = rw.as_read().open_path("/A.TXT").unwrap().unwrap();
+            assert_eq!(e.first_cluster, 5);
+        }
+        assert_eq!(fs_info_fields(&disk), (65520, 7));
 
-            // cluster 2 (root) -> EOC
-            fat[2 * 4..2 * 4 + 4].copy_from_slice(&eoc_bytes);
-            // cluster 3 (HELLO.TXT) -> EOC
-            fat[3 * 4..3 * 4 + 4].copy_from_slice(&eoc_bytes);
-            // cluster 4 (DIR) -> EOC
-            fat[4 * 4..4 * 4 + 4].copy_from_slice(&eoc_bytes);
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.remove_file("/A.TXT").unwrap();
 
-            // clusters 5.. = 0 -> libres
+            // Deux trous de 2 clusters : 5..=6 et 8..=9
+            for (name, len) in [("/H1", 2), ("/X", 1), ("/H2", 2), ("/Y", 1)] {
+                rw.write_file_by_path(name, &alloc::vec![1u8; len * 512]).unwrap();
+            }
+            rw.remove_file("/H1").unwrap();
+            rw.remove_file("/H2").unwrap();
         }
+        assert_eq!(fs_info_fields(&disk).0, 65522 - 2);
 
-        // root dir = cluster 2 -> secteur 2
+        // À taille égale, l’indice départage : le trou à partir de 8 plutôt que le premier
+        disk[512 + 492..512 + 496].copy_from_slice(&8u32.to_le_bytes());
         {
-            let root_off = 2 * SECTOR_SIZE;
-            let dir = &mut disk[root_off..root_off + SECTOR_SIZE];
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/F", &[2u8; 2 * 512]).unwrap();
+            assert_eq!(rw.as_read().open_path("/F").unwrap().unwrap().first_cluster, 8);
+        }
+        assert_eq!(fs_info_fields(&disk), (65522 - 4, 10));
+    }
 
-            // HELLO.TXT
-            let mut hello = [0u8; 32];
-            hello[0..8].copy_from_slice(b"HELLO   ");
-            hello[8..11].copy_from_slice(b"TXT");
-            hello[11] = 0x20; // archive file
-            // first_cluster = 3
-            hello[26] = 0x03;
-            hello[27] = 0x00;
-            // size = 5
-            hello[28] = 5;
-            dir[0..32].copy_from_slice(&hello);
+    #[test]
+    fn invalid_fs_info_falls_back_to_fat_scan() {
+        // Compteur inconnu : on compte dans la FAT
+        let disk = build_test_image_with_fs_info(0xFFFF_FFFF, 0xFFFF_FFFF);
+        assert_eq!(Fat32::new(&disk).unwrap().free_clusters().unwrap(), 65522);
 
-            // DIR
-            let mut subdir = [0u8; 32];
-            subdir[0..8].copy_from_slice(b"DIR     ");
-            subdir[8..11].copy_from_slice(b"   ");
-            subdir[11] = 0x10; // directory
-            // first_cluster = 4
-            subdir[26] = 0x04;
-            subdir[27] = 0x00;
-            dir[32..64].copy_from_slice(&subdir);
+        // Signature cassée : FSInfo ignoré, même si le compteur semble valable
+        let mut disk = build_test_image_with_fs_info(1, 7);
+        disk[512] = 0;
+        assert_eq!(Fat32::new(&disk).unwrap().free_clusters().unwrap(), 65522);
 
-            // end of dir
-            dir[64] = 0x00;
+        // Sans FSInfo (image de base) : scan aussi
+        let disk = build_test_image();
+        assert_eq!(Fat32::new(&disk).unwrap().free_clusters().unwrap(), 65522);
+    }
+
+    /// Image FAT12/16 minimale : 1 secteur réservé, 2 FAT de `spf` secteurs,
+    /// racine fixe de 16 entrées (1 secteur) puis `clusters` clusters d’un secteur.
+    ///
+    /// Contenu : HELLO.TXT (cluster 2) et DIR (cluster 3), comme l’image FAT32.
+    fn build_small_fat_image(spf: u16, clusters: u16) -> Vec<u8> {
+        let total = 1 + 2 * spf + 1 + clusters;
+        let mut disk = alloc::vec![0u8; total as usize * SECTOR_SIZE];
+
+        {
+            let b = &mut disk[0..SECTOR_SIZE];
+            b[0..3].copy_from_slice(&[0xEB, 0x3C, 0x90]);
+            b[11..13].copy_from_slice(&512u16.to_le_bytes());
+            b[13] = 1;
+            b[14..16].copy_from_slice(&1u16.to_le_bytes());
+            b[16] = 2;
+            b[17..19].copy_from_slice(&16u16.to_le_bytes());
+            b[19..21].copy_from_slice(&total.to_le_bytes());
+            b[21] = 0xF8;
+            b[22..24].copy_from_slice(&spf.to_le_bytes());
+            b[510] = 0x55;
+            b[511] = 0xAA;
+        }
+
+        let fat_type = FatType::from_cluster_count(clusters as u32);
+        for copy in 0..2 {
+            let fat = SECTOR_SIZE * (1 + copy * spf as usize);
+            for (cl, value) in [(0, 0x0FFF_FFF8), (1, FAT32_EOC), (2, FAT32_EOC), (3, FAT32_EOC)] {
+                let (rel, len) = fat_type.entry_location(cl);
+                fat_type.encode(cl, value, &mut disk[fat + rel..fat + rel + len]);
+            }
         }
 
-        // cluster 3 data -> secteur 3
-        {
-            let off = 3 * SECTOR_SIZE;
-            disk[off..off + 5].copy_from_slice(b"HELLO");
+        let root = SECTOR_SIZE * (1 + 2 * spf as usize);
+        let mut hello = [0u8; 32];
+        hello[0..11].copy_from_slice(b"HELLO   TXT");
+        hello[11] = 0x20;
+        hello[26] = 2;
+        hello[28] = 5;
+        disk[root..root + 32].copy_from_slice(&hello);
+
+        let mut subdir = [0u8; 32];
+        subdir[0..11].copy_from_slice(b"DIR        ");
+        subdir[11] = 0x10;
+        subdir[26] = 3;
+        disk[root + 32..root + 64].copy_from_slice(&subdir);
+
+        let data = root + SECTOR_SIZE;
+        disk[data..data + 5].copy_from_slice(b"HELLO");
+        put_dot_entries(&mut disk[data + SECTOR_SIZE..], 3, 0);
+
+        disk
+    }
+
+    #[test]
+    fn fat12_and_fat16_images_are_read() {
+        for (spf, clusters, expected) in [(1, 200, FatType::Fat12), (20, 5000, FatType::Fat16)] {
+            let disk = build_small_fat_image(spf, clusters);
+            let fs = Fat32::new(&disk).unwrap();
+            assert_eq!(fs.fat_type(), expected);
+
+            let names: Vec<_> = fs.list_root().unwrap().iter().map(|e| e.name.clone()).collect();
+            assert_eq!(names, ["HELLO.TXT", "DIR"]);
+            assert_eq!(fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"HELLO");
+            let dir = fs.list_dir_path("/DIR").unwrap();
+            let names: Vec<&str> = dir.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(names, [".", ".."]);
+            assert_eq!(fs.free_clusters().unwrap(), clusters as u32 - 2);
         }
 
-        // cluster 4 data -> secteur 4 (DIR empty)
+        assert_eq!(Fat32::new(&build_test_image()).unwrap().fat_type(), FatType::Fat32);
+    }
+
+    #[test]
+    fn fat12_write_packs_entries_and_keeps_copies_in_sync() {
+        let mut disk = build_small_fat_image(1, 200);
+        let content: Vec<u8> = (0..1500u32).map(|i| (i % 251) as u8).collect();
         {
-            let off = 4 * SECTOR_SIZE;
-            disk[off] = 0x00;
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.write_file_by_path("/BIG.BIN", &content).unwrap();
+            assert_eq!(rw.as_read().read_file_by_path("/BIG.BIN").unwrap().unwrap(), content);
         }
 
-        disk
+        // Entrées de 12 bits : cluster n aux octets n * 3 / 2, demi-octet haut si n impair
+        let entry = |cl: usize| {
+            let off = SECTOR_SIZE + cl * 3 / 2;
+            let pair = u16::from_le_bytes([disk[off], disk[off + 1]]);
+            if cl % 2 == 1 { pair >> 4 } else { pair & 0x0FFF }
+        };
+        assert_eq!((entry(4), entry(5), entry(6)), (5, 6, 0x0FFF));
+        // Les voisins qui partagent un octet sont intacts
+        assert_eq!((entry(2), entry(3), entry(7)), (0x0FFF, 0x0FFF, 0));
+        assert_eq!(disk[SECTOR_SIZE..2 * SECTOR_SIZE], disk[2 * SECTOR_SIZE..3 * SECTOR_SIZE]);
     }
 
-    fn fat_entry_raw(disk: &[u8], cluster: u32) -> u32 {
-        // Dans notre image de test: reserved=1, bytes_per_sector=512 donc FAT start = 512.
-        let fat_start = 512usize;
-        let off = fat_start + cluster as usize * 4;
-        let bytes = &disk[off..off + 4];
-        let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-        v & 0x0FFF_FFFF
+    #[test]
+    fn fixed_root_accepts_subdirectories_but_never_grows() {
+        let mut disk = build_small_fat_image(20, 5000);
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+
+        rw.create_dir("/SUB").unwrap();
+        rw.write_file_by_path("/SUB/NOTE.TXT", b"note").unwrap();
+        let sub = rw.as_read().open_path("/SUB").unwrap().unwrap();
+        let dotdot = rw.as_read().cluster_to_offset(sub.first_cluster).unwrap() + 32;
+        let mut raw = [0u8; 32];
+        rw.read_bytes(dotdot, &mut raw).unwrap();
+        assert_eq!(&raw[0..2], b"..");
+        assert_eq!(u16::from_le_bytes([raw[26], raw[27]]), 0);
+        assert_eq!(rw.as_read().list_dir_path("/SUB/..").unwrap().len(), 3);
+
+        // 16 entrées : HELLO.TXT, DIR, SUB, puis 13 fichiers
+        for i in 0..13 {
+            rw.write_file_by_path(&format!("/F{i}.TXT"), b"x").unwrap();
+        }
+        let free = rw.as_read().free_clusters().unwrap();
+        assert_eq!(
+            rw.write_file_by_path("/LAST.TXT", b"x").unwrap_err(),
+            FatError::NoSpaceLeft
+        );
+        assert_eq!(rw.as_read().list_root().unwrap().len(), 16);
+        // La chaîne écrite avant l’échec est rendue
+        assert_eq!(rw.as_read().free_clusters().unwrap(), free);
+        assert!(rw.as_read().check().unwrap().is_clean());
     }
 
-    #[test]
-    fn new_on_too_small_buffer_fails() {
-        let tiny = [0u8; 128];
-        let err = Fat32::new(&tiny).unwrap_err();
-        assert_eq!(err, FatError::BufferTooSmall);
+    /// Écrit une entrée de partition MBR/EBR (`slot` de 0 à 3) dans `sector`.
+    fn put_mbr_entry(sector: &mut [u8], slot: usize, type_id: u8, start: u32, count: u32) {
+        let e = &mut sector[446 + slot * 16..446 + slot * 16 + 16];
+        e[4] = type_id;
+        e[8..12].copy_from_slice(&start.to_le_bytes());
+        e[12..16].copy_from_slice(&count.to_le_bytes());
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
     }
 
     #[test]
-    fn list_root_and_read_file() {
-        let disk = build_test_image();
-        let fs = Fat32::new(&disk).expect("Fat32::new failed");
+    fn mbr_primary_and_logical_partitions_open_as_fat_volumes() {
+        let fat16 = build_small_fat_image(20, 5000);
+        let fat12 = build_small_fat_image(1, 200);
+        let fat16_sectors = (fat16.len() / SECTOR_SIZE) as u32;
+        let fat12_sectors = (fat12.len() / SECTOR_SIZE) as u32;
+
+        // LBA 0 : MBR; 1..8 : Linux; puis FAT16; puis une étendue (EBR + FAT12)
+        let fat16_start = 8;
+        let ext_start = fat16_start + fat16_sectors;
+        let mut disk = alloc::vec![0u8; (ext_start + 1 + fat12_sectors) as usize * SECTOR_SIZE];
+        put_mbr_entry(&mut disk[..SECTOR_SIZE], 0, 0x83, 1, 7);
+        put_mbr_entry(&mut disk[..SECTOR_SIZE], 1, 0x06, fat16_start, fat16_sectors);
+        put_mbr_entry(&mut disk[..SECTOR_SIZE], 2, 0x05, ext_start, 1 + fat12_sectors);
+        let ebr = ext_start as usize * SECTOR_SIZE;
+        put_mbr_entry(&mut disk[ebr..ebr + SECTOR_SIZE], 0, 0x01, 1, fat12_sectors);
+        let at = |lba: u32| lba as usize * SECTOR_SIZE;
+        disk[at(fat16_start)..at(ext_start)].copy_from_slice(&fat16);
+        disk[at(ext_start + 1)..].copy_from_slice(&fat12);
+
+        let parts = list_partitions(&disk).unwrap();
+        let summary: Vec<_> = parts.iter().map(|p| (p.start_lba, p.sector_count, p.is_fat())).collect();
+        assert_eq!(
+            summary,
+            [
+                (1, 7, false),
+                (fat16_start as u64, fat16_sectors as u64, true),
+                (ext_start as u64 + 1, fat12_sectors as u64, true),
+            ]
+        );
+
+        let fs = Fat32::from_device(PartitionDevice::open_fat(&disk, 0).unwrap()).unwrap();
+        assert_eq!(fs.fat_type(), FatType::Fat16);
+        assert_eq!(fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"HELLO");
 
-        let root = fs.list_root().expect("list_root failed");
-        assert_eq!(root.len(), 2);
+        {
+            let dev = PartitionDevice::open_fat(&mut disk, 1).unwrap();
+            let mut rw = Fat32Mut::from_device(dev).unwrap();
+            assert_eq!(rw.as_read().fat_type(), FatType::Fat12);
+            rw.write_file_by_path("/NEW.TXT", b"inside").unwrap();
+        }
+        let fs = Fat32::from_device(PartitionDevice::open_fat(&disk, 1).unwrap()).unwrap();
+        assert_eq!(fs.read_file_by_path("/NEW.TXT").unwrap().unwrap(), b"inside");
+        // Le volume voisin n’a pas bougé
+        assert_eq!(disk[at(fat16_start)..at(ext_start)], fat16[..]);
+
+        assert_eq!(
+            PartitionDevice::open_fat(&disk, 2).unwrap_err(),
+            FatError::PartitionNotFound
+        );
+        // Pas de table : le secteur 0 est le BPB
+        assert!(list_partitions(&build_test_image()).unwrap().is_empty());
+    }
 
-        let hello = root
-            .iter()
-            .find(|e| e.name == "HELLO.TXT")
-            .expect("HELLO.TXT missing");
-        let dir = root.iter().find(|e| e.name == "DIR").expect("DIR missing");
+    #[test]
+    fn gpt_headers_are_checked_and_backup_is_used() {
+        assert_eq!(partition::crc32(b"123456789"), 0xCBF4_3926);
+
+        let fat12 = build_small_fat_image(1, 200);
+        let fat_sectors = (fat12.len() / SECTOR_SIZE) as u64;
+        // MBR protecteur, en-tête, 32 secteurs d’entrées, volume, entrées + en-tête de secours
+        let total = 34 + fat_sectors + 33;
+        let mut disk = alloc::vec![0u8; total as usize * SECTOR_SIZE];
+        put_mbr_entry(&mut disk[..SECTOR_SIZE], 0, 0xEE, 1, (total - 1) as u32);
+        disk[34 * SECTOR_SIZE..(34 + fat_sectors) as usize * SECTOR_SIZE].copy_from_slice(&fat12);
+
+        let mut entries = alloc::vec![0u8; 128 * 128];
+        // GUID Basic Data (ordre disque), bornes inclusives, nom UTF-16
+        entries[0..16].copy_from_slice(&[
+            0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26,
+            0x99, 0xC7,
+        ]);
+        entries[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entries[40..48].copy_from_slice(&(33 + fat_sectors).to_le_bytes());
+        for (i, c) in "DATA".encode_utf16().enumerate() {
+            entries[56 + i * 2..58 + i * 2].copy_from_slice(&c.to_le_bytes());
+        }
+        let entries_crc = partition::crc32(&entries);
+
+        let last = total - 1;
+        for (my_lba, alt_lba, entries_lba) in [(1, last, 2), (last, 1, last - 32)] {
+            let mut h = [0u8; SECTOR_SIZE];
+            h[0..8].copy_from_slice(b"EFI PART");
+            h[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+            h[12..16].copy_from_slice(&92u32.to_le_bytes());
+            h[24..32].copy_from_slice(&my_lba.to_le_bytes());
+            h[32..40].copy_from_slice(&alt_lba.to_le_bytes());
+            h[40..48].copy_from_slice(&34u64.to_le_bytes());
+            h[48..56].copy_from_slice(&(last - 33).to_le_bytes());
+            h[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+            h[80..84].copy_from_slice(&128u32.to_le_bytes());
+            h[84..88].copy_from_slice(&128u32.to_le_bytes());
+            h[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+            let crc = partition::crc32(&h[..92]);
+            h[16..20].copy_from_slice(&crc.to_le_bytes());
+
+            let off = my_lba as usize * SECTOR_SIZE;
+            disk[off..off + SECTOR_SIZE].copy_from_slice(&h);
+            let off = entries_lba as usize * SECTOR_SIZE;
+            disk[off..off + entries.len()].copy_from_slice(&entries);
+        }
 
-        assert!(hello.is_file());
-        assert!(dir.is_dir());
+        let parts = list_partitions(&disk).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!((parts[0].start_lba, parts[0].sector_count), (34, fat_sectors));
+        assert!(matches!(&parts[0].kind, PartitionKind::Gpt { name, .. } if name == "DATA"));
+        let fs = Fat32::from_device(PartitionDevice::open_fat(&disk, 0).unwrap()).unwrap();
+        assert_eq!(fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"HELLO");
+
+        // En-tête primaire abîmé : CRC faux, on passe à l’en-tête de secours
+        disk[SECTOR_SIZE + 40] ^= 1;
+        assert_eq!(list_partitions(&disk).unwrap(), parts);
+
+        // Tableau d’entrées de secours abîmé aussi : plus rien d’exploitable
+        disk[(last - 32) as usize * SECTOR_SIZE + 32] ^= 1;
+        assert_eq!(
+            list_partitions(&disk).unwrap_err(),
+            FatError::InvalidPartitionTable
+        );
+
+        // En-têtes au CRC valide mais aux champs hostiles : refusés sans allouer 2 Tio
+        // ni déborder en calculant la position du tableau
+        for (entries_lba, count, size) in [
+            (2u64, 1024u32, 1u32 << 31),
+            (2, 1024, 1024),
+            (u64::MAX, 128, 128),
+            (last, 128, 128),
+        ] {
+            for lba in [1, last] {
+                let off = lba as usize * SECTOR_SIZE;
+                let h = &mut disk[off..off + SECTOR_SIZE];
+                h[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+                h[80..84].copy_from_slice(&count.to_le_bytes());
+                h[84..88].copy_from_slice(&size.to_le_bytes());
+                h[16..20].fill(0);
+                let crc = partition::crc32(&h[..92]);
+                h[16..20].copy_from_slice(&crc.to_le_bytes());
+            }
+            assert_eq!(
+                list_partitions(&disk).unwrap_err(),
+                FatError::InvalidPartitionTable
+            );
+        }
+    }
 
-        let content = fs.read_file_by_path("/HELLO.TXT").unwrap().unwrap();
-        assert_eq!(content, b"HELLO");
+    #[test]
+    fn fat_date_time_encoding_and_unix_conversion() {
+        let t = FatDateTime::new(2024, 2, 29, 13, 45, 7).unwrap();
+        let (date, time, hundredths) = t.to_fat();
+        assert_eq!(date, (44 << 9) | (2 << 5) | 29);
+        assert_eq!(time, (13 << 11) | (45 << 5) | 3);
+        assert_eq!(hundredths, 100);
+        assert_eq!(FatDateTime::from_fat(date, time, hundredths), Some(t));
+        assert_eq!(FatDateTime::from_fat(0, time, 0), None);
+        assert_eq!(FatDateTime::from_fat(date | 0x1F << 5, time, 0), None);
+
+        assert_eq!(FatDateTime::from_unix(1_709_214_307), t);
+        assert_eq!(FatDateTime::from_unix(0), FatDateTime::EPOCH);
+        assert_eq!(t.to_string(), "2024-02-29 13:45:07");
+        assert!(FatDateTime::new(1979, 12, 31, 0, 0, 0).is_none());
     }
 
     #[test]
-    fn list_dir_on_file_returns_not_a_directory() {
-        let disk = build_test_image();
+    fn writes_are_dated_by_the_time_source() {
+        let mut disk = build_test_image();
+        let t1 = FatDateTime::new(2023, 6, 1, 8, 30, 0).unwrap();
+        let t2 = FatDateTime::new(2024, 1, 15, 17, 5, 42).unwrap();
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            // Source par défaut : date fixe au 1er janvier 1980
+            rw.write_file_by_path("/OLD.TXT", b"x").unwrap();
+            let old = rw.as_read().open_path("/OLD.TXT").unwrap().unwrap();
+            assert_eq!(old.modified, Some(FatDateTime::EPOCH));
+
+            rw.set_time_source(FixedTime(t1));
+            rw.write_file_by_path("/A.TXT", b"one").unwrap();
+            rw.set_time_source(FixedTime(t2));
+            rw.write_file_by_path("/A.TXT", b"two").unwrap();
+        }
+
         let fs = Fat32::new(&disk).unwrap();
+        let a = fs.open_path("/A.TXT").unwrap().unwrap();
+        assert_eq!(a.created, Some(t1));
+        assert_eq!(a.modified, Some(t2));
+        let day = FatDateTime::new(2024, 1, 15, 0, 0, 0);
+        assert_eq!(a.accessed, day);
+        // Entrées de l’image de test : dates à zéro, donc non renseignées
+        assert_eq!(fs.open_path("/HELLO.TXT").unwrap().unwrap().modified, None);
+
+        // Une écriture par flux met à jour la modification (secondes paires), pas la création
+        let t3 = FatDateTime::new(2025, 3, 3, 3, 3, 4).unwrap();
+        {
+            let mut rw = Fat32Mut::new(&mut disk).unwrap();
+            rw.set_time_source(FixedTime(t3));
+            let mut f = rw.open("/A.TXT").unwrap();
+            f.seek(SeekFrom::End(0)).unwrap();
+            f.write(b"!").unwrap();
+        }
+        let a = Fat32::new(&disk).unwrap().open_path("/A.TXT").unwrap().unwrap();
+        assert_eq!((a.created, a.modified), (Some(t1), Some(t3)));
+    }
 
-        let err = fs.list_dir_path("/HELLO.TXT").unwrap_err();
-        assert_eq!(err, FatError::NotADirectory);
+    #[test]
+    fn append_and_write_at_reuse_the_existing_chain() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+
+        // 5 + 600 octets : on complète le cluster 3 puis on chaîne un seul cluster
+        rw.append_file("/HELLO.TXT", &[b'a'; 600]).unwrap();
+        let e = rw.as_read().open_path("/HELLO.TXT").unwrap().unwrap();
+        assert_eq!((e.first_cluster, e.size), (3, 605));
+        assert_eq!(rw.as_read().follow_chain(3).unwrap(), [3, 5]);
+        let mut expected = b"HELLO".to_vec();
+        expected.extend_from_slice(&[b'a'; 600]);
+        assert_eq!(rw.as_read().read_file(&e).unwrap(), expected);
+
+        // Écriture au milieu : ni la taille ni la chaîne ne changent
+        rw.write_at("/HELLO.TXT", 510, b"XYZ").unwrap();
+        expected[510..513].copy_from_slice(b"XYZ");
+        assert_eq!(rw.as_read().read_file_by_path("/HELLO.TXT").unwrap().unwrap(), expected);
+        assert_eq!(rw.as_read().follow_chain(3).unwrap(), [3, 5]);
+
+        // Au-delà de la fin : trou de zéros
+        rw.write_at("/HELLO.TXT", 700, b"!").unwrap();
+        expected.resize(700, 0);
+        expected.push(b'!');
+        assert_eq!(rw.as_read().read_file_by_path("/HELLO.TXT").unwrap().unwrap(), expected);
+
+        assert_eq!(rw.append_file("/DIR", b"x").unwrap_err(), FatError::NotAFile);
+        assert_eq!(rw.write_at("/NOPE.TXT", 0, b"x").unwrap_err(), FatError::PathNotFound);
     }
 
     #[test]
-    fn read_file_on_directory_via_open_path_returns_not_a_file() {
-        let disk = build_test_image();
-        let fs = Fat32::new(&disk).unwrap();
+    fn set_len_truncates_or_zero_extends_by_path() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        let free = rw.as_read().count_free_clusters().unwrap();
+
+        rw.write_file_by_path("/BIG.BIN", &[0xAB; 1500]).unwrap();
+        let first = rw.as_read().open_path("/BIG.BIN").unwrap().unwrap().first_cluster;
+        let chain = rw.as_read().follow_chain(first).unwrap();
+        assert_eq!(chain.len(), 3);
+
+        // Réduction : le 2e cluster devient la fin de chaîne, le 3e est libéré
+        rw.set_len("/BIG.BIN", 600).unwrap();
+        assert_eq!(rw.as_read().follow_chain(first).unwrap(), chain[..2]);
+        assert_eq!(rw.read_fat_entry(chain[1]).unwrap(), FAT32_EOC);
+        assert_eq!(rw.read_fat_entry(chain[2]).unwrap(), 0);
+        assert_eq!(rw.as_read().read_file_by_path("/BIG.BIN").unwrap().unwrap(), [0xAB; 600]);
+
+        // Agrandissement : les octets ajoutés (même sur des clusters réutilisés) sont nuls
+        rw.set_len("/BIG.BIN", 2000).unwrap();
+        let data = rw.as_read().read_file_by_path("/BIG.BIN").unwrap().unwrap();
+        assert_eq!(data.len(), 2000);
+        assert!(data[..600].iter().all(|&b| b == 0xAB));
+        assert!(data[600..].iter().all(|&b| b == 0));
+
+        rw.set_len("/BIG.BIN", 0).unwrap();
+        let e = rw.as_read().open_path("/BIG.BIN").unwrap().unwrap();
+        assert_eq!((e.first_cluster, e.size), (0, 0));
+        assert_eq!(rw.as_read().count_free_clusters().unwrap(), free);
+    }
 
-        let entry = fs.open_path("/DIR").unwrap().unwrap();
-        assert!(entry.is_dir());
+    #[test]
+    fn preallocate_reserves_contiguous_clusters_without_changing_size() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        // Occupe le cluster 6 : la réserve ne peut pas suivre le fichier (cluster 5)
+        rw.write_file_by_path("/LOG.TXT", b"start").unwrap();
+        rw.write_file_by_path("/PAD.BIN", b"pad").unwrap();
+        assert_eq!(rw.as_read().open_path("/PAD.BIN").unwrap().unwrap().first_cluster, 6);
+
+        rw.preallocate("/LOG.TXT", 4 * 512).unwrap();
+        let e = rw.as_read().open_path("/LOG.TXT").unwrap().unwrap();
+        assert_eq!(e.size, 5);
+        assert_eq!(rw.as_read().follow_chain(5).unwrap(), [5, 7, 8, 9]);
+        assert_eq!(rw.as_read().read_file(&e).unwrap(), b"start");
+
+        // Écrire dans la réserve n’alloue rien de plus
+        let free = rw.as_read().count_free_clusters().unwrap();
+        rw.write_at("/LOG.TXT", 1500, b"end").unwrap();
+        assert_eq!(rw.as_read().count_free_clusters().unwrap(), free);
+        assert_eq!(rw.as_read().follow_chain(5).unwrap(), [5, 7, 8, 9]);
+
+        // Fichier vide : la réserve devient le début de la chaîne, à la suite
+        rw.write_file_by_path("/EMPTY.BIN", b"").unwrap();
+        rw.preallocate("/EMPTY.BIN", 3 * 512).unwrap();
+        let e = rw.as_read().open_path("/EMPTY.BIN").unwrap().unwrap();
+        assert_eq!((e.first_cluster, e.size), (10, 0));
+        assert_eq!(rw.as_read().follow_chain(10).unwrap(), [10, 11, 12]);
+
+        // Une réserve n’est pas une erreur : `repair` la garde
+        let report = rw.as_read().check().unwrap();
+        assert!(report.is_clean(), "{:?}", report.problems);
+        rw.repair().unwrap();
+        assert_eq!(rw.as_read().follow_chain(5).unwrap(), [5, 7, 8, 9]);
+        assert_eq!(rw.as_read().follow_chain(10).unwrap(), [10, 11, 12]);
+    }
 
-        let err = fs.read_file(&entry).unwrap_err();
-        assert_eq!(err, FatError::NotAFile);
+    #[test]
+    fn allocator_prefers_the_smallest_contiguous_run() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+
+        // Trous de 2 (5..=6) et 4 (9..=12) clusters, séparés par des fichiers
+        for (name, len) in [("/A", 2), ("/B", 2), ("/C", 4), ("/D", 1)] {
+            rw.write_file_by_path(name, &alloc::vec![1u8; len * 512]).unwrap();
+        }
+        rw.remove_file("/A").unwrap();
+        rw.remove_file("/C").unwrap();
+
+        // 3 clusters : le trou de 2 est trop petit, celui de 4 convient
+        rw.write_file_by_path("/E", &[2u8; 3 * 512]).unwrap();
+        assert_eq!(rw.as_read().follow_chain(9).unwrap(), [9, 10, 11]);
+        // 2 clusters : le trou de 2 est exactement ajusté
+        rw.write_file_by_path("/F", &[3u8; 2 * 512]).unwrap();
+        assert_eq!(rw.as_read().open_path("/F").unwrap().unwrap().first_cluster, 5);
+
+        let frag = rw.as_read().fragmentation("/E").unwrap();
+        assert_eq!((frag.clusters, frag.extents), (3, 1));
+        assert!(frag.is_contiguous());
+        assert_eq!(rw.as_read().fragmentation("/HELLO.TXT").unwrap().extents, 1);
     }
 
     #[test]
-    fn open_path_is_case_insensitive_for_short_names() {
-        let disk = build_test_image();
-        let fs = Fat32::new(&disk).unwrap();
+    fn allocator_falls_back_to_fewest_extents() {
+        let mut disk = build_small_fat_image(1, 200);
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+
+        // Remplit tout, puis libère des trous de 3, 1 et 5 clusters
+        rw.write_file_by_path("/FILL", &alloc::vec![0u8; 198 * 512]).unwrap();
+        let chain = rw.as_read().follow_chain(4).unwrap();
+        assert_eq!(chain.len(), 198);
+        rw.remove_file("/FILL").unwrap();
+        for (name, len) in [("/A", 10), ("/B", 3), ("/C", 20), ("/D", 1), ("/E", 30), ("/F", 5)] {
+            rw.write_file_by_path(name, &alloc::vec![1u8; len * 512]).unwrap();
+        }
+        rw.write_file_by_path("/G", &alloc::vec![1u8; 129 * 512]).unwrap();
+        assert_eq!(rw.as_read().count_free_clusters().unwrap(), 0);
+        for name in ["/B", "/D", "/F"] {
+            rw.remove_file(name).unwrap();
+        }
 
-        let entry = fs.open_path("/hello.txt").unwrap().unwrap();
-        assert_eq!(entry.name, "HELLO.TXT");
-        assert!(entry.is_file());
+        // 7 clusters : aucune plage ne suffit; 5 + 3 (les plus grandes), dans l’ordre du disque
+        rw.write_file_by_path("/H", &[9u8; 7 * 512]).unwrap();
+        let frag = rw.as_read().fragmentation("/H").unwrap();
+        assert_eq!((frag.clusters, frag.extents), (7, 2));
+        let first = rw.as_read().open_path("/H").unwrap().unwrap().first_cluster;
+        assert_eq!(first, 14);
+        assert_eq!(rw.as_read().read_file_by_path("/H").unwrap().unwrap(), [9u8; 7 * 512]);
+
+        assert_eq!(
+            rw.write_file_by_path("/I", &[0u8; 3 * 512]).unwrap_err(),
+            FatError::NoSpaceLeft
+        );
     }
 
     #[test]
-    fn write_create_new_file_and_read_back() {
+    fn check_accepts_a_consistent_volume() {
         let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.create_dir_all("/BOOT/CFG").unwrap();
+        rw.write_file_by_path("/BOOT/CFG/config.json", b"{}").unwrap();
+        rw.write_file_by_path("/EMPTY", b"").unwrap();
+
+        let report = rw.as_read().check().unwrap();
+        assert!(report.is_clean(), "{:?}", report.problems);
+        assert_eq!((report.files, report.dirs), (3, 3));
+
+        for (spf, clusters) in [(1, 200), (20, 5000)] {
+            let disk = build_small_fat_image(spf, clusters);
+            assert!(Fat32::new(&disk).unwrap().check().unwrap().is_clean());
+        }
+    }
 
+    #[test]
+    fn check_reports_corruption_and_repair_fixes_it() {
+        let mut disk = build_test_image();
+        let mut clusters = Vec::new();
         {
             let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/NEW.TXT", b"ABC").unwrap();
+            for (name, len) in [("/A", 3), ("/B", 2), ("/C", 1), ("/D", 2)] {
+                rw.write_file_by_path(name, &alloc::vec![7u8; len * 512]).unwrap();
+                let first = rw.as_read().open_path(name).unwrap().unwrap().first_cluster;
+                clusters.push(rw.as_read().follow_chain(first).unwrap());
+            }
+        }
+        let (a, b, c, d) = (&clusters[0], &clusters[1], &clusters[2], &clusters[3]);
+
+        // A : taille réduite à un cluster (chaîne trop longue : réserve valide);
+        // B : boucle; C : rejoint HELLO.TXT; D : lien hors volume; DIR : `..` faux;
+        // 100 -> 101 : chaîne perdue
+        let a_entry = cluster_off(2) + 2 * 32;
+        disk[a_entry + 28..a_entry + 32].copy_from_slice(&100u32.to_le_bytes());
+        set_fat_raw(&mut disk, b[1], b[0]);
+        set_fat_raw(&mut disk, c[0], 3);
+        set_fat_raw(&mut disk, d[0], 0x0100_0000);
+        disk[cluster_off(4) + 32 + 26] = 9;
+        set_fat_raw(&mut disk, 100, 101);
+        set_fat_raw(&mut disk, 101, FAT32_EOC);
+        disk[cluster_off(100)..cluster_off(100) + 4].copy_from_slice(b"LOST");
+
+        let report = Fat32::new(&disk).unwrap().check().unwrap();
+        let expected = [
+            Problem::ChainLoop { path: "/B".into(), cluster: b[0] },
+            Problem::CrossLinked { path: "/C".into(), other: "/HELLO.TXT".into(), cluster: 3 },
+            Problem::BrokenChain { path: "/D".into(), cluster: d[0], next: 0x0100_0000 },
+            Problem::SizeMismatch { path: "/D".into(), size: 1024, clusters: 1 },
+            Problem::BadDotEntry { path: "/DIR".into(), name: "..", expected: 0 },
+            // Le second cluster de D n’est plus référencé
+            Problem::LostChain { start: d[1], clusters: 1 },
+            Problem::LostChain { start: 100, clusters: 2 },
+        ];
+        for problem in &expected {
+            assert!(report.problems.contains(problem), "{problem} absent de {:?}", report.problems);
         }
+        assert_eq!(report.problems.len(), expected.len(), "{:?}", report.problems);
+
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        assert_eq!(rw.repair().unwrap(), report);
+        let after = rw.as_read().check().unwrap();
+        assert!(after.is_clean(), "{:?}", after.problems);
+
+        let ro = rw.as_read();
+        assert_eq!(ro.read_file_by_path("/A").unwrap().unwrap(), [7u8; 100]);
+        assert_eq!(&ro.follow_chain(a[0]).unwrap(), a);
+        assert_eq!(ro.read_file_by_path("/D").unwrap().unwrap().len(), 512);
+        assert_eq!(ro.read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"HELLO");
+        assert_eq!(ro.open_path("/FOUND.000/FILE0000.CHK").unwrap().unwrap().first_cluster, d[1]);
+        let lost = ro.read_file_by_path("/FOUND.000/FILE0001.CHK").unwrap().unwrap();
+        assert_eq!((lost.len(), &lost[..4]), (1024, &b"LOST"[..]));
+    }
 
-        let ro = Fat32::new(&disk).unwrap();
-        let content = ro.read_file_by_path("/NEW.TXT").unwrap().unwrap();
-        assert_eq!(content, b"ABC");
+    #[test]
+    fn repair_resyncs_fat_copies_and_rewrites_dot_entries() {
+        let mut disk = build_small_fat_image(1, 200);
+        let fat_type = FatType::Fat12;
+
+        // Seconde FAT : cluster 10 marqué occupé; DIR : `.` effacé
+        let (rel, len) = fat_type.entry_location(10);
+        let copy1 = 2 * SECTOR_SIZE + rel;
+        fat_type.encode(10, FAT32_EOC, &mut disk[copy1..copy1 + len]);
+        let dir = 5 * SECTOR_SIZE;
+        disk[dir..dir + 32].fill(0);
+        disk[dir] = 0xE5;
+
+        let report = Fat32::new(&disk).unwrap().check().unwrap();
+        assert_eq!(
+            report.problems,
+            [
+                Problem::FatCopyMismatch { copy: 1, entries: 1 },
+                Problem::BadDotEntry { path: "/DIR".into(), name: ".", expected: 3 },
+            ]
+        );
+
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.repair().unwrap();
+        assert!(rw.as_read().check().unwrap().is_clean());
+        drop(rw);
+        assert_eq!(disk[SECTOR_SIZE..2 * SECTOR_SIZE], disk[2 * SECTOR_SIZE..3 * SECTOR_SIZE]);
+        assert_eq!(&disk[dir..dir + 11], b".          ");
     }
 
     #[test]
-    fn write_overwrite_existing_file() {
+    fn format_creates_a_mountable_fat32_volume() {
+        let mut disk = alloc::vec![0xAAu8; 64 * 1024 * 1024];
+        let options = FormatOptions {
+            label: "boot".into(),
+            volume_id: 0x1234_5678,
+            ..FormatOptions::default()
+        };
+        format(&mut disk, &options).unwrap();
+
+        // 64 Mio : clusters de 512 o, 2 FAT de 1016 secteurs (formule de la spécification)
+        let bpb = parse_bpb(&disk[..512], disk.len()).unwrap();
+        assert_eq!((bpb.sectors_per_cluster, bpb.num_fats, bpb.sectors_per_fat), (1, 2, 1016));
+        assert_eq!(bpb.cluster_count(), 131_072 - 32 - 2 * 1016);
+        assert_eq!(disk[0..512], disk[6 * 512..7 * 512]);
+        assert_eq!(&disk[71..82], b"BOOT       ");
+
+        let fs = Fat32::new(&disk).unwrap();
+        assert_eq!(fs.fat_type(), FatType::Fat32);
+        assert_eq!(fs.free_clusters().unwrap(), bpb.cluster_count() - 1);
+        assert_eq!(fs.count_free_clusters().unwrap(), bpb.cluster_count() - 1);
+        assert_eq!(fs.read_fat_entry(1).unwrap(), FAT32_EOC);
+        // L’entrée “nom de volume” n’apparaît pas dans le listing
+        assert!(fs.list_root().unwrap().is_empty());
+        let root = bpb.data_start_byte();
+        assert_eq!(&disk[root..root + 12], b"BOOT       \x08");
+
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.create_dir("/DIR").unwrap();
+        rw.write_file_by_path("/DIR/NOTE.TXT", b"NOTE").unwrap();
+        assert_eq!(rw.as_read().read_file_by_path("/DIR/NOTE.TXT").unwrap().unwrap(), b"NOTE");
+        assert!(rw.as_read().check().unwrap().is_clean());
+    }
+
+    #[test]
+    fn format_rejects_bad_options_and_small_devices() {
+        let mut disk = alloc::vec![0u8; 64 * 1024 * 1024];
+        let with = |label: &str, spc| FormatOptions {
+            label: label.into(),
+            sectors_per_cluster: spc,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format(&mut disk, &with("TOO LONG NAME", None)), Err(FatError::InvalidName));
+        assert_eq!(format(&mut disk, &with("A*B", None)), Err(FatError::InvalidName));
+        assert_eq!(format(&mut disk, &with("", Some(3))), Err(FatError::InvalidSectorsPerCluster));
+        // 64 Mio en clusters de 4 Kio : moins de 65525 clusters
+        assert_eq!(format(&mut disk, &with("", Some(8))), Err(FatError::BufferTooSmall));
+
+        let mut small = alloc::vec![0u8; 16 * 1024 * 1024];
+        assert_eq!(format(&mut small, &FormatOptions::default()), Err(FatError::BufferTooSmall));
+    }
+
+    #[test]
+    fn large_file_is_read_past_4096_clusters() {
         let mut disk = build_test_image();
+        let content: Vec<u8> = (0..5000 * 512 + 7).map(|i| (i % 251) as u8).collect();
 
-        {
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.write_file_by_path("/BIG.BIN", &content).unwrap();
+        let fs = rw.as_read();
+        assert_eq!(fs.fragmentation("/BIG.BIN").unwrap().clusters, 5001);
+        assert_eq!(fs.read_file_by_path("/BIG.BIN").unwrap().unwrap(), content);
+    }
+
+    #[test]
+    fn corrupt_chains_are_reported() {
+        let mut disk = build_test_image();
+        let chain = {
             let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/HELLO.TXT", b"HELLO WORLD").unwrap();
+            rw.write_file_by_path("/DATA.BIN", &[7u8; 2048]).unwrap();
+            let first = rw.as_read().open_path("/DATA.BIN").unwrap().unwrap().first_cluster;
+            rw.as_read().follow_chain(first).unwrap()
+        };
+        assert_eq!(chain.len(), 4);
+
+        let corrupt = |cluster: u32, value: u32| {
+            let mut copy = disk.clone();
+            set_fat_raw(&mut copy, cluster, value);
+            copy
+        };
+        let read = |image: &[u8]| Fat32::new(image).unwrap().read_file_by_path("/DATA.BIN");
+
+        // Boucle (y compris sur soi-même), lien vers un cluster libre, défectueux ou hors volume
+        for image in [
+            corrupt(chain[3], chain[1]),
+            corrupt(chain[0], chain[0]),
+            corrupt(chain[1], 60_000),
+            corrupt(chain[2], 0x0FFF_FFF7),
+            corrupt(chain[2], 0x0FFF_FFF0),
+        ] {
+            assert_eq!(read(&image), Err(FatError::CorruptChain));
         }
 
-        let ro = Fat32::new(&disk).unwrap();
-        let content = ro.read_file_by_path("/HELLO.TXT").unwrap().unwrap();
-        assert_eq!(content, b"HELLO WORLD");
+        // Chaîne plus courte que la taille annoncée : plus de lecture silencieusement tronquée
+        let mut short = corrupt(chain[1], FAT32_EOC);
+        assert_eq!(read(&short), Err(FatError::CorruptChain));
+        let mut rw = Fat32Mut::new(&mut short).unwrap();
+        let mut file = rw.open("/DATA.BIN").unwrap();
+        let mut buf = [0u8; 2048];
+        assert_eq!(file.read(&mut buf), Err(FatError::CorruptChain));
+
+        // Une boucle ne fait pas tourner indéfiniment l’extension du fichier
+        let mut looped = corrupt(chain[3], chain[2]);
+        let mut rw = Fat32Mut::new(&mut looped).unwrap();
+        assert_eq!(rw.append_file("/DATA.BIN", b"!"), Err(FatError::CorruptChain));
+    }
+
+    /// Allocateur du binaire de test qui compte les allocations du thread courant
+    /// (les tests tournent en parallèle).
+    struct CountingAlloc;
+
+    std::thread_local! {
+        static ALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+
+    unsafe impl core::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            unsafe { std::alloc::System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+            unsafe { std::alloc::System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Nombre d’allocations faites par `f` sur le thread courant.
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(|n| n.get());
+        f();
+        ALLOCATIONS.with(|n| n.get()) - before
     }
 
     #[test]
-    fn write_rejects_invalid_8_3_name() {
+    fn read_dir_is_lazy_and_stops_at_the_end_marker() {
         let mut disk = build_test_image();
-
-        let res = {
+        let dir_cluster = {
             let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/TOO_LONG_NAME.TXT", b"x")
+            rw.create_dir("/MANY").unwrap();
+            for i in 0..40 {
+                rw.write_file_by_path(&format!("/MANY/F{i}.TXT"), b"x").unwrap();
+            }
+            rw.as_read().open_path("/MANY").unwrap().unwrap().first_cluster
         };
 
-        assert_eq!(res.unwrap_err(), FatError::InvalidName);
+        // `.`, `..` puis 40 fichiers sur trois clusters, chacun avec son offset sur le disque
+        let fs = Fat32::new(&disk).unwrap();
+        let items: Vec<LocatedEntry> = fs.read_dir("/MANY").unwrap().map(Result::unwrap).collect();
+        assert_eq!(items.len(), 42);
+        assert_eq!(fs.follow_chain(dir_cluster).unwrap().len(), 3);
+        for item in &items[2..] {
+            assert_eq!(disk[item.offset], b'F');
+            assert_eq!(&disk[item.offset + 8..item.offset + 11], b"TXT");
+        }
+        assert_eq!(fs.read_dir("/HELLO.TXT").err(), Some(FatError::NotADirectory));
+        assert_eq!(fs.read_dir("/NOPE").err(), Some(FatError::PathNotFound));
+
+        // Une entrée trouvée dans le premier cluster ne lit pas la suite du répertoire
+        let dev = CountingDevice {
+            data: disk.clone(),
+            reads: core::cell::Cell::new(0),
+            writes: 0,
+        };
+        let fs = Fat32::from_device(dev).unwrap();
+        fs.device().reads.set(0);
+        assert!(fs.open_path("/MANY/F1.TXT").unwrap().is_some());
+        let lookup = fs.device().reads.get();
+        fs.device().reads.set(0);
+        assert_eq!(fs.list_dir_path("/MANY").unwrap().len(), 42);
+        assert!(lookup < fs.device().reads.get(), "{lookup} secteurs lus");
+
+        // Les entrées parcourues ne sont pas décodées : seules celles trouvées (MANY, puis
+        // le fichier) allouent, quel que soit le nombre d’entrées lues avant
+        let fs = Fat32::new(&disk).unwrap();
+        fs.open_path("/MANY/F39.TXT").unwrap().unwrap();
+        let found = allocations_during(|| {
+            fs.open_path("/MANY/F39.TXT").unwrap().unwrap();
+        });
+        assert!(found <= 4, "{found} allocations");
+
+        let mut broken = disk.clone();
+        set_fat_raw(&mut broken, dir_cluster, 0x0FFF_FFF7);
+        let fs = Fat32::new(&broken).unwrap();
+        assert!(fs.open_path("/MANY/F1.TXT").unwrap().is_some());
+        assert_eq!(fs.list_dir_path("/MANY").err(), Some(FatError::CorruptChain));
+
+        // Rien n’est lu après le marqueur de fin, même si la suite n’est pas vide
+        let end = items[7].offset;
+        disk[end] = 0x00;
+        let fs = Fat32::new(&disk).unwrap();
+        let names: Vec<String> = fs.read_dir("/MANY").unwrap().map(|l| l.unwrap().entry.name).collect();
+        assert_eq!(names.len(), 7);
+        assert_eq!(names[6], "F4.TXT");
+    }
+
+    /// Périphérique de test qui “perd le courant” après `budget` secteurs écrits :
+    /// les écritures suivantes échouent sans toucher au disque.
+    struct PowerCutDevice {
+        data: Vec<u8>,
+        budget: usize,
+        writes: usize,
+    }
+
+    impl BlockDevice for PowerCutDevice {
+        fn sector_count(&self) -> u64 {
+            self.data.sector_count()
+        }
+
+        fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+            self.data.read_sectors(start, buf)
+        }
+
+        fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+            for (i, sector) in buf.chunks(SECTOR_SIZE).enumerate() {
+                if self.writes == self.budget {
+                    return Err(FatError::Io);
+                }
+                self.data.write_sectors(start + i as u64, sector)?;
+                self.writes += 1;
+            }
+            Ok(())
+        }
+    }
+
+    /// Cache de capacité nulle : toutes les écritures vont directement au périphérique.
+    const NO_CACHE: CacheConfig = CacheConfig {
+        fat_sectors: 0,
+        data_sectors: 0,
+    };
+
+    /// Cache de cinq secteurs : presque chaque écriture en évince une autre.
+    const TINY_CACHE: CacheConfig = CacheConfig {
+        fat_sectors: 2,
+        data_sectors: 3,
+    };
+
+    type CutVolume<'a> = Fat32Mut<CachedDevice<&'a mut PowerCutDevice>>;
+
+    /// Rejoue `op` sur `base`, à travers un cache `cache`, en coupant le courant après
+    /// chaque écriture de secteur possible (0 = avant la première), et passe chaque
+    /// image obtenue à `verify`.
+    ///
+    /// Retour : nombre de secteurs écrits par l’opération complète.
+    fn power_cut_harness(
+        base: &[u8],
+        cache: CacheConfig,
+        op: impl Fn(CutVolume<'_>) -> Result<(), FatError>,
+        mut verify: impl FnMut(usize, usize, Vec<u8>),
+    ) -> usize {
+        let run = |budget| {
+            let mut dev = PowerCutDevice {
+                data: base.to_vec(),
+                budget,
+                writes: 0,
+            };
+            let done = Fat32Mut::from_device(CachedDevice::new(&mut dev, cache)).and_then(&op);
+            (done, dev)
+        };
+
+        let (done, full) = run(usize::MAX);
+        done.unwrap();
+        for cut in 0..=full.writes {
+            let (_, dev) = run(cut);
+            verify(cut, full.writes, dev.data);
+        }
+        full.writes
     }
 
     #[test]
-    fn write_fails_when_parent_directory_missing() {
-        let mut disk = build_test_image();
+    fn power_loss_at_any_sector_leaves_a_consistent_volume() {
+        let mut base = build_test_image();
+        set_fat_raw(&mut base, 1, FAT32_EOC);
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.write_file_by_path("/OLD.BIN", &[b'A'; 1536]).unwrap();
+        rw.unmount().unwrap();
+        assert!(!Fat32::new(&base).unwrap().is_dirty().unwrap());
+
+        let op = |mut rw: CutVolume<'_>| {
+            rw.write_file_by_path("/OLD.BIN", &[b'B'; 2560])?;
+            rw.write_file_by_path("/Nouveau fichier.txt", b"new")?;
+            rw.create_dir("/SUB")?;
+            rw.unmount().map(|_| ())
+        };
 
-        let res = {
-            let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/NOPE/FILE.TXT", b"x")
+        // Sans cache, puis avec un cache qui évince (et écrit) sans arrêt :
+        // les barrières d’ordre doivent donner les mêmes garanties
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut new_seen) = (false, false);
+            let total = power_cut_harness(&base, cache, op, |cut, total, mut image| {
+                let fs = Fat32::new(&image).unwrap();
+                assert_eq!(fs.is_dirty().unwrap(), cut != 0 && cut != total, "coupure après {cut} secteurs");
+
+                // Au pire des clusters perdus : jamais de lien cassé, de cross-link ou de taille fausse
+                let report = fs.check().unwrap();
+                assert!(
+                    report.problems.iter().all(|p| matches!(p, Problem::LostChain { .. })),
+                    "coupure après {cut} secteurs : {:?}",
+                    report.problems
+                );
+
+                // L’ancien contenu ou le nouveau, jamais un mélange
+                match fs.read_file_by_path("/OLD.BIN").unwrap().unwrap() {
+                    data if data == [b'A'; 1536] => old_seen = true,
+                    data if data == [b'B'; 2560] => new_seen = true,
+                    data => panic!("coupure après {cut} secteurs : contenu mélangé ({} octets)", data.len()),
+                }
+                if let Some(data) = fs.read_file_by_path("/Nouveau fichier.txt").unwrap() {
+                    assert_eq!(data, b"new");
+                }
+
+                let mut rw = Fat32Mut::new(&mut image).unwrap();
+                rw.repair().unwrap();
+                assert!(rw.as_read().check().unwrap().is_clean());
+                rw.unmount().unwrap();
+                assert!(!Fat32::new(&image).unwrap().is_dirty().unwrap());
+            });
+            assert!(total > 10);
+            assert!(old_seen && new_seen);
+        }
+    }
+
+    #[test]
+    fn replace_file_keeps_the_old_file_until_the_copy_is_done() {
+        let mut base = build_test_image();
+        set_fat_raw(&mut base, 1, FAT32_EOC);
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.write_file_by_path("/OLD.BIN", &[b'A'; 1536]).unwrap();
+        let mut attrs = rw.as_read().metadata("/OLD.BIN").unwrap().attrs;
+        attrs.hidden = true;
+        rw.set_attributes("/OLD.BIN", attrs).unwrap();
+
+        // Une copie qui échoue ne touche pas au fichier et rend ses clusters
+        let free = rw.as_read().free_clusters().unwrap();
+        let failed = rw.replace_file("/OLD.BIN", |f| {
+            f.write(&[b'B'; 1024])?;
+            Err(FatError::Io)
+        });
+        assert_eq!(failed, Err(FatError::Io));
+        assert_eq!(rw.as_read().read_file_by_path("/OLD.BIN").unwrap().unwrap(), [b'A'; 1536]);
+        assert_eq!(rw.as_read().free_clusters().unwrap(), free);
+        rw.unmount().unwrap();
+
+        // Comme `put` : copie par morceaux, coupure à chaque secteur possible
+        let op = |mut rw: CutVolume<'_>| {
+            rw.replace_file("/OLD.BIN", |f| {
+                for chunk in [b'B'; 2560].chunks(700) {
+                    f.write(chunk)?;
+                }
+                Ok(())
+            })?;
+            rw.unmount().map(|_| ())
+        };
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut new_seen) = (false, false);
+            power_cut_harness(&base, cache, op, |cut, _, image| {
+                let fs = Fat32::new(&image).unwrap();
+                let report = fs.check().unwrap();
+                assert!(
+                    report.problems.iter().all(|p| matches!(p, Problem::LostChain { .. })),
+                    "coupure après {cut} secteurs : {:?}",
+                    report.problems
+                );
+                match fs.read_file_by_path("/OLD.BIN").unwrap().unwrap() {
+                    data if data == [b'A'; 1536] => old_seen = true,
+                    data if data == [b'B'; 2560] => new_seen = true,
+                    data => panic!("coupure après {cut} secteurs : contenu mélangé ({} octets)", data.len()),
+                }
+                assert!(fs.metadata("/OLD.BIN").unwrap().attrs.hidden);
+            });
+            assert!(old_seen && new_seen);
+        }
+    }
+
+    #[test]
+    fn rename_over_a_file_survives_power_loss_at_any_sector() {
+        let mut base = build_test_image();
+        set_fat_raw(&mut base, 1, FAT32_EOC);
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.write_file_by_path("/APP.BIN", &[b'A'; 1536]).unwrap();
+        rw.write_file_by_path("/APP.NEW", &[b'B'; 2560]).unwrap();
+        rw.unmount().unwrap();
+
+        let op = |mut rw: CutVolume<'_>| {
+            rw.rename("/APP.NEW", "/APP.BIN")?;
+            rw.unmount().map(|_| ())
         };
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut new_seen) = (false, false);
+            power_cut_harness(&base, cache, op, |cut, _, mut image| {
+                // Sans journal, jamais deux entrées sur la même chaîne : au pire la source est perdue
+                let fs = Fat32::new(&image).unwrap();
+                let report = fs.check().unwrap();
+                assert!(
+                    report.problems.iter().all(|p| matches!(p, Problem::LostChain { .. })),
+                    "coupure après {cut} secteurs : {:?}",
+                    report.problems
+                );
+                let app = fs.read_file_by_path("/APP.BIN").unwrap().unwrap();
+                let new = fs.read_file_by_path("/APP.NEW").unwrap();
+                match (app.as_slice(), new.as_deref()) {
+                    (a, Some(n)) if a == [b'A'; 1536] && n == [b'B'; 2560] => old_seen = true,
+                    (a, None) if a == [b'A'; 1536] => assert!(!report.is_clean()),
+                    (a, None) if a == [b'B'; 2560] => new_seen = true,
+                    (a, n) => panic!("coupure après {cut} secteurs : {} octets, APP.NEW {:?}", a.len(), n.map(|n| n.len())),
+                }
 
-        assert_eq!(res.unwrap_err(), FatError::PathNotFound);
+                // Après `repair`, le nouveau contenu est toujours quelque part
+                // (APP.BIN, APP.NEW, ou une chaîne récupérée dans FOUND.000)
+                let mut rw = Fat32Mut::new(&mut image).unwrap();
+                rw.repair().unwrap();
+                let fs = rw.as_read();
+                assert!(fs.check().unwrap().is_clean());
+                let mut paths = alloc::vec![String::from("/APP.BIN"), String::from("/APP.NEW")];
+                if let Ok(found) = fs.list_dir_path("/FOUND.000") {
+                    paths.extend(found.iter().filter(|e| e.is_file()).map(|e| format!("/FOUND.000/{}", e.name)));
+                }
+                assert!(
+                    paths.iter().any(|p| {
+                        let data = fs.read_file_by_path(p).unwrap().unwrap_or_default();
+                        data.len() >= 2560 && data[..2560] == [b'B'; 2560]
+                    }),
+                    "coupure après {cut} secteurs : nouveau contenu perdu"
+                );
+            });
+            assert!(old_seen && new_seen);
+        }
+
+        // Avec un journal, le remplacement est atomique : APP.BIN et APP.NEW d’avant,
+        // ou le seul nouveau APP.BIN
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.create_journal(64).unwrap();
+        rw.unmount().unwrap();
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut new_seen) = (false, false);
+            power_cut_harness(&base, cache, op, |cut, _, mut image| {
+                Fat32Mut::new(&mut image).unwrap();
+                let fs = Fat32::new(&image).unwrap();
+                assert!(fs.check().unwrap().is_clean(), "coupure après {cut} secteurs");
+                let app = fs.read_file_by_path("/APP.BIN").unwrap().unwrap();
+                let new = fs.read_file_by_path("/APP.NEW").unwrap();
+                match (app.as_slice(), new.as_deref()) {
+                    (a, Some(n)) if a == [b'A'; 1536] && n == [b'B'; 2560] => old_seen = true,
+                    (a, None) if a == [b'B'; 2560] => new_seen = true,
+                    (a, n) => panic!("coupure après {cut} secteurs : {} octets, APP.NEW {:?}", a.len(), n.map(|n| n.len())),
+                }
+            });
+            assert!(old_seen && new_seen);
+        }
     }
 
     #[test]
-    fn overwrite_frees_old_clusters_in_fat() {
+    fn move_across_directories_survives_power_loss_at_any_sector() {
+        let mut base = build_test_image();
+        set_fat_raw(&mut base, 1, FAT32_EOC);
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.create_dir("/SRC").unwrap();
+        rw.create_dir("/DST").unwrap();
+        rw.write_file_by_path("/SRC/DATA.BIN", &[b'D'; 1536]).unwrap();
+        rw.unmount().unwrap();
+
+        let op = |mut rw: CutVolume<'_>| {
+            rw.rename("/SRC/DATA.BIN", "/DST/DATA.BIN")?;
+            rw.unmount().map(|_| ())
+        };
+        let read = |fs: &Fat32<&[u8]>, path: &str| {
+            let data = fs.read_file_by_path(path).unwrap();
+            assert!(data.as_ref().is_none_or(|d| d == &[b'D'; 1536]), "{path} abîmé");
+            data.is_some()
+        };
+
+        // Sans journal, les données gardent toujours un nom : au pire les deux
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut both_seen, mut new_seen) = (false, false, false);
+            power_cut_harness(&base, cache, op, |cut, _, image| {
+                let fs = Fat32::new(&image).unwrap();
+                let report = fs.check().unwrap();
+                match (read(&fs, "/SRC/DATA.BIN"), read(&fs, "/DST/DATA.BIN")) {
+                    (true, false) => old_seen = true,
+                    (false, true) => new_seen = true,
+                    (true, true) => {
+                        both_seen = true;
+                        assert!(
+                            !report.is_clean()
+                                && report.problems.iter().all(|p| matches!(p, Problem::CrossLinked { .. })),
+                            "coupure après {cut} secteurs : {:?}",
+                            report.problems
+                        );
+                        return;
+                    }
+                    (false, false) => panic!("coupure après {cut} secteurs : fichier perdu"),
+                }
+                assert!(report.is_clean(), "coupure après {cut} secteurs : {:?}", report.problems);
+            });
+            assert!(old_seen && both_seen && new_seen);
+        }
+
+        // Avec un journal, le fichier est à un seul endroit et le volume reste cohérent
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.create_journal(64).unwrap();
+        rw.unmount().unwrap();
+        for cache in [NO_CACHE, TINY_CACHE] {
+            let (mut old_seen, mut new_seen) = (false, false);
+            power_cut_harness(&base, cache, op, |cut, _, image| {
+                let fs = Fat32::new(&image).unwrap();
+                assert!(fs.check().unwrap().is_clean(), "coupure après {cut} secteurs");
+                match (read(&fs, "/SRC/DATA.BIN"), read(&fs, "/DST/DATA.BIN")) {
+                    (true, false) => old_seen = true,
+                    (false, true) => new_seen = true,
+                    found => panic!("coupure après {cut} secteurs : {found:?}"),
+                }
+            });
+            assert!(old_seen && new_seen);
+        }
+    }
+
+    #[test]
+    fn transaction_commits_every_write_or_none() {
         let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.transaction(|tx| {
+            tx.write_file_by_path("/HELLO.TXT", b"v2")?;
+            tx.create_dir("/BOOT")?;
+            // `rename` ne relance pas de transaction dans la transaction
+            tx.write_file_by_path("/BOOT/VERSION.NEW", b"2")?;
+            tx.rename("/BOOT/VERSION.NEW", "/BOOT/VERSION")
+        })
+        .unwrap();
+        assert_eq!(rw.as_read().read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"v2");
+        assert_eq!(rw.as_read().read_file_by_path("/BOOT/VERSION").unwrap().unwrap(), b"2");
+
+        // Journal créé à la première transaction : caché, système, 64 Kio, vide
+        let journal = rw.as_read().open_path("/JOURNAL.SYS").unwrap().unwrap();
+        assert!(journal.attrs.hidden && journal.attrs.system);
+        assert_eq!(journal.size, 128 * 512);
+        assert_eq!(rw.create_journal(16), Err(FatError::AlreadyExists));
+        rw.unmount().unwrap();
+        let snapshot = disk.clone();
+
+        // Échec de la closure : rien n’atteint le volume, sauf le bit “sale”
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        let failed: Result<(), FatError> = rw.transaction(|tx| {
+            tx.write_file_by_path("/HELLO.TXT", b"v3")?;
+            tx.remove_file("/ABSENT.TXT")
+        });
+        assert_eq!(failed, Err(FatError::PathNotFound));
+        rw.unmount().unwrap();
+        assert_eq!(disk, snapshot);
+
+        // Transaction plus grosse que le journal : annulée elle aussi
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.remove_file("/JOURNAL.SYS").unwrap();
+        rw.create_journal(4).unwrap();
+        let before = rw.as_read().check().unwrap();
+        let full = rw.transaction(|tx| tx.write_file_by_path("/BIG.BIN", &[7u8; 4096]));
+        assert_eq!(full, Err(FatError::JournalFull));
+        assert!(rw.as_read().open_path("/BIG.BIN").unwrap().is_none());
+        assert_eq!(rw.as_read().check().unwrap(), before);
+        assert_eq!(rw.as_read().read_file_by_path("/HELLO.TXT").unwrap().unwrap(), b"v2");
+    }
 
-        // 600 bytes => 2 clusters (cluster_size=512)
-        let big = vec![0x41u8; 600];
+    #[test]
+    fn journal_is_replayed_after_power_loss_during_commit() {
+        let mut base = build_test_image();
+        set_fat_raw(&mut base, 1, FAT32_EOC);
+        let mut rw = Fat32Mut::new(&mut base).unwrap();
+        rw.write_file_by_path("/KERNEL.BIN", &[b'A'; 1536]).unwrap();
+        rw.create_journal(64).unwrap();
+        rw.unmount().unwrap();
+
+        let op = |mut rw: CutVolume<'_>| {
+            rw.transaction(|tx| {
+                tx.write_file_by_path("/KERNEL.BIN", &[b'B'; 2560])?;
+                tx.write_file_by_path("/VERSION", b"2")
+            })?;
+            rw.unmount().map(|_| ())
+        };
 
-        let (first_cluster, second_cluster) = {
-            let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/BIG.TXT", &big).unwrap();
+        let read_ro = |disk: &mut [u8]| {
+            let options = MountOptions {
+                read_only: true,
+                ..MountOptions::default()
+            };
+            let mut ro = Fat32Mut::from_device_with(disk, options).unwrap();
+            let mut kernel = Vec::new();
+            std::io::Read::read_to_end(&mut ro.open("/KERNEL.BIN").unwrap(), &mut kernel).unwrap();
+            let version = ro.as_read().read_file_by_path("/VERSION").unwrap();
+            (kernel, version)
+        };
 
-            let ro = rw.as_read();
-            let e = ro.open_path("/BIG.TXT").unwrap().unwrap();
-            assert_eq!(e.size as usize, big.len());
-            assert!(e.first_cluster >= 2);
+        let (mut old_seen, mut new_seen, mut pending_seen) = (false, false, false);
+        power_cut_harness(&base, NO_CACHE, op, |cut, _, mut image| {
+            // Vue en lecture et montage en lecture seule d’abord : le journal validé
+            // est appliqué en mémoire, sans écrire sur l’image
+            let before = image.clone();
+            let fs = Fat32::new(&before).unwrap();
+            pending_seen |= fs.has_pending_journal();
+            let kernel = fs.read_file_by_path("/KERNEL.BIN").unwrap().unwrap();
+            let version = fs.read_file_by_path("/VERSION").unwrap();
+            assert!(fs.check().unwrap().is_clean(), "coupure après {cut} secteurs");
+            assert_eq!(read_ro(&mut image), (kernel.clone(), version.clone()));
+            assert_eq!(image, before, "coupure après {cut} secteurs");
+
+            // Le montage en écriture rejoue le journal : même contenu, sur le volume
+            Fat32Mut::new(&mut image).unwrap();
+            let fs = Fat32::new(&image).unwrap();
+            assert!(!fs.has_pending_journal());
+            assert_eq!(fs.read_file_by_path("/KERNEL.BIN").unwrap().unwrap(), kernel);
+            assert_eq!(fs.read_file_by_path("/VERSION").unwrap(), version);
+            match (kernel.as_slice(), version.as_deref()) {
+                (k, None) if k == [b'A'; 1536] => old_seen = true,
+                (k, Some(b"2")) if k == [b'B'; 2560] => new_seen = true,
+                (k, v) => panic!("coupure après {cut} secteurs : {} octets, {v:?}", k.len()),
+            }
+            assert!(fs.check().unwrap().is_clean(), "coupure après {cut} secteurs");
+        });
+        assert!(old_seen && new_seen && pending_seen);
+    }
 
-            let c1 = e.first_cluster;
-            let c2 = fat_entry_raw(&disk, c1);
-            assert!(c2 >= 2, "le fichier BIG.TXT devrait chaîner sur un 2e cluster");
-            assert!(fat_entry_raw(&disk, c2) >= 0x0FFF_FFF8, "le 2e cluster devrait être EOC");
+    #[test]
+    fn cached_device_serves_repeated_reads_and_defers_writes() {
+        fn ops<D: BlockDevice>(rw: &mut Fat32Mut<D>) {
+            rw.write_file_by_path("/NEW.TXT", &[b'N'; 2048]).unwrap();
+            rw.create_dir("/SUB").unwrap();
+            rw.write_file_by_path("/SUB/BIG.BIN", &[b'B'; 16 * 512]).unwrap();
+            rw.append_file("/NEW.TXT", b"+").unwrap();
+            rw.remove_file("/HELLO.TXT").unwrap();
+        }
 
-            (c1, c2)
+        let dev = CountingDevice {
+            data: build_test_image(),
+            reads: core::cell::Cell::new(0),
+            writes: 0,
         };
+        let config = CacheConfig {
+            fat_sectors: 4,
+            data_sectors: 8,
+        };
+        let mut rw = Fat32Mut::from_device(CachedDevice::new(dev, config)).unwrap();
 
-        // Overwrite en fichier vide => doit libérer les anciens clusters
-        {
-            let mut rw = Fat32Mut::new(&mut disk).unwrap();
-            rw.write_file_by_path("/BIG.TXT", b"").unwrap();
+        // La FAT et la racine ne sont lues qu’une fois sur le périphérique
+        for _ in 0..10 {
+            let content = rw.as_read().read_file_by_path("/HELLO.TXT").unwrap().unwrap();
+            assert_eq!(content, b"HELLO");
         }
+        let stats = rw.cache_stats();
+        assert!(stats.hits > 5 * stats.misses, "{stats:?}");
+        assert_eq!(rw.as_read().device().get_ref().reads.get(), stats.misses + 1);
+
+        // Les données et la FAT passent les barrières d’ordre; la dernière étape
+        // (ici l’entrée, puis la libération) reste en cache jusqu’au flush
+        rw.write_file_by_path("/LOG.TXT", b"boot").unwrap();
+        let writes = rw.as_read().device().get_ref().writes;
+        assert!(writes > 0);
+        assert!(rw.as_read().device().dirty_sectors() > 0);
+        rw.remove_file("/LOG.TXT").unwrap();
+        assert!(rw.as_read().device().dirty_sectors() > 0);
+        rw.flush().unwrap();
+        assert!(rw.as_read().device().get_ref().writes > writes);
+        assert_eq!(rw.as_read().device().dirty_sectors(), 0);
+
+        // Même résultat qu’un volume sans cache, malgré les évictions
+        ops(&mut rw);
+        let stats = rw.cache_stats();
+        assert!(stats.evictions > 0 && stats.write_backs > 0, "{stats:?}");
+        let cached = rw.unmount().unwrap().into_inner().unwrap().data;
+
+        let mut plain = build_test_image();
+        let mut rw = Fat32Mut::new(&mut plain).unwrap();
+        rw.write_file_by_path("/LOG.TXT", b"boot").unwrap();
+        rw.remove_file("/LOG.TXT").unwrap();
+        ops(&mut rw);
+        rw.unmount().unwrap();
+        assert!(cached == plain);
+    }
 
-        // FAT entries doivent être à 0 (libres)
-        assert_eq!(fat_entry_raw(&disk, first_cluster), 0);
-        assert_eq!(fat_entry_raw(&disk, second_cluster), 0);
+    #[test]
+    fn metadata_and_attributes_round_trip() {
+        for b in 0..0x40u8 {
+            assert_eq!(Attributes::from_byte(b).to_byte(), b);
+        }
 
-        let ro = Fat32::new(&disk).unwrap();
-        let e = ro.open_path("/BIG.TXT").unwrap().unwrap();
-        assert_eq!(e.size, 0);
-        assert_eq!(e.first_cluster, 0);
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.write_file_by_path("/DIR/DATA.BIN", &[1u8; 1000]).unwrap();
+
+        let meta = rw.as_read().metadata("/DIR/DATA.BIN").unwrap();
+        assert!(meta.is_file());
+        assert_eq!((meta.size, meta.clusters, meta.allocated_size), (1000, 2, 1024));
+        assert_eq!(meta.modified, Some(FatDateTime::EPOCH));
+        let hello = rw.as_read().metadata("/HELLO.TXT").unwrap();
+        assert_eq!((hello.size, hello.clusters, hello.first_cluster), (5, 1, 3));
+        let root = rw.as_read().metadata("/").unwrap();
+        assert!(root.is_dir() && root.clusters == 1 && root.created.is_none());
+        assert_eq!(rw.as_read().metadata("/NOPE").err(), Some(FatError::PathNotFound));
+
+        // Seuls R/H/S/A changent : un répertoire reste un répertoire, les dates ne bougent pas
+        let attrs = Attributes {
+            read_only: true,
+            hidden: true,
+            ..Attributes::default()
+        };
+        rw.set_attributes("/DIR/DATA.BIN", attrs).unwrap();
+        rw.set_attributes("/DIR", Attributes { system: true, ..Attributes::default() }).unwrap();
+        assert_eq!(rw.set_attributes("/", attrs), Err(FatError::Other));
+
+        let meta2 = rw.as_read().metadata("/DIR/DATA.BIN").unwrap();
+        assert_eq!(meta2.attrs.to_byte(), 0x03);
+        assert_eq!(meta2.modified, meta.modified);
+        let dir = rw.as_read().metadata("/DIR").unwrap();
+        assert_eq!(dir.attrs.to_byte(), 0x14);
+        assert!(rw.as_read().list_dir_path("/DIR").is_ok());
+    }
+
+    #[test]
+    fn read_only_entries_and_mounts_refuse_writes() {
+        let mut disk = build_test_image();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        rw.write_file_by_path("/KEEP.TXT", b"v1").unwrap();
+        rw.create_dir("/LOCKED").unwrap();
+        let ro = Attributes {
+            read_only: true,
+            hidden: true,
+            ..Attributes::default()
+        };
+        rw.set_attributes("/KEEP.TXT", ro).unwrap();
+        rw.set_attributes("/LOCKED", ro).unwrap();
+
+        // Entrées en lecture seule : lisibles, mais ni écrasées, ni modifiées, ni supprimées
+        let before = disk.clone();
+        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        assert_eq!(rw.write_file_by_path("/KEEP.TXT", b"v2"), Err(FatError::ReadOnly));
+        assert_eq!(rw.append_file("/KEEP.TXT", b"+"), Err(FatError::ReadOnly));
+        assert_eq!(rw.set_len("/KEEP.TXT", 0), Err(FatError::ReadOnly));
+        assert_eq!(rw.remove_file("/KEEP.TXT"), Err(FatError::ReadOnly));
+        assert_eq!(rw.remove_dir("/LOCKED"), Err(FatError::ReadOnly));
+        assert_eq!(rw.rename("/KEEP.TXT", "/MOVED.TXT"), Err(FatError::ReadOnly));
+        assert_eq!(rw.rename("/HELLO.TXT", "/KEEP.TXT"), Err(FatError::ReadOnly));
+        let mut buf = [0u8; 2];
+        assert_eq!(rw.open("/KEEP.TXT").unwrap().read(&mut buf), Ok(2));
+        drop(rw);
+        assert!(disk == before);
+
+        // `force` passe outre, et l’overwrite garde les attributs
+        let force = MountOptions {
+            force: true,
+            ..MountOptions::default()
+        };
+        let mut rw = Fat32Mut::from_device_with(&mut disk, force).unwrap();
+        rw.write_file_by_path("/KEEP.TXT", b"v2").unwrap();
+        let attrs = rw.as_read().metadata("/KEEP.TXT").unwrap().attrs;
+        assert!(attrs.read_only && attrs.hidden && attrs.archive);
+        rw.rename("/KEEP.TXT", "/MOVED.TXT").unwrap();
+        rw.remove_dir("/LOCKED").unwrap();
+        rw.unmount().unwrap();
+
+        // Montage en lecture seule : aucune écriture, pas même le bit “sale”
+        let before = disk.clone();
+        let read_only = MountOptions {
+            read_only: true,
+            force: true,
+        };
+        let mut rw = Fat32Mut::from_device_with(&mut disk, read_only).unwrap();
+        assert_eq!(rw.write_file_by_path("/NEW.TXT", b"x"), Err(FatError::ReadOnly));
+        assert_eq!(rw.create_dir("/D"), Err(FatError::ReadOnly));
+        assert_eq!(rw.set_attributes("/MOVED.TXT", Attributes::default()), Err(FatError::ReadOnly));
+        assert_eq!(rw.transaction(|tx| tx.remove_file("/HELLO.TXT")), Err(FatError::ReadOnly));
+        assert_eq!(rw.as_read().read_file_by_path("/MOVED.TXT").unwrap().unwrap(), b"v2");
+        rw.unmount().unwrap();
+        assert!(disk == before);
     }
 }
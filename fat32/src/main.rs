@@ -1,35 +1,82 @@
 //! Petite CLI pour explorer et modifier une image FAT32.
 //!
 //! Cette CLI s’appuie sur la bibliothèque `fat32_parser`:
-//! - lecture: `ls`, `cat`, navigation avec `cd` et `pwd`
-//! - écriture simple: `put` pour créer/écraser un fichier 8.3
+//! - lecture: `ls` (`-l` pour la taille et la date de modification), `cat`,
+//!   navigation avec `cd` et `pwd`
+//! - écriture simple: `put` pour créer/écraser un fichier (noms longs acceptés)
+//! - `cat` et `put` passent par un `fat32_parser::File`: le contenu est copié
+//!   par morceaux, sans être chargé en mémoire
+//! - répertoires: `mkdir` (avec `-p` pour créer les parents)
+//! - les entrées écrites sont datées avec l’horloge système (UTC)
+//! - suppression (shell): `rm` pour un fichier, `rmdir` pour un répertoire vide
 //! - mode non interactif via options ou mode shell interactif
+//! - images disque complètes: `parts` liste les partitions MBR/GPT,
+//!   `--partition N` ouvre la N-ième partition FAT (à partir de 0)
+//! - vérification: `fsck` liste les incohérences (chaînes perdues, cross-links,
+//!   boucles, tailles, copies de FAT, `.`/`..`), `fsck --repair` les corrige
+//! - formatage: `mkfs --size 64M out.img` crée une image FAT32 vide
+//! - protection: les fichiers en lecture seule (`+r`) ne sont ni écrasés, ni supprimés,
+//!   ni renommés sans `--force`; `--ro` ouvre l’image en lecture seule pour toutes les commandes
+//! - métadonnées: `stat` (taille, place occupée, attributs, dates),
+//!   `attrib` pour afficher ou changer les attributs (`+r`, `-h`, `+s`, `-a`...)
+//!
+//! L’image est ouverte comme un `std::fs::File` (périphérique bloc de la bibliothèque):
+//! seuls les secteurs utiles sont lus ou réécrits, l’image n’est jamais chargée en entier,
+//! et les secteurs récents restent dans un cache (`CachedDevice`).
 //! 
 //! Exemple rapide:
 //! ```
 //! fat32_cli --file disk.img --ls /
 //! fat32_cli --file disk.img --cat /HELLO.TXT
 //! fat32_cli --file disk.img --put /NEW.TXT ./local.txt
+//! fat32_cli --file disk.img --mkdir /BOOT/CFG
+//! fat32_cli --file whole.img --parts
+//! fat32_cli --file whole.img --partition 0 --ls /
+//! fat32_cli --file disk.img fsck --repair
+//! fat32_cli --file disk.img --stat /HELLO.TXT
+//! fat32_cli --file disk.img --attrib /HELLO.TXT +r +h
+//! fat32_cli --file golden.img --ro
+//! fat32_cli mkfs --size 64M --label BOOT out.img
 //! ```
-use fat32_parser::{Fat32, Fat32Mut};
+use fat32_parser::{
+    format, list_partitions, Attributes, BlockDevice, CacheConfig, CachedDevice, CheckReport, Fat32,
+    Fat32Mut, FatDateTime, FatError, FormatOptions, MountOptions, PartitionDevice, PartitionKind,
+    SystemClock,
+};
 use std::env;
-use std::fs;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
 
 /// Affiche l’usage de la CLI avec les commandes disponibles.
 fn print_usage() {
     eprintln!(
         "Usage:
-  fat32_cli --file <disk.img> [--ls <path>] [--cat <path>] [--put <fat_path> <host_file>]
+  fat32_cli --file <disk.img> [--partition <n>] [--ro] [--force]
+                              [--ls <path> [-l]] [--cat <path>]
+                              [--put <fat_path> <host_file>] [--mkdir <path>] [--parts]
+                              [--stat <path>] [--attrib <path> [+r|-r|+h|-h|+s|-s|+a|-a]...]
+                              [fsck [--repair]]
+  fat32_cli mkfs --size <taille> [--label <nom>] <image>
 
 Exemples:
   fat32_cli --file disk.img --ls /
+  fat32_cli --file disk.img --ls / -l          (avec dates de modification)
   fat32_cli --file disk.img --cat /HELLO.TXT
   fat32_cli --file disk.img --put /NEW.TXT ./local.txt
+  fat32_cli --file disk.img --mkdir /BOOT/CFG   (crée aussi les parents)
+  fat32_cli --file whole.img --parts            (table MBR/GPT)
+  fat32_cli --file whole.img --partition 0 --ls /
+  fat32_cli --file disk.img fsck                (vérification seule)
+  fat32_cli --file disk.img fsck --repair       (corrige l'image)
+  fat32_cli --file disk.img --stat /HELLO.TXT   (taille, clusters, attributs, dates)
+  fat32_cli --file disk.img --attrib /HELLO.TXT +r -a
+  fat32_cli --file golden.img --ro              (shell sans aucune écriture possible)
+  fat32_cli --file disk.img --force --put /RO.TXT ./local.txt   (écrase un fichier +r)
+  fat32_cli mkfs --size 64M out.img             (nouvelle image FAT32, taille en K/M/G)
 
 Mode shell:
   fat32_cli --file disk.img
-  (puis: ls, cd, cat, put, pwd, help, exit)"
+  (puis: ls, cd, cat, stat, attrib, put, mkdir, rm, rmdir, parts, fsck, pwd, help, exit)"
     );
 }
 
@@ -37,10 +84,18 @@ Mode shell:
 fn print_shell_help() {
     println!(
         "Commandes:
-  ls [path]            - lister un répertoire
+  ls [-l] [path]       - lister un répertoire (-l: date de modification)
   cat <path>           - lire un fichier
   cd [path]            - changer de répertoire courant
+  stat <path>          - taille, place occupée, attributs et dates
+  attrib <path> [+r|-r|+h|-h|+s|-s|+a|-a]...
+                       - afficher ou changer les attributs
   put <fat_path> <src> - écrire un fichier dans l'image (persistant)
+  mkdir [-p] <path>    - créer un répertoire (-p: avec les parents)
+  rm <path>            - supprimer un fichier
+  rmdir <path>         - supprimer un répertoire vide
+  parts                - lister les partitions de l'image
+  fsck [--repair]      - vérifier (et réparer) le volume
   pwd                  - afficher le répertoire courant
   help                 - cette aide
   exit                 - quitter"
@@ -48,19 +103,45 @@ fn print_shell_help() {
 }
 
 /// Point d’entrée de la CLI: parse les arguments,
-/// ouvre l’image en mémoire, puis exécute la commande
+/// ouvre le fichier image, puis exécute la commande
 /// demandée ou bascule en mode shell interactif.
 fn main() {
-    let mut args = env::args().skip(1);
+    let mut args = env::args().skip(1).peekable();
+
+    // `mkfs` crée l’image : pas de --file, ses propres options
+    if args.peek().map(String::as_str) == Some("mkfs") {
+        args.next();
+        run_mkfs(args);
+        return;
+    }
 
     let mut dump_path: Option<String> = None;
     let mut command: Option<String> = None;
     let mut target_a: Option<String> = None;
     let mut target_b: Option<String> = None;
+    let mut partition: Option<usize> = None;
+    let mut long = false;
+    let mut repair = false;
+    let mut attrib_flags: Vec<String> = Vec::new();
+    let mut options = MountOptions::default();
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--file" | "-f" => dump_path = args.next(),
+            "--partition" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => partition = Some(n),
+                None => {
+                    eprintln!("--partition nécessite un numéro");
+                    print_usage();
+                    return;
+                }
+            },
+            "--parts" => command = Some("parts".to_string()),
+            "--fsck" | "fsck" => command = Some("fsck".to_string()),
+            "--repair" => repair = true,
+            "--ro" => options.read_only = true,
+            "--force" => options.force = true,
+            "-l" | "--long" => long = true,
             "--ls" => {
                 command = Some("ls".to_string());
                 target_a = args.next();
@@ -74,6 +155,21 @@ fn main() {
                 target_a = args.next();
                 target_b = args.next();
             }
+            "--mkdir" => {
+                command = Some("mkdir".to_string());
+                target_a = args.next();
+            }
+            "--stat" => {
+                command = Some("stat".to_string());
+                target_a = args.next();
+            }
+            "--attrib" => {
+                command = Some("attrib".to_string());
+                target_a = args.next();
+            }
+            flag if command.as_deref() == Some("attrib") && is_attrib_flag(flag) => {
+                attrib_flags.push(arg);
+            }
             _ => {
                 eprintln!("Argument inconnu : {arg}");
                 print_usage();
@@ -90,17 +186,28 @@ fn main() {
         }
     };
 
-    let mut data = match fs::read(&dump_path) {
-        Ok(v) => v,
+    // Lecture seule avec --ro, pour ls/cat/stat/parts, fsck sans --repair et attrib
+    // sans changement; lecture/écriture pour le reste
+    let writable = match command.as_deref() {
+        _ if options.read_only => false,
+        Some("ls") | Some("cat") | Some("stat") | Some("parts") => false,
+        Some("fsck") => repair,
+        Some("attrib") => !attrib_flags.is_empty(),
+        _ => true,
+    };
+    let mut file = match OpenOptions::new().read(true).write(writable).open(&dump_path) {
+        Ok(f) => f,
         Err(e) => {
-            eprintln!("Impossible de lire {dump_path}: {e}");
+            eprintln!("Impossible d'ouvrir {dump_path}: {e}");
             return;
         }
     };
 
     match command.as_deref() {
+        Some("parts") => run_parts(&file),
+        Some("fsck") => run_fsck(&mut file, partition, options, repair),
         Some("ls") => {
-            let ro = match Fat32::new(&data) {
+            let ro = match open_volume(&file, partition).and_then(Fat32::from_device) {
                 Ok(fs) => fs,
                 Err(e) => {
                     eprintln!("Erreur FAT32: {e:?}");
@@ -112,10 +219,10 @@ fn main() {
                 .as_deref()
                 .map(|p| resolve_path(cwd, p))
                 .unwrap_or_else(|| "/".to_string());
-            run_ls(&ro, &path);
+            run_ls(&ro, &path, long);
         }
         Some("cat") => {
-            let ro = match Fat32::new(&data) {
+            let mut ro = match open_volume(&file, partition).and_then(mount_read_only) {
                 Ok(fs) => fs,
                 Err(e) => {
                     eprintln!("Erreur FAT32: {e:?}");
@@ -132,7 +239,7 @@ fn main() {
                 }
             };
             let path = resolve_path(cwd, &rel);
-            run_cat(&ro, &path);
+            run_cat(&mut ro, &path);
         }
         Some("put") => {
             let fat_path = match target_a {
@@ -152,41 +259,57 @@ fn main() {
                 }
             };
 
-            let content = match fs::read(&src) {
-                Ok(v) => v,
+            let mut host = match File::open(&src) {
+                Ok(f) => f,
                 Err(e) => {
                     eprintln!("Impossible de lire {src}: {e}");
                     return;
                 }
             };
 
-            {
-                let mut rw = match Fat32Mut::new(&mut data) {
-                    Ok(fs) => fs,
-                    Err(e) => {
-                        eprintln!("Erreur FAT32: {e:?}");
-                        return;
-                    }
-                };
-
-                if let Err(e) = rw.write_file_by_path(&fat_path, &content) {
-                    eprintln!("Erreur put {fat_path}: {e:?}");
+            if persist_write(&mut file, partition, options, &format!("put {fat_path}"), |rw| {
+                copy_into(rw, &fat_path, &mut host)
+            }) {
+                println!("OK: {src} -> {fat_path} (image mise à jour)");
+            }
+        }
+        Some("mkdir") => {
+            let dir_path = match target_a {
+                Some(p) => resolve_path("/", &p),
+                None => {
+                    eprintln!("--mkdir nécessite un chemin");
+                    print_usage();
                     return;
                 }
-            }
+            };
 
-            if let Err(e) = fs::write(&dump_path, &data) {
-                eprintln!("Impossible d'écrire {dump_path}: {e}");
-                return;
+            if persist_write(&mut file, partition, options, &format!("mkdir {dir_path}"), |rw| {
+                rw.create_dir_all(&dir_path)
+            }) {
+                println!("OK: {dir_path} créé (image mise à jour)");
+            }
+        }
+        Some("stat") | Some("attrib") => {
+            let path = match target_a {
+                Some(p) => resolve_path("/", &p),
+                None => {
+                    eprintln!("--{} nécessite un chemin", command.as_deref().unwrap_or_default());
+                    print_usage();
+                    return;
+                }
+            };
+            if command.as_deref() == Some("stat") {
+                run_stat(&file, partition, &path);
+            } else {
+                let flags: Vec<&str> = attrib_flags.iter().map(String::as_str).collect();
+                run_attrib(&mut file, partition, options, &path, &flags);
             }
-
-            println!("OK: {src} -> {fat_path} (image mise à jour)");
         }
         Some(other) => {
             eprintln!("Commande inconnue : {other}");
             print_usage();
         }
-        None => run_shell(&dump_path, &mut data),
+        None => run_shell(&mut file, partition, options),
     }
 }
 
@@ -232,35 +355,435 @@ fn push_component(components: &mut Vec<String>, part: &str) {
     }
 }
 
+/// Ouvre le volume à explorer: la partition FAT `partition` de l’image,
+/// ou l’image entière si aucune partition n’est demandée.
+///
+/// Le volume passe par un cache de secteurs (taille par défaut): la FAT n’est pas
+/// relue dans le fichier à chaque maillon de chaîne.
+fn open_volume<D: BlockDevice>(
+    dev: D,
+    partition: Option<usize>,
+) -> Result<CachedDevice<PartitionDevice<D>>, FatError> {
+    let volume = match partition {
+        Some(n) => PartitionDevice::open_fat(dev, n)?,
+        None => {
+            let count = dev.sector_count();
+            PartitionDevice::new(dev, 0, count)?
+        }
+    };
+    Ok(CachedDevice::new(volume, CacheConfig::default()))
+}
+
+/// Affiche la table de partitions de l’image (numéro FAT utilisable avec `--partition`).
+fn run_parts(file: &File) {
+    let parts = match list_partitions(file) {
+        Ok(parts) => parts,
+        Err(e) => {
+            println!("Erreur parts: {e:?}");
+            return;
+        }
+    };
+    if parts.is_empty() {
+        println!("Aucune table de partitions: le volume commence au secteur 0.");
+        return;
+    }
+
+    let mut fat_index = 0;
+    for p in parts {
+        let fat = if p.is_fat() {
+            fat_index += 1;
+            format!("FAT {}", fat_index - 1)
+        } else {
+            "-".to_string()
+        };
+        let kind = match &p.kind {
+            PartitionKind::Mbr { type_id } => format!("MBR 0x{type_id:02X}"),
+            PartitionKind::Gpt { name, .. } => format!("GPT {name}"),
+        };
+        println!(
+            "#{:<2} {:>12} {:>12} secteurs  {:<6} {kind}",
+            p.index, p.start_lba, p.sector_count, fat
+        );
+    }
+}
+
+/// `mkfs --size <taille> [--label <nom>] <image>` : crée (ou écrase) l’image
+/// à la taille demandée, puis la formate en FAT32.
+fn run_mkfs(mut args: impl Iterator<Item = String>) {
+    let mut size = None;
+    let mut options = FormatOptions::default();
+    let mut out = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => match args.next().and_then(|s| parse_size(&s)) {
+                Some(n) => size = Some(n),
+                None => {
+                    eprintln!("--size nécessite une taille (ex: 64M, 512K, 1G)");
+                    print_usage();
+                    return;
+                }
+            },
+            "--label" => match args.next() {
+                Some(label) => options.label = label,
+                None => {
+                    eprintln!("--label nécessite un nom");
+                    print_usage();
+                    return;
+                }
+            },
+            _ if out.is_none() && !arg.starts_with('-') => out = Some(arg),
+            _ => {
+                eprintln!("Argument inconnu : {arg}");
+                print_usage();
+                return;
+            }
+        }
+    }
+
+    let (Some(size), Some(out)) = (size, out) else {
+        eprintln!("mkfs nécessite --size et un fichier image");
+        print_usage();
+        return;
+    };
+
+    let mut file = match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&out)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Impossible de créer {out}: {e}");
+            return;
+        }
+    };
+    if let Err(e) = file.set_len(size) {
+        eprintln!("Impossible de dimensionner {out}: {e}");
+        return;
+    }
+
+    // Numéro de série tiré de l’horloge, comme mkfs.fat
+    options.volume_id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0);
+
+    let formatted = format(&mut file, &options)
+        .and_then(|_| Fat32::from_device(&file)?.free_bytes());
+    match formatted {
+        Ok(free) => println!("OK: {out} formaté en FAT32 ({} Kio libres)", free / 1024),
+        Err(FatError::BufferTooSmall) => {
+            eprintln!("Erreur mkfs: {size} octets, c'est trop petit pour FAT32 (~33 Mio minimum)")
+        }
+        Err(e) => eprintln!("Erreur mkfs: {e:?}"),
+    }
+}
+
+/// Taille en octets : un nombre, éventuellement suivi de `K`, `M` ou `G` (puissances de 1024).
+fn parse_size(s: &str) -> Option<u64> {
+    let (digits, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, 'B'),
+    };
+    let shift = match unit {
+        'B' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+/// Vérifie le volume et affiche les problèmes trouvés.
+///
+/// Avec `repair`, le volume est monté en écriture et corrigé, puis revérifié :
+/// ce qui n’a pas pu être réparé est affiché à part.
+fn run_fsck(file: &mut File, partition: Option<usize>, options: MountOptions, repair: bool) {
+    if !repair {
+        let checked = open_volume(&*file, partition)
+            .and_then(Fat32::from_device)
+            .and_then(|fs| Ok((fs.is_dirty()?, fs.has_pending_journal(), fs.check()?)));
+        let (dirty, pending, report) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                eprintln!("Erreur fsck: {e:?}");
+                return;
+            }
+        };
+        if dirty {
+            println!("Volume non démonté proprement (bit de FAT[1]).");
+        }
+        if pending {
+            println!("Transaction validée en attente dans /JOURNAL.SYS (rejouée au prochain montage en écriture).");
+        }
+        print_report(&report);
+        if !report.is_clean() {
+            println!("Relancer avec --repair pour corriger l'image.");
+        }
+        return;
+    }
+
+    persist_write(file, partition, options, "fsck --repair", |rw| {
+        let report = rw.repair()?;
+        print_report(&report);
+        if !report.is_clean() {
+            println!("{} problème(s) corrigé(s) (image mise à jour)", report.problems.len());
+        }
+
+        let left = rw.as_read().check()?;
+        if !left.is_clean() {
+            println!("Non réparé:");
+            for problem in &left.problems {
+                println!("  {problem}");
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Affiche un rapport de vérification: un problème par ligne, puis un résumé.
+fn print_report(report: &CheckReport) {
+    for problem in &report.problems {
+        println!("  {problem}");
+    }
+    println!(
+        "{} fichier(s), {} répertoire(s): {}",
+        report.files,
+        report.dirs,
+        if report.is_clean() {
+            "aucun problème".to_string()
+        } else {
+            format!("{} problème(s)", report.problems.len())
+        }
+    );
+}
+
 /// Liste un répertoire et affiche une vue simple
 /// (type + nom + taille) pour chaque entrée.
-fn run_ls(fs: &Fat32, path: &str) {
+///
+/// Avec `long`, affiche aussi la date de dernière modification (`-` si non renseignée).
+fn run_ls<D: BlockDevice>(fs: &Fat32<D>, path: &str, long: bool) {
     match fs.list_dir_path(path) {
         Ok(entries) => {
             println!("Listing de {path}:");
             for e in entries {
                 let kind = if e.is_dir() { "DIR " } else { "FILE" };
-                println!("{kind} {:<24} {:>8} bytes", e.name, e.size);
+                if long {
+                    let mtime = e
+                        .modified
+                        .map(|t| t.to_string())
+                        .unwrap_or_else(|| "-".to_string());
+                    println!("{kind} {:>10} {mtime:<19} {}", e.size, e.display_name());
+                } else {
+                    println!("{kind} {:<24} {:>8} bytes", e.display_name(), e.size);
+                }
             }
         }
         Err(e) => eprintln!("Erreur ls {path}: {e:?}"),
     }
 }
 
-/// Lit un fichier et écrit son contenu sur la sortie standard.
-fn run_cat(fs: &Fat32, path: &str) {
-    match fs.read_file_by_path(path) {
-        Ok(Some(bytes)) => {
-            print!("{}", String::from_utf8_lossy(&bytes));
+/// Affiche les métadonnées de `path`: taille, place occupée, attributs et dates.
+fn run_stat(file: &File, partition: Option<usize>, path: &str) {
+    let meta = match open_volume(file, partition)
+        .and_then(Fat32::from_device)
+        .and_then(|fs| fs.metadata(path))
+    {
+        Ok(meta) => meta,
+        Err(FatError::PathNotFound) => {
+            eprintln!("Entrée introuvable : {path}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Erreur stat {path}: {e:?}");
+            return;
+        }
+    };
+    let date = |t: Option<FatDateTime>| t.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string());
+
+    println!("Chemin     : {path}");
+    println!("Type       : {}", if meta.is_dir() { "répertoire" } else { "fichier" });
+    println!("Taille     : {} octets", meta.size);
+    println!(
+        "Occupé     : {} octets ({} cluster(s), premier: {})",
+        meta.allocated_size, meta.clusters, meta.first_cluster
+    );
+    println!("Attributs  : {}", attr_letters(&meta.attrs));
+    println!("Création   : {}", date(meta.created));
+    println!("Modifié    : {}", date(meta.modified));
+    println!("Accès      : {}", date(meta.accessed).split(' ').next().unwrap_or("-"));
+}
+
+/// Affiche les attributs de `path`, ou les change si des `flags` (`+r`, `-h`...) sont donnés.
+fn run_attrib(
+    file: &mut File,
+    partition: Option<usize>,
+    options: MountOptions,
+    path: &str,
+    flags: &[&str],
+) {
+    if flags.is_empty() {
+        match open_volume(&*file, partition)
+            .and_then(Fat32::from_device)
+            .and_then(|fs| fs.metadata(path))
+        {
+            Ok(meta) => println!("{} {path}", attr_letters(&meta.attrs)),
+            Err(e) => eprintln!("Erreur attrib {path}: {e:?}"),
+        }
+        return;
+    }
+
+    let mut updated = None;
+    if persist_write(file, partition, options, &format!("attrib {path}"), |rw| {
+        let attrs = apply_attrib_flags(rw.as_read().metadata(path)?.attrs, flags);
+        rw.set_attributes(path, attrs)?;
+        updated = Some(attrs);
+        Ok(())
+    }) {
+        if let Some(attrs) = updated {
+            println!("OK: {} {path} (image mise à jour)", attr_letters(&attrs));
+        }
+    }
+}
+
+/// `true` si `flag` est une option d’`attrib`: `+` ou `-` suivi de `r`, `h`, `s` ou `a`.
+fn is_attrib_flag(flag: &str) -> bool {
+    let mut chars = flag.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some('+' | '-'), Some('r' | 'h' | 's' | 'a' | 'R' | 'H' | 'S' | 'A'), None)
+    )
+}
+
+/// Applique des options `attrib` déjà validées par [`is_attrib_flag`] (les autres sont ignorées).
+fn apply_attrib_flags(mut attrs: Attributes, flags: &[&str]) -> Attributes {
+    for flag in flags {
+        let set = flag.starts_with('+');
+        match flag[1..].to_ascii_lowercase().as_str() {
+            "r" => attrs.read_only = set,
+            "h" => attrs.hidden = set,
+            "s" => attrs.system = set,
+            "a" => attrs.archive = set,
+            _ => {}
+        }
+    }
+    attrs
+}
+
+/// Attributs sous forme de lettres, façon `attrib` de DOS: `RHSAD`, `-` si absent.
+fn attr_letters(attrs: &Attributes) -> String {
+    [
+        (attrs.read_only, 'R'),
+        (attrs.hidden, 'H'),
+        (attrs.system, 'S'),
+        (attrs.archive, 'A'),
+        (attrs.directory, 'D'),
+    ]
+    .iter()
+    .map(|&(set, c)| if set { c } else { '-' })
+    .collect()
+}
+
+/// Monte un volume en lecture seule, pour les commandes qui n’écrivent jamais (`cat`).
+fn mount_read_only<D: BlockDevice>(dev: D) -> Result<Fat32Mut<D>, FatError> {
+    let options = MountOptions {
+        read_only: true,
+        ..MountOptions::default()
+    };
+    Fat32Mut::from_device_with(dev, options)
+}
+
+/// Lit un fichier par morceaux et écrit son contenu sur la sortie standard.
+///
+/// L’image peut être ouverte en lecture seule: `cat` n’écrit jamais.
+fn run_cat<D: BlockDevice>(fs: &mut Fat32Mut<D>, path: &str) {
+    let mut file = match fs.open(path) {
+        Ok(f) => f,
+        Err(FatError::PathNotFound) => {
+            eprintln!("Fichier introuvable : {path}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Erreur cat {path}: {e:?}");
+            return;
+        }
+    };
+
+    let mut out = io::stdout().lock();
+    if let Err(e) = io::copy(&mut file, &mut out) {
+        eprintln!("Erreur cat {path}: {e}");
+    }
+}
+
+/// Copie un fichier hôte dans l’image (création ou overwrite) sans le charger en entier.
+///
+/// La copie va dans une chaîne neuve, reliée au fichier seulement à la fin :
+/// interrompue, elle laisse l’ancien fichier intact.
+fn copy_into<D: BlockDevice>(
+    rw: &mut Fat32Mut<D>,
+    fat_path: &str,
+    host: &mut File,
+) -> Result<(), FatError> {
+    rw.replace_file(fat_path, |dst| {
+        io::copy(host, dst).map(|_| ()).map_err(|e| {
+            eprintln!("Copie interrompue: {e}");
+            FatError::Io
+        })
+    })
+}
+
+/// Monte l’image (ou sa partition `partition`) en écriture, applique `op`,
+/// puis démonte le volume (bit “démonté proprement” remis, fichier synchronisé).
+///
+/// Les erreurs sont affichées avec `label` comme contexte.
+/// Retourne `true` si l’opération a réussi et que l’image a été synchronisée.
+fn persist_write<F>(
+    file: &mut File,
+    partition: Option<usize>,
+    options: MountOptions,
+    label: &str,
+    op: F,
+) -> bool
+where
+    F: FnOnce(&mut Fat32Mut<CachedDevice<PartitionDevice<&mut File>>>) -> Result<(), FatError>,
+{
+    if options.read_only {
+        eprintln!("Erreur {label}: image ouverte en lecture seule (--ro)");
+        return false;
+    }
+    let opened = open_volume(file, partition).and_then(|v| Fat32Mut::from_device_with(v, options));
+    let mut rw = match opened {
+        Ok(fs) => fs,
+        Err(e) => {
+            eprintln!("Erreur FAT32: {e:?}");
+            return false;
         }
-        Ok(None) => eprintln!("Fichier introuvable : {path}"),
-        Err(e) => eprintln!("Erreur cat {path}: {e:?}"),
+    };
+    rw.set_time_source(SystemClock);
+
+    if let Err(e) = op(&mut rw) {
+        eprintln!("Erreur {label}: {e:?}");
+        return false;
+    }
+
+    if let Err(e) = rw.unmount() {
+        eprintln!("Erreur de synchronisation de l'image: {e:?}");
+        return false;
     }
+
+    true
 }
 
 /// Lance un petit shell interactif pour manipuler l’image:
-/// navigation (`cd`, `pwd`), listage (`ls`), lecture (`cat`) et écriture (`put`).
-fn run_shell(img_path: &str, data: &mut Vec<u8>) {
+/// navigation (`cd`, `pwd`), listage (`ls`), lecture (`cat`),
+/// écriture (`put`, `mkdir`) et suppression (`rm`, `rmdir`).
+///
+/// Toutes les commandes travaillent sur la partition `partition` si elle est donnée.
+fn run_shell(file: &mut File, partition: Option<usize>, options: MountOptions) {
     println!("FAT32 shell. Tapez 'help' pour l'aide, 'exit' pour quitter.");
 
     let stdin = io::stdin();
@@ -293,8 +816,10 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
             "exit" | "quit" => break,
             "help" => print_shell_help(),
             "pwd" => println!("{current_dir}"),
+            "parts" => run_parts(file),
+            "fsck" => run_fsck(file, partition, options, parts.next() == Some("--repair")),
             "ls" => {
-                let ro = match Fat32::new(data) {
+                let ro = match open_volume(&*file, partition).and_then(Fat32::from_device) {
                     Ok(fs) => fs,
                     Err(e) => {
                         println!("Erreur FAT32: {e:?}");
@@ -302,15 +827,24 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
                     }
                 };
 
-                let path = if let Some(p) = parts.next() {
-                    resolve_path(&current_dir, p)
-                } else {
-                    current_dir.clone()
+                let mut long = false;
+                let mut target = None;
+                for arg in parts.by_ref() {
+                    if arg == "-l" {
+                        long = true;
+                    } else {
+                        target = Some(arg);
+                    }
+                }
+
+                let path = match target {
+                    Some(p) => resolve_path(&current_dir, p),
+                    None => current_dir.clone(),
                 };
-                run_ls(&ro, &path);
+                run_ls(&ro, &path, long);
             }
             "cat" => {
-                let ro = match Fat32::new(data) {
+                let mut ro = match open_volume(&*file, partition).and_then(mount_read_only) {
                     Ok(fs) => fs,
                     Err(e) => {
                         println!("Erreur FAT32: {e:?}");
@@ -320,13 +854,13 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
 
                 if let Some(p) = parts.next() {
                     let path = resolve_path(&current_dir, p);
-                    run_cat(&ro, &path);
+                    run_cat(&mut ro, &path);
                 } else {
                     println!("Usage: cat <path>");
                 }
             }
             "cd" => {
-                let ro = match Fat32::new(data) {
+                let ro = match open_volume(&*file, partition).and_then(Fat32::from_device) {
                     Ok(fs) => fs,
                     Err(e) => {
                         println!("Erreur FAT32: {e:?}");
@@ -347,6 +881,21 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
                     Err(e) => println!("Erreur cd vers {target}: {e:?}"),
                 }
             }
+            "stat" => match parts.next() {
+                Some(p) => run_stat(file, partition, &resolve_path(&current_dir, p)),
+                None => println!("Usage: stat <path>"),
+            },
+            "attrib" => match parts.next() {
+                Some(p) => {
+                    let flags: Vec<&str> = parts.by_ref().collect();
+                    if let Some(bad) = flags.iter().find(|f| !is_attrib_flag(f)) {
+                        println!("Option d'attribut inconnue : {bad} (+r, -r, +h, -h, +s, -s, +a, -a)");
+                        continue;
+                    }
+                    run_attrib(file, partition, options, &resolve_path(&current_dir, p), &flags);
+                }
+                None => println!("Usage: attrib <path> [+r|-r|+h|-h|+s|-s|+a|-a]..."),
+            },
             "put" => {
                 let fat_path = match parts.next() {
                     Some(p) => resolve_path(&current_dir, p),
@@ -363,35 +912,67 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
                     }
                 };
 
-                let content = match fs::read(&src) {
-                    Ok(v) => v,
+                let mut host = match File::open(&src) {
+                    Ok(f) => f,
                     Err(e) => {
                         println!("Impossible de lire {src}: {e}");
                         continue;
                     }
                 };
 
-                {
-                    let mut rw = match Fat32Mut::new(data) {
-                        Ok(fs) => fs,
-                        Err(e) => {
-                            println!("Erreur FAT32: {e:?}");
-                            continue;
-                        }
-                    };
-
-                    if let Err(e) = rw.write_file_by_path(&fat_path, &content) {
-                        println!("Erreur put {fat_path}: {e:?}");
-                        continue;
+                if persist_write(file, partition, options, &format!("put {fat_path}"), |rw| {
+                    copy_into(rw, &fat_path, &mut host)
+                }) {
+                    println!("OK: {src} -> {fat_path} (image mise à jour)");
+                }
+            }
+            "mkdir" => {
+                let mut parents = false;
+                let mut target = None;
+                for arg in parts.by_ref() {
+                    if arg == "-p" {
+                        parents = true;
+                    } else {
+                        target = Some(arg);
                     }
                 }
 
-                if let Err(e) = fs::write(img_path, &*data) {
-                    println!("Impossible d'écrire {img_path}: {e}");
-                    continue;
+                let dir_path = match target {
+                    Some(p) => resolve_path(&current_dir, p),
+                    None => {
+                        println!("Usage: mkdir [-p] <path>");
+                        continue;
+                    }
+                };
+
+                if persist_write(file, partition, options, &format!("mkdir {dir_path}"), |rw| {
+                    if parents {
+                        rw.create_dir_all(&dir_path)
+                    } else {
+                        rw.create_dir(&dir_path)
+                    }
+                }) {
+                    println!("OK: {dir_path} créé (image mise à jour)");
                 }
+            }
+            "rm" | "rmdir" => {
+                let target = match parts.next() {
+                    Some(p) => resolve_path(&current_dir, p),
+                    None => {
+                        println!("Usage: {cmd} <path>");
+                        continue;
+                    }
+                };
 
-                println!("OK: {src} -> {fat_path} (image mise à jour)");
+                if persist_write(file, partition, options, &format!("{cmd} {target}"), |rw| {
+                    if cmd == "rm" {
+                        rw.remove_file(&target)
+                    } else {
+                        rw.remove_dir(&target)
+                    }
+                }) {
+                    println!("OK: {target} supprimé (image mise à jour)");
+                }
             }
             _ => println!("Commande inconnue: {cmd}. Tapez 'help'."),
         }
@@ -400,7 +981,28 @@ fn run_shell(img_path: &str, data: &mut Vec<u8>) {
 
 #[cfg(test)]
 mod cli_path_tests {
-    use super::resolve_path;
+    use super::{apply_attrib_flags, attr_letters, is_attrib_flag, parse_size, resolve_path};
+    use fat32_parser::Attributes;
+
+    #[test]
+    fn options_attrib() {
+        assert!(is_attrib_flag("+r") && is_attrib_flag("-H"));
+        assert!(!is_attrib_flag("-l") && !is_attrib_flag("+rh") && !is_attrib_flag("r"));
+
+        let attrs = Attributes::from_byte(0x20);
+        let attrs = apply_attrib_flags(attrs, &["+r", "+h", "-a"]);
+        assert_eq!(attrs.to_byte(), 0x03);
+        assert_eq!(attr_letters(&attrs), "RH---");
+    }
+
+    #[test]
+    fn taille_avec_suffixe() {
+        assert_eq!(parse_size("64M"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_size("512k"), Some(512 * 1024));
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("12X"), None);
+        assert_eq!(parse_size("M"), None);
+    }
 
     #[test]
     fn chemin_parent_depuis_dir() {
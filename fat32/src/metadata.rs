@@ -0,0 +1,166 @@
+//! Métadonnées d’une entrée (`stat`) et modification de ses attributs (`attrib`).
+//!
+//! [`Fat32::metadata`] rassemble ce que l’entrée de répertoire sait (attributs, taille,
+//! dates) et ce que la FAT en dit (nombre de clusters, donc place réellement occupée).
+//! [`Fat32Mut::set_attributes`] réécrit l’octet d’attributs (offset 11) sur place.
+
+use crate::{Attributes, BlockDevice, Fat32, Fat32Mut, FatDateTime, FatError};
+
+/// Bits d’attributs modifiables : lecture seule, caché, système, archive.
+///
+/// Les bits “répertoire” et “volume” décrivent la nature de l’entrée : ils ne changent pas.
+const EDITABLE_ATTRS: u8 = 0x01 | 0x02 | 0x04 | 0x20;
+
+/// Métadonnées d’un fichier ou d’un répertoire, renvoyées par [`Fat32::metadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Attributs FAT.
+    pub attrs: Attributes,
+    /// Taille en octets (0 pour un répertoire).
+    pub size: u32,
+    /// Place occupée sur le volume, en octets (`clusters * taille de cluster`,
+    /// ou la zone fixe pour la racine FAT12/16).
+    pub allocated_size: u64,
+    /// Nombre de clusters de la chaîne.
+    pub clusters: u32,
+    /// Premier cluster de la chaîne (0 si vide).
+    pub first_cluster: u32,
+    /// Date de création (`None` si non renseignée, et pour la racine).
+    pub created: Option<FatDateTime>,
+    /// Date de dernière modification.
+    pub modified: Option<FatDateTime>,
+    /// Date de dernier accès (le jour seulement).
+    pub accessed: Option<FatDateTime>,
+}
+
+impl Metadata {
+    /// Indique si l’entrée est un répertoire.
+    pub fn is_dir(&self) -> bool {
+        self.attrs.directory
+    }
+
+    /// Indique si l’entrée est un fichier.
+    pub fn is_file(&self) -> bool {
+        !self.attrs.directory
+    }
+}
+
+impl<D: BlockDevice> Fat32<D> {
+    /// Métadonnées de l’entrée désignée par `path` (`"/"` compris).
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu.
+    ///
+    /// Retour : `Result<Metadata, FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu.
+    /// - `PathNotFound` si l’entrée n’existe pas.
+    /// - `CorruptChain` si sa chaîne de clusters est incohérente.
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError};
+    /// # let disk = vec![0u8; 10 * 512];
+    /// let fs = Fat32::new(&disk)?;
+    /// let meta = fs.metadata("/HELLO.TXT")?;
+    /// assert!(meta.allocated_size >= meta.size as u64);
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn metadata(&self, path: &str) -> Result<Metadata, FatError> {
+        if !path.starts_with('/') {
+            return Err(FatError::Other);
+        }
+
+        // La racine n’a pas d’entrée : répertoire sans dates
+        if path.split('/').all(str::is_empty) {
+            let root = Attributes {
+                directory: true,
+                ..Attributes::default()
+            };
+            if self.bpb.is_fixed_root(0) {
+                return Ok(Metadata {
+                    attrs: root,
+                    size: 0,
+                    allocated_size: self.bpb.root_dir_len() as u64,
+                    clusters: 0,
+                    first_cluster: 0,
+                    created: None,
+                    modified: None,
+                    accessed: None,
+                });
+            }
+            let clusters = self.follow_chain(self.bpb.root_cluster)?.len() as u32;
+            return Ok(Metadata {
+                attrs: root,
+                size: 0,
+                allocated_size: clusters as u64 * self.cluster_size() as u64,
+                clusters,
+                first_cluster: self.bpb.root_cluster,
+                created: None,
+                modified: None,
+                accessed: None,
+            });
+        }
+
+        let entry = self.open_path(path)?.ok_or(FatError::PathNotFound)?;
+        let clusters = if entry.first_cluster >= 2 {
+            self.follow_chain(entry.first_cluster)?.len() as u32
+        } else {
+            0
+        };
+        Ok(Metadata {
+            attrs: entry.attrs,
+            size: entry.size,
+            allocated_size: clusters as u64 * self.cluster_size() as u64,
+            clusters,
+            first_cluster: entry.first_cluster,
+            created: entry.created,
+            modified: entry.modified,
+            accessed: entry.accessed,
+        })
+    }
+}
+
+impl<D: BlockDevice> Fat32Mut<D> {
+    /// Change les attributs lecture seule, caché, système et archive de `path`.
+    ///
+    /// Les champs `directory` et `volume_id` de `attrs` sont ignorés : la nature
+    /// de l’entrée est conservée. Les dates ne changent pas (comme `attrib` sous DOS).
+    ///
+    /// Paramètres :
+    /// - `path` : chemin absolu d’un fichier ou d’un répertoire (pas la racine).
+    /// - `attrs` : nouveaux attributs.
+    ///
+    /// Retour : `Result<(), FatError>`.
+    ///
+    /// Erreurs :
+    /// - `Other` si `path` n’est pas absolu ou vaut `"/"`.
+    /// - `PathNotFound` si l’entrée n’existe pas.
+    /// - `ReadOnly` si le volume est monté en lecture seule (l’attribut lecture seule
+    ///   de l’entrée, lui, n’empêche pas de le retirer).
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32Mut, FatError};
+    /// # let mut disk = vec![0u8; 10 * 512];
+    /// let mut rw = Fat32Mut::new(&mut disk)?;
+    /// let mut attrs = rw.as_read().metadata("/HELLO.TXT")?.attrs;
+    /// attrs.read_only = true;
+    /// rw.set_attributes("/HELLO.TXT", attrs)?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn set_attributes(&mut self, path: &str, attrs: Attributes) -> Result<(), FatError> {
+        let (_, located) = self.locate(path)?;
+        let old = located.raw[11];
+        let byte = (old & !EDITABLE_ATTRS) | (attrs.to_byte() & EDITABLE_ATTRS);
+        if byte == old {
+            return Ok(());
+        }
+        self.write_bytes(located.offset + 11, &[byte])
+    }
+}
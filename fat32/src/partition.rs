@@ -0,0 +1,425 @@
+//! Tables de partitions (MBR et GPT) d’une image disque complète.
+//!
+//! Une image produite par `qemu-img` ou un vrai disque commence par une table de
+//! partitions, pas par le BPB : le volume FAT se trouve plus loin, dans une partition.
+//! Ce module liste les partitions et expose l’une d’elles comme un [`BlockDevice`]
+//! ([`PartitionDevice`]) dont le secteur 0 est le premier secteur de la partition.
+//!
+//! Formats gérés :
+//! - MBR : 4 entrées primaires, et partitions logiques d’une partition étendue (chaîne d’EBR),
+//! - GPT : en-tête primaire (LBA 1) avec CRC32 de l’en-tête et du tableau d’entrées;
+//!   si le primaire est abîmé, on essaie l’en-tête de secours (dernier secteur).
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::device::{check_range, read_bytes};
+use crate::{parse_bpb, BlockDevice, FatError, SECTOR_SIZE};
+
+/// Type MBR d’une partition protectrice GPT.
+const MBR_GPT_PROTECTIVE: u8 = 0xEE;
+
+/// Types MBR d’une partition étendue (CHS, LBA, Linux).
+const MBR_EXTENDED: [u8; 3] = [0x05, 0x0F, 0x85];
+
+/// Types MBR d’un volume FAT (y compris les variantes cachées `0x1X`).
+const MBR_FAT: [u8; 12] = [
+    0x01, 0x04, 0x06, 0x0B, 0x0C, 0x0E, 0x11, 0x14, 0x16, 0x1B, 0x1C, 0x1E,
+];
+
+/// Nombre maximal d’EBR suivis dans une partition étendue (protège d’une chaîne en boucle).
+const MAX_LOGICAL: usize = 128;
+
+/// Signature d’un en-tête GPT.
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+
+/// GUID “Microsoft Basic Data” (EBD0A0A2-B9E5-4433-87C0-68B6B72699C7), tel que stocké sur disque.
+const GUID_BASIC_DATA: [u8; 16] = [
+    0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+];
+
+/// GUID “EFI System Partition” (C12A7328-F81F-11D2-BA4B-00A0C93EC93B), tel que stocké sur disque.
+const GUID_EFI_SYSTEM: [u8; 16] = [
+    0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B,
+];
+
+/// Type d’une partition, selon la table qui la décrit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionKind {
+    /// Entrée MBR (primaire ou logique) avec son octet de type.
+    Mbr {
+        /// Octet de type (`0x0C` = FAT32 LBA, `0x06` = FAT16...).
+        type_id: u8,
+    },
+    /// Entrée GPT.
+    Gpt {
+        /// GUID de type, dans l’ordre des octets du disque.
+        type_guid: [u8; 16],
+        /// Nom de la partition (UTF-16 décodé).
+        name: String,
+    },
+}
+
+/// Partition trouvée dans la table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// Position dans la liste retournée par [`list_partitions`].
+    pub index: usize,
+    /// Premier secteur de la partition sur le disque.
+    pub start_lba: u64,
+    /// Nombre de secteurs de la partition.
+    pub sector_count: u64,
+    /// Type de la partition.
+    pub kind: PartitionKind,
+}
+
+impl Partition {
+    /// `true` si le type de la partition annonce un volume FAT
+    /// (types MBR FAT12/16/32, ou GPT “Basic Data” / “EFI System”).
+    pub fn is_fat(&self) -> bool {
+        match &self.kind {
+            PartitionKind::Mbr { type_id } => MBR_FAT.contains(type_id),
+            PartitionKind::Gpt { type_guid, .. } => {
+                *type_guid == GUID_BASIC_DATA || *type_guid == GUID_EFI_SYSTEM
+            }
+        }
+    }
+}
+
+/// Liste les partitions d’une image disque.
+///
+/// Un disque sans table (le secteur 0 est directement un BPB valide) n’a aucune partition :
+/// la liste est vide et le volume s’ouvre tel quel.
+///
+/// Paramètres :
+/// - `dev` : disque complet.
+///
+/// Retour : partitions dans l’ordre de la table (MBR : primaires puis logiques).
+///
+/// Erreurs :
+/// - `InvalidPartitionTable` si le secteur 0 n’est ni un BPB ni un MBR cohérent,
+///   ou si les deux en-têtes GPT sont invalides (signature, CRC32, bornes).
+/// - `Io` si la lecture du périphérique échoue.
+///
+/// Panics : aucune.
+///
+/// Exemples :
+/// ```rust,no_run
+/// # use fat32_parser::{list_partitions, FatError};
+/// let disk = std::fs::File::open("disk.img").map_err(|_| FatError::Io)?;
+/// for p in list_partitions(&disk)? {
+///     println!("{} : {} secteurs, FAT = {}", p.index, p.sector_count, p.is_fat());
+/// }
+/// # Ok::<(), FatError>(())
+/// ```
+pub fn list_partitions<D: BlockDevice + ?Sized>(dev: &D) -> Result<Vec<Partition>, FatError> {
+    let mut mbr = [0u8; SECTOR_SIZE];
+    dev.read_sector(0, &mut mbr)?;
+
+    let disk_len = dev.sector_count() as usize * SECTOR_SIZE;
+    if parse_bpb(&mbr, disk_len).is_ok() {
+        return Ok(Vec::new());
+    }
+
+    let primaries = parse_mbr_entries(&mbr, dev.sector_count())?;
+    if primaries
+        .iter()
+        .any(|&(type_id, _, _)| type_id == MBR_GPT_PROTECTIVE)
+    {
+        return read_gpt(dev);
+    }
+
+    let mut found = Vec::new();
+    for &(type_id, start, count) in &primaries {
+        if MBR_EXTENDED.contains(&type_id) {
+            read_logical(dev, start, count, &mut found)?;
+        } else {
+            found.push((PartitionKind::Mbr { type_id }, start, count));
+        }
+    }
+
+    Ok(found
+        .into_iter()
+        .enumerate()
+        .map(|(index, (kind, start_lba, sector_count))| Partition {
+            index,
+            start_lba,
+            sector_count,
+            kind,
+        })
+        .collect())
+}
+
+/// Lit les 4 entrées d’un MBR ou d’un EBR : `(type, début, nombre de secteurs)`.
+///
+/// Les entrées vides (type 0) sont omises; les débuts sont relatifs au secteur lu.
+fn parse_mbr_entries(sector: &[u8], disk_sectors: u64) -> Result<Vec<(u8, u64, u64)>, FatError> {
+    if sector[510] != 0x55 || sector[511] != 0xAA {
+        return Err(FatError::InvalidPartitionTable);
+    }
+
+    let mut entries = Vec::new();
+    for e in sector[446..510].chunks(16) {
+        // Octet de statut : 0x00 (inactive) ou 0x80 (amorçable), rien d’autre
+        if e[0] != 0x00 && e[0] != 0x80 {
+            return Err(FatError::InvalidPartitionTable);
+        }
+        let type_id = e[4];
+        if type_id == 0 {
+            continue;
+        }
+        let start = u32::from_le_bytes([e[8], e[9], e[10], e[11]]) as u64;
+        let count = u32::from_le_bytes([e[12], e[13], e[14], e[15]]) as u64;
+        // Une partition protectrice GPT peut annoncer 0xFFFFFFFF secteurs
+        if type_id != MBR_GPT_PROTECTIVE && (start == 0 || count == 0 || count > disk_sectors) {
+            return Err(FatError::InvalidPartitionTable);
+        }
+        entries.push((type_id, start, count));
+    }
+    Ok(entries)
+}
+
+/// Suit la chaîne d’EBR d’une partition étendue et ajoute ses partitions logiques.
+///
+/// Dans chaque EBR, la 1re entrée (relative à l’EBR) décrit la partition logique,
+/// la 2e (relative au début de l’étendue) pointe sur l’EBR suivant.
+fn read_logical<D: BlockDevice + ?Sized>(
+    dev: &D,
+    ext_start: u64,
+    ext_count: u64,
+    found: &mut Vec<(PartitionKind, u64, u64)>,
+) -> Result<(), FatError> {
+    let mut ebr_lba = ext_start;
+    let mut sector = [0u8; SECTOR_SIZE];
+
+    for _ in 0..MAX_LOGICAL {
+        dev.read_sector(ebr_lba, &mut sector)?;
+        let entries = parse_mbr_entries(&sector, ext_count)?;
+
+        let mut next = None;
+        for &(type_id, start, count) in &entries {
+            if MBR_EXTENDED.contains(&type_id) {
+                next = Some(ext_start + start);
+            } else {
+                let abs = ebr_lba + start;
+                if abs + count > ext_start + ext_count {
+                    return Err(FatError::InvalidPartitionTable);
+                }
+                found.push((PartitionKind::Mbr { type_id }, abs, count));
+            }
+        }
+
+        match next {
+            // L’EBR suivant doit avancer dans l’étendue, sinon la chaîne boucle
+            Some(lba) if lba > ebr_lba && lba < ext_start + ext_count => ebr_lba = lba,
+            Some(_) => return Err(FatError::InvalidPartitionTable),
+            None => return Ok(()),
+        }
+    }
+
+    Err(FatError::InvalidPartitionTable)
+}
+
+/// Lit la table GPT : en-tête primaire (LBA 1), sinon en-tête de secours (dernier LBA).
+fn read_gpt<D: BlockDevice + ?Sized>(dev: &D) -> Result<Vec<Partition>, FatError> {
+    let last = dev.sector_count().saturating_sub(1);
+    read_gpt_at(dev, 1).or_else(|_| read_gpt_at(dev, last))
+}
+
+/// Lit et vérifie un en-tête GPT situé au secteur `lba`, puis son tableau d’entrées.
+fn read_gpt_at<D: BlockDevice + ?Sized>(dev: &D, lba: u64) -> Result<Vec<Partition>, FatError> {
+    let mut header = [0u8; SECTOR_SIZE];
+    dev.read_sector(lba, &mut header)?;
+
+    let u32_at = |b: &[u8], at: usize| u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]]);
+    let u64_at = |b: &[u8], at: usize| u32_at(b, at) as u64 | (u32_at(b, at + 4) as u64) << 32;
+
+    if &header[0..8] != GPT_SIGNATURE {
+        return Err(FatError::InvalidPartitionTable);
+    }
+    let header_size = u32_at(&header, 12) as usize;
+    if !(92..=SECTOR_SIZE).contains(&header_size) || u64_at(&header, 24) != lba {
+        return Err(FatError::InvalidPartitionTable);
+    }
+
+    // Le CRC de l’en-tête se calcule avec son propre champ à zéro
+    let expected = u32_at(&header, 16);
+    let mut zeroed = header;
+    zeroed[16..20].fill(0);
+    if crc32(&zeroed[..header_size]) != expected {
+        return Err(FatError::InvalidPartitionTable);
+    }
+
+    let first_usable = u64_at(&header, 40);
+    let last_usable = u64_at(&header, 48);
+    let entries_lba = u64_at(&header, 72);
+    let entry_count = u32_at(&header, 80) as usize;
+    let entry_size = u32_at(&header, 84) as usize;
+    if !(128..=SECTOR_SIZE).contains(&entry_size)
+        || !entry_size.is_power_of_two()
+        || entry_count > 1024
+    {
+        return Err(FatError::InvalidPartitionTable);
+    }
+
+    // Le tableau (au plus 512 Kio) doit tenir sur le périphérique : un en-tête dont
+    // le CRC est bon peut quand même annoncer n’importe quoi
+    let table_len = entry_count
+        .checked_mul(entry_size)
+        .ok_or(FatError::InvalidPartitionTable)?;
+    let table_end = entries_lba.checked_add(table_len.div_ceil(SECTOR_SIZE) as u64);
+    if table_end.is_none_or(|end| end > dev.sector_count()) {
+        return Err(FatError::InvalidPartitionTable);
+    }
+    let table_offset = usize::try_from(entries_lba)
+        .ok()
+        .and_then(|lba| lba.checked_mul(SECTOR_SIZE))
+        .ok_or(FatError::InvalidPartitionTable)?;
+
+    let mut table = alloc::vec![0u8; table_len];
+    read_bytes(dev, table_offset, &mut table)?;
+    if crc32(&table) != u32_at(&header, 88) {
+        return Err(FatError::InvalidPartitionTable);
+    }
+
+    let mut partitions = Vec::new();
+    for e in table.chunks(entry_size) {
+        let mut type_guid = [0u8; 16];
+        type_guid.copy_from_slice(&e[0..16]);
+        if type_guid == [0u8; 16] {
+            continue;
+        }
+
+        let first = u64_at(e, 32);
+        let last = u64_at(e, 40);
+        if first < first_usable || last > last_usable || last < first {
+            return Err(FatError::InvalidPartitionTable);
+        }
+
+        let units: Vec<u16> = e[56..128]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&u| u != 0)
+            .collect();
+        let name = char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+
+        partitions.push(Partition {
+            index: partitions.len(),
+            start_lba: first,
+            sector_count: last - first + 1,
+            kind: PartitionKind::Gpt { type_guid, name },
+        });
+    }
+
+    Ok(partitions)
+}
+
+/// CRC32 (IEEE 802.3, polynôme réfléchi `0xEDB88320`), celui utilisé par GPT.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Sous-partie d’un périphérique vue comme un périphérique à part entière.
+///
+/// Le secteur 0 de la vue est le secteur `start` du périphérique sous-jacent;
+/// tout accès au-delà de la partition est refusé (`OutOfBounds`).
+#[derive(Debug)]
+pub struct PartitionDevice<D> {
+    inner: D,
+    start: u64,
+    count: u64,
+}
+
+impl<D: BlockDevice> PartitionDevice<D> {
+    /// Construit une vue sur `count` secteurs à partir du secteur `start` de `inner`.
+    ///
+    /// Erreurs :
+    /// - `OutOfBounds` si la plage dépasse le périphérique.
+    ///
+    /// Panics : aucune.
+    pub fn new(inner: D, start: u64, count: u64) -> Result<Self, FatError> {
+        match start.checked_add(count) {
+            Some(end) if end <= inner.sector_count() => Ok(Self { inner, start, count }),
+            _ => Err(FatError::OutOfBounds),
+        }
+    }
+
+    /// Ouvre la `n`-ième partition FAT (à partir de 0) de `inner`.
+    ///
+    /// Seules les partitions dont le type annonce un volume FAT sont comptées
+    /// ([`Partition::is_fat`]).
+    ///
+    /// Paramètres :
+    /// - `inner` : disque complet.
+    /// - `n` : rang de la partition parmi les partitions FAT.
+    ///
+    /// Retour : une vue prête pour `Fat32::from_device` / `Fat32Mut::from_device`.
+    ///
+    /// Erreurs :
+    /// - `PartitionNotFound` s’il y a moins de `n + 1` partitions FAT.
+    /// - mêmes erreurs que [`list_partitions`].
+    ///
+    /// Panics : aucune.
+    ///
+    /// Exemples :
+    /// ```rust,no_run
+    /// # use fat32_parser::{Fat32, FatError, PartitionDevice};
+    /// let disk = std::fs::File::open("disk.img").map_err(|_| FatError::Io)?;
+    /// let fs = Fat32::from_device(PartitionDevice::open_fat(&disk, 0)?)?;
+    /// let entries = fs.list_root()?;
+    /// # Ok::<(), FatError>(())
+    /// ```
+    pub fn open_fat(inner: D, n: usize) -> Result<Self, FatError> {
+        let partition = list_partitions(&inner)?
+            .into_iter()
+            .filter(Partition::is_fat)
+            .nth(n)
+            .ok_or(FatError::PartitionNotFound)?;
+        Self::new(inner, partition.start_lba, partition.sector_count)
+    }
+
+    /// Premier secteur de la vue sur le périphérique sous-jacent.
+    pub fn start_lba(&self) -> u64 {
+        self.start
+    }
+
+    /// Rend le périphérique sous-jacent.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: BlockDevice> BlockDevice for PartitionDevice<D> {
+    fn sector_count(&self) -> u64 {
+        self.count
+    }
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.count)?;
+        self.inner.read_sectors(self.start + start, buf)
+    }
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        check_range(start, buf.len(), self.count)?;
+        self.inner.write_sectors(self.start + start, buf)
+    }
+
+    fn write_back(&mut self) -> Result<(), FatError> {
+        self.inner.write_back()
+    }
+
+    fn flush(&mut self) -> Result<(), FatError> {
+        self.inner.flush()
+    }
+}
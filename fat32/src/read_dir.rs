@@ -0,0 +1,242 @@
+//! Parcours paresseux d’un répertoire.
+//!
+//! [`ReadDir`] lit le répertoire secteur par secteur dans un tampon interne
+//! (pas de `Vec` par cluster), suit la chaîne de clusters seulement quand il en a
+//! besoin et s’arrête au marqueur de fin `0x00`. Une recherche par nom
+//! s’arrête donc à la première entrée qui correspond, sans lire la suite,
+//! et compare les noms sans allouer.
+
+extern crate alloc;
+
+use alloc::vec::{self, Vec};
+
+use crate::dir_entry::{is_lfn_entry, short_name_matches, LfnCollector};
+use crate::{BlockDevice, DirEntry, Fat32, FatError, LocatedEntry, LoopGuard, SECTOR_SIZE};
+
+/// Zones (offset, longueur) du répertoire qui restent à lire.
+enum Zones {
+    /// Racine fixe FAT12/16 : une seule zone, prise au premier passage.
+    Fixed(Option<(usize, usize)>),
+    /// Chaîne de clusters, suivie au fur et à mesure.
+    Chain {
+        start: u32,
+        current: Option<u32>,
+        guard: LoopGuard,
+    },
+    /// Zones déjà connues (chaîne relevée par le vérificateur).
+    List(vec::IntoIter<(usize, usize)>),
+}
+
+/// Itérateur sur les entrées d’un répertoire, avec leur position dans l’image.
+///
+/// Obtenu via [`Fat32::read_dir`]. Chaque élément est un [`LocatedEntry`] :
+/// l’entrée décodée (nom long compris) et l’offset de son entrée courte.
+/// Les entrées `.` et `..` d’un sous-répertoire sont retournées comme les autres.
+///
+/// Après une erreur (chaîne corrompue, lecture refusée), l’itérateur s’arrête.
+pub struct ReadDir<'a, D> {
+    fs: &'a Fat32<D>,
+    zones: Zones,
+    /// Offset du prochain secteur à lire et octets restants dans la zone courante.
+    zone: (usize, usize),
+    sector: [u8; SECTOR_SIZE],
+    /// Offset du secteur chargé dans `sector`.
+    sector_base: usize,
+    /// Nombre d’entrées valides dans `sector`, et index de la prochaine.
+    slots: usize,
+    slot: usize,
+    lfn: LfnCollector,
+    done: bool,
+}
+
+impl<'a, D: BlockDevice> ReadDir<'a, D> {
+    /// Parcours du répertoire `dir_cluster` (`0` = la racine).
+    pub(crate) fn new(fs: &'a Fat32<D>, dir_cluster: u32) -> Self {
+        let zones = if fs.bpb.is_fixed_root(dir_cluster) {
+            Zones::Fixed(Some((fs.bpb.root_dir_start_byte(), fs.bpb.root_dir_len())))
+        } else {
+            let start = fs.bpb.dir_start_cluster(dir_cluster);
+            Zones::Chain {
+                start,
+                current: None,
+                guard: LoopGuard::new(start, fs.bpb.cluster_count()),
+            }
+        };
+        Self::with_zones(fs, zones)
+    }
+
+    /// Parcours des zones `regions` (zones d’un même répertoire, dans l’ordre).
+    pub(crate) fn over_regions(fs: &'a Fat32<D>, regions: Vec<(usize, usize)>) -> Self {
+        Self::with_zones(fs, Zones::List(regions.into_iter()))
+    }
+
+    fn with_zones(fs: &'a Fat32<D>, zones: Zones) -> Self {
+        Self {
+            fs,
+            zones,
+            zone: (0, 0),
+            sector: [0u8; SECTOR_SIZE],
+            sector_base: 0,
+            slots: 0,
+            slot: 0,
+            lfn: LfnCollector::new(),
+            done: false,
+        }
+    }
+
+    /// Première entrée dont le nom court ou long correspond à `name` (sans casse).
+    ///
+    /// Le parcours s’arrête dès qu’elle est trouvée. Les noms sont comparés sur les
+    /// octets bruts et les caractères UCS-2 collectés : seule l’entrée trouvée est
+    /// décodée en [`DirEntry`] (aucune allocation pour les autres).
+    pub(crate) fn find_name(mut self, name: &str) -> Result<Option<LocatedEntry>, FatError> {
+        while let Some((offset, raw)) = self.next_short()? {
+            if short_name_matches(&raw, name) || self.lfn.matches(&raw, name) {
+                return Ok(self.located(offset, raw));
+            }
+            self.lfn.reset();
+        }
+        Ok(None)
+    }
+
+    /// Passe à la zone suivante. Retour : `false` s’il n’y en a plus.
+    fn next_zone(&mut self) -> Result<bool, FatError> {
+        let zone = match &mut self.zones {
+            Zones::Fixed(zone) => zone.take(),
+            Zones::List(zones) => zones.next(),
+            Zones::Chain {
+                start,
+                current,
+                guard,
+            } => {
+                let cluster = match *current {
+                    None if *start > self.fs.max_cluster_number()? => {
+                        return Err(FatError::CorruptChain)
+                    }
+                    None => *start,
+                    Some(cl) => match self.fs.next_cluster(cl)? {
+                        Some(next) => {
+                            guard.step(next)?;
+                            next
+                        }
+                        None => return Ok(false),
+                    },
+                };
+                *current = Some(cluster);
+                Some((self.fs.cluster_to_offset(cluster)?, self.fs.cluster_size()))
+            }
+        };
+
+        match zone {
+            Some(zone) => {
+                self.zone = zone;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Charge le secteur suivant du répertoire. Retour : `false` en fin de répertoire.
+    fn next_sector(&mut self) -> Result<bool, FatError> {
+        while self.zone.1 == 0 {
+            if !self.next_zone()? {
+                return Ok(false);
+            }
+        }
+
+        let (base, left) = self.zone;
+        let len = core::cmp::min(SECTOR_SIZE, left);
+        self.fs.read_bytes(base, &mut self.sector[..len])?;
+        self.zone = (base + len, left - len);
+        self.sector_base = base;
+        self.slots = len / 32;
+        self.slot = 0;
+        Ok(true)
+    }
+
+    /// Entrée suivante, ou `None` au marqueur de fin.
+    fn next_entry(&mut self) -> Result<Option<LocatedEntry>, FatError> {
+        while let Some((offset, raw)) = self.next_short()? {
+            if let Some(located) = self.located(offset, raw) {
+                return Ok(Some(located));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Entrée courte suivante (offset et octets bruts), ou `None` au marqueur de fin.
+    ///
+    /// Les entrées LFN qui la précèdent restent dans `self.lfn`, pour `located`.
+    /// Les entrées supprimées et les Volume ID sont sautées.
+    fn next_short(&mut self) -> Result<Option<(usize, [u8; 32])>, FatError> {
+        loop {
+            if self.slot == self.slots && !self.next_sector()? {
+                return Ok(None);
+            }
+
+            let offset = self.sector_base + self.slot * 32;
+            let chunk = &self.sector[self.slot * 32..(self.slot + 1) * 32];
+            self.slot += 1;
+
+            // 0x00 = fin de répertoire (à partir de là, tout est libre)
+            if chunk[0] == 0x00 {
+                return Ok(None);
+            }
+
+            // Entrée supprimée : casse une éventuelle séquence LFN en cours
+            if chunk[0] == 0xE5 {
+                self.lfn.reset();
+                continue;
+            }
+
+            if is_lfn_entry(chunk) {
+                self.lfn.push(chunk, offset);
+                continue;
+            }
+
+            // Volume ID : pas une entrée de fichier, et la séquence LFN ne s’y rattache pas
+            if chunk[11] & 0x08 != 0 {
+                self.lfn.reset();
+                continue;
+            }
+
+            let mut raw = [0u8; 32];
+            raw.copy_from_slice(chunk);
+            return Ok(Some((offset, raw)));
+        }
+    }
+
+    /// Décode l’entrée courte `raw` (lue à `offset`) avec le nom long collecté avant elle.
+    fn located(&mut self, offset: usize, raw: [u8; 32]) -> Option<LocatedEntry> {
+        let long = self.lfn.finish(&raw);
+        let mut entry = DirEntry::parse(&raw)?;
+        let lfn_offsets = match long {
+            Some((name, offsets)) => {
+                entry.long_name = Some(name);
+                offsets
+            }
+            None => Vec::new(),
+        };
+        Some(LocatedEntry {
+            entry,
+            offset,
+            raw,
+            lfn_offsets,
+        })
+    }
+}
+
+impl<D: BlockDevice> Iterator for ReadDir<'_, D> {
+    type Item = Result<LocatedEntry, FatError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_entry().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
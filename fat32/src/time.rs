@@ -0,0 +1,170 @@
+//! Horodatage des entrées de répertoire.
+//!
+//! Une entrée courte porte trois dates : création (date, heure, centièmes),
+//! dernière modification (date, heure) et dernier accès (date seule).
+//! Sur disque, une date tient sur 16 bits (année depuis 1980, mois, jour)
+//! et une heure aussi (heures, minutes, secondes / 2).
+//!
+//! Le crate ne connaît pas l’heure : l’écriture demande la date courante à un
+//! [`TimeSource`]. Par défaut c’est une date fixe ([`FixedTime`]), utilisable en `no_std`;
+//! avec la feature `std`, [`SystemClock`] lit l’horloge système.
+
+use core::fmt;
+
+/// Date et heure au format FAT (de 1980 à 2107, résolution d’une seconde).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FatDateTime {
+    /// Année (1980..=2107).
+    pub year: u16,
+    /// Mois (1..=12).
+    pub month: u8,
+    /// Jour du mois (1..=31).
+    pub day: u8,
+    /// Heure (0..=23).
+    pub hour: u8,
+    /// Minute (0..=59).
+    pub minute: u8,
+    /// Seconde (0..=59).
+    pub second: u8,
+}
+
+impl FatDateTime {
+    /// Le 1er janvier 1980 à minuit, plus petite date représentable.
+    pub const EPOCH: Self = Self {
+        year: 1980,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    /// Construit une date en vérifiant chaque champ.
+    ///
+    /// Retour : `None` si un champ sort de sa plage (année hors 1980..=2107, mois 13...).
+    ///
+    /// Panics : aucune.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = (1980..=2107).contains(&year)
+            && (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        valid.then_some(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Décode les champs bruts d’une entrée : date, heure et centièmes (création seulement).
+    ///
+    /// Une date nulle signifie “non renseignée” : on retourne `None`, comme pour
+    /// un champ incohérent.
+    pub(crate) fn from_fat(date: u16, time: u16, hundredths: u8) -> Option<Self> {
+        if date == 0 {
+            return None;
+        }
+        let extra = if hundredths >= 100 { 1 } else { 0 };
+        Self::new(
+            1980 + (date >> 9),
+            ((date >> 5) & 0x0F) as u8,
+            (date & 0x1F) as u8,
+            (time >> 11) as u8,
+            ((time >> 5) & 0x3F) as u8,
+            (time & 0x1F) as u8 * 2 + extra,
+        )
+    }
+
+    /// Encode en `(date, heure, centièmes)` tels qu’écrits dans l’entrée.
+    ///
+    /// L’heure n’a qu’une résolution de 2 s : la seconde impaire passe dans les centièmes.
+    pub(crate) fn to_fat(self) -> (u16, u16, u8) {
+        let date = (self.year - 1980) << 9 | (self.month as u16) << 5 | self.day as u16;
+        let time = (self.hour as u16) << 11 | (self.minute as u16) << 5 | (self.second / 2) as u16;
+        (date, time, (self.second % 2) * 100)
+    }
+
+    /// Convertit un temps Unix (secondes depuis 1970, UTC), borné à la plage FAT.
+    pub fn from_unix(secs: u64) -> Self {
+        // 1980-01-01 et 2107-12-31 23:59:59 en temps Unix
+        let secs = secs.clamp(315_532_800, 4_354_819_199);
+        let days = secs / 86_400;
+        let rem = secs % 86_400;
+
+        // Jours depuis 1970 -> date civile (algorithme de H. Hinnant)
+        let z = days as i64 + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u16;
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u8,
+            minute: (rem / 60 % 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+}
+
+impl fmt::Display for FatDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Source de la date courante, appelée à chaque écriture d’entrée.
+pub trait TimeSource {
+    /// Date et heure à inscrire maintenant.
+    fn now(&self) -> FatDateTime;
+}
+
+/// Horloge arrêtée : retourne toujours la même date.
+///
+/// C’est la source par défaut (`FixedTime::default()` = [`FatDateTime::EPOCH`]),
+/// pratique en `no_std` sans RTC et pour des images reproductibles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedTime(pub FatDateTime);
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self(FatDateTime::EPOCH)
+    }
+}
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> FatDateTime {
+        self.0
+    }
+}
+
+/// Horloge système (UTC), disponible avec la feature `std`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl TimeSource for SystemClock {
+    fn now(&self) -> FatDateTime {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        FatDateTime::from_unix(secs)
+    }
+}
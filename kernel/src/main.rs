@@ -9,8 +9,11 @@ extern crate alloc;
 use bootloader::entry_point;
 use core::panic::PanicInfo;
 use core::alloc::{Layout, GlobalAlloc};
+use alloc::collections::BTreeMap;
 use alloc::format;
-use fat32_parser::{Fat32, Fat32Mut};
+use fat32_parser::{
+    format, BlockDevice, CacheConfig, CachedDevice, Fat32, Fat32Mut, FatError, FormatOptions, SECTOR_SIZE,
+};
 use slaballoc::LockedAlloc;
 
 /// Écrit un octet sur un port d’E/S x86.
@@ -93,62 +96,60 @@ fn vga_write_line(row: usize, s: &str) {
     }
 }
 
-fn build_test_image() -> [u8; 5120] {
-    const SECTOR_SIZE: usize = 512;
-    const NUM_SECTORS: usize = 10;
-    let mut disk = [0u8; SECTOR_SIZE * NUM_SECTORS];
-    {
-        let b = &mut disk[0..SECTOR_SIZE];
-        b[11] = 0x00;
-        b[12] = 0x02;
-        b[13] = 0x01;
-        b[14] = 0x01;
-        b[15] = 0x00;
-        b[16] = 0x01;
-        b[36] = 0x01;
-        b[37] = 0x00;
-        b[38] = 0x00;
-        b[39] = 0x00;
-        b[44] = 0x02;
-        b[45] = 0x00;
-        b[46] = 0x00;
-        b[47] = 0x00;
-    }
-    {
-        let fat_start = SECTOR_SIZE;
-        let fat = &mut disk[fat_start..fat_start + SECTOR_SIZE];
-        let eoc = 0x0F_FF_FF_F8u32.to_le_bytes();
-        fat[2 * 4..2 * 4 + 4].copy_from_slice(&eoc);
-        fat[3 * 4..3 * 4 + 4].copy_from_slice(&eoc);
-        fat[4 * 4..4 * 4 + 4].copy_from_slice(&eoc);
+/// Disque RAM “creux” : seuls les secteurs non nuls occupent le tas,
+/// les autres se lisent comme des zéros.
+///
+/// Un volume FAT32 valide fait au moins 65525 clusters (~33 Mio),
+/// ce qui ne tient pas dans le tas de 128 Kio du kernel.
+struct SparseDisk {
+    sectors: BTreeMap<u64, [u8; SECTOR_SIZE]>,
+    count: u64,
+}
+
+impl BlockDevice for SparseDisk {
+    fn sector_count(&self) -> u64 {
+        self.count
     }
-    {
-        let root_off = 2 * SECTOR_SIZE;
-        let dir = &mut disk[root_off..root_off + SECTOR_SIZE];
-        let mut hello = [0u8; 32];
-        hello[0..8].copy_from_slice(b"HELLO   ");
-        hello[8..11].copy_from_slice(b"TXT");
-        hello[11] = 0x20;
-        hello[26] = 0x03;
-        hello[27] = 0x00;
-        hello[28] = 5;
-        dir[0..32].copy_from_slice(&hello);
-        let mut subdir = [0u8; 32];
-        subdir[0..8].copy_from_slice(b"DIR     ");
-        subdir[8..11].copy_from_slice(b"   ");
-        subdir[11] = 0x10;
-        subdir[26] = 0x04;
-        subdir[27] = 0x00;
-        dir[32..64].copy_from_slice(&subdir);
-        dir[64] = 0x00;
+
+    fn read_sectors(&self, start: u64, buf: &mut [u8]) -> Result<(), FatError> {
+        for (i, chunk) in buf.chunks_mut(SECTOR_SIZE).enumerate() {
+            let lba = start + i as u64;
+            if lba >= self.count { return Err(FatError::OutOfBounds); }
+            match self.sectors.get(&lba) {
+                Some(data) => chunk.copy_from_slice(&data[..chunk.len()]),
+                None => chunk.fill(0),
+            }
+        }
+        Ok(())
     }
-    {
-        let off = 3 * SECTOR_SIZE;
-        disk[off..off + 5].copy_from_slice(b"HELLO");
+
+    fn write_sectors(&mut self, start: u64, buf: &[u8]) -> Result<(), FatError> {
+        for (i, chunk) in buf.chunks(SECTOR_SIZE).enumerate() {
+            let lba = start + i as u64;
+            if lba >= self.count { return Err(FatError::OutOfBounds); }
+            if chunk.iter().all(|&b| b == 0) {
+                self.sectors.remove(&lba);
+            } else {
+                let mut data = [0u8; SECTOR_SIZE];
+                data[..chunk.len()].copy_from_slice(chunk);
+                self.sectors.insert(lba, data);
+            }
+        }
+        Ok(())
     }
+}
+
+/// Volume FAT32 de 34 Mio formaté par la bibliothèque (juste au-dessus des 65525 clusters
+/// minimum), avec HELLO.TXT et le répertoire DIR dans la racine.
+fn build_test_image() -> SparseDisk {
+    let mut disk = SparseDisk { sectors: BTreeMap::new(), count: 34 * 1024 * 1024 / SECTOR_SIZE as u64 };
+    let options = FormatOptions { label: "THE HEAP".into(), ..FormatOptions::default() };
+    format(&mut disk, &options).unwrap();
     {
-        let off = 4 * SECTOR_SIZE;
-        disk[off] = 0x00;
+        let mut rw = Fat32Mut::from_device(&mut disk).unwrap();
+        rw.write_file_by_path("/HELLO.TXT", b"HELLO").unwrap();
+        rw.create_dir("/DIR").unwrap();
+        rw.unmount().unwrap();
     }
     disk
 }
@@ -162,7 +163,7 @@ fn demo() {
     serial_write_str("The Heap - kernel");
     vga_write_line(0, "The Heap - kernel");
     let mut disk = build_test_image();
-    let ro = Fat32::new(&disk).unwrap();
+    let ro = Fat32::from_device(&disk).unwrap();
     let root = ro.list_root().unwrap();
     let mut names = alloc::vec::Vec::new();
     for e in root { names.push(e.name); }
@@ -173,10 +174,15 @@ fn demo() {
     serial_write_str(hello_s);
     vga_write_line(2, hello_s);
     {
-        let mut rw = Fat32Mut::new(&mut disk).unwrap();
+        // Cache de 12 Kio : doit tenir dans le tas de 128 Kio
+        let cache = CacheConfig { fat_sectors: 8, data_sectors: 16 };
+        let mut rw = Fat32Mut::from_device(CachedDevice::new(&mut disk, cache)).unwrap();
         rw.write_file_by_path("/NEW.TXT", b"NEW!").unwrap();
+        rw.write_file_by_path("/LOG.TXT", b"boot\n").unwrap();
+        rw.append_file("/LOG.TXT", b"fat32 ok\n").unwrap();
+        rw.unmount().unwrap();
     }
-    let ro2 = Fat32::new(&disk).unwrap();
+    let ro2 = Fat32::from_device(&disk).unwrap();
     let root2 = ro2.list_root().unwrap();
     let mut names2 = alloc::vec::Vec::new();
     for e in root2 { names2.push(e.name); }